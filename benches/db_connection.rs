@@ -0,0 +1,29 @@
+#![feature(test)]
+
+extern crate focusvent;
+extern crate diesel;
+extern crate test;
+
+use diesel::RunQueryDsl;
+use diesel::QueryDsl;
+use test::Bencher;
+use focusvent::models::db_connection::*;
+use focusvent::schema::products::dsl::*;
+
+#[bench]
+fn fresh_connection_per_query(bencher: &mut Bencher) {
+    bencher.iter(|| {
+        let connection = establish_connection();
+        products.count().get_result::<i64>(&connection).unwrap()
+    });
+}
+
+#[bench]
+fn pooled_connection_per_query(bencher: &mut Bencher) {
+    let pool = init_pool();
+
+    bencher.iter(|| {
+        let connection = pool.get().unwrap();
+        products.count().get_result::<i64>(&connection).unwrap()
+    });
+}
@@ -0,0 +1,20 @@
+-- Your SQL goes here
+
+CREATE TYPE stock_document AS ENUM ('initial',
+                                    'sale',
+                                    'sale_cancellation');
+
+CREATE TABLE stock_movements(
+    id SERIAL PRIMARY KEY,
+    product_id INTEGER NOT NULL REFERENCES products(id),
+    amount DOUBLE PRECISION NOT NULL,
+    document stock_document NOT NULL,
+    document_id INTEGER NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX stock_movements_product_id_idx ON stock_movements (product_id);
+CREATE INDEX stock_movements_document_idx ON stock_movements (document, document_id);
+
+INSERT INTO stock_movements (product_id, amount, document)
+SELECT id, stock, 'initial' FROM products WHERE stock IS NOT NULL AND stock <> 0;
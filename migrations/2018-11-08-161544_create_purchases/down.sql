@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+DELETE FROM stock_movements WHERE document IN ('purchase', 'purchase_cancellation');
+DROP TABLE purchase_products;
+DROP TABLE purchases;
+DROP TYPE purchase_status;
@@ -0,0 +1,39 @@
+-- Your SQL goes here
+
+CREATE TYPE purchase_status AS ENUM ('draft',
+                                     'received',
+                                     'cancelled');
+
+CREATE TABLE purchases(
+    id SERIAL PRIMARY KEY,
+    supplier_id INTEGER NOT NULL REFERENCES suppliers(id),
+    purchase_date DATE NOT NULL,
+    sub_total INTEGER NOT NULL,
+    sub_total_without_discount INTEGER NOT NULL,
+    discount_calculated INTEGER NOT NULL,
+    taxes_calculated INTEGER NOT NULL,
+    total INTEGER NOT NULL,
+    observation TEXT,
+    currency_id INTEGER NOT NULL REFERENCES currencies(id),
+    status purchase_status NOT NULL DEFAULT 'draft',
+    update_costs BOOLEAN NOT NULL DEFAULT false
+);
+
+CREATE TABLE purchase_products(
+    id SERIAL PRIMARY KEY,
+    purchase_id INTEGER NOT NULL REFERENCES purchases(id),
+    product_id INTEGER NOT NULL REFERENCES products(id),
+    tax INTEGER NOT NULL,
+    amount FLOAT NOT NULL,
+    price INTEGER NOT NULL,
+    discount INTEGER NOT NULL,
+    subtotal INTEGER NOT NULL,
+    sub_total_without_discount INTEGER NOT NULL,
+    discount_calculated INTEGER NOT NULL,
+    taxes_calculated INTEGER NOT NULL,
+    total INTEGER NOT NULL,
+    observation TEXT
+);
+
+ALTER TYPE stock_document ADD VALUE IF NOT EXISTS 'purchase';
+ALTER TYPE stock_document ADD VALUE IF NOT EXISTS 'purchase_cancellation';
@@ -0,0 +1,19 @@
+-- Your SQL goes here
+
+CREATE TYPE payment_method AS ENUM ('cash',
+                                    'card',
+                                    'transfer',
+                                    'check',
+                                    'other');
+
+CREATE TABLE payments(
+    id SERIAL PRIMARY KEY,
+    sale_id INTEGER NOT NULL REFERENCES sales(id),
+    payment_date DATE NOT NULL,
+    amount INTEGER NOT NULL,
+    currency_id INTEGER NOT NULL REFERENCES currencies(id),
+    payment_method payment_method NOT NULL DEFAULT 'cash',
+    observation TEXT
+);
+
+CREATE INDEX payments_sale_id_idx ON payments (sale_id);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX sales_status_expiring_date_idx;
+DROP TABLE overdue_sweeps;
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+
+CREATE TABLE overdue_sweeps(
+    id SERIAL PRIMARY KEY,
+    reference_date DATE NOT NULL,
+    sale_ids INTEGER[] NOT NULL,
+    swept_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX sales_status_expiring_date_idx ON sales (status, expiring_date);
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+
+CREATE TABLE sale_status_history(
+    id SERIAL PRIMARY KEY,
+    sale_id INTEGER NOT NULL REFERENCES sales(id),
+    from_status sale_status NOT NULL,
+    to_status sale_status NOT NULL,
+    changed_by VARCHAR NOT NULL,
+    changed_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX sale_status_history_sale_id_idx ON sale_status_history (sale_id);
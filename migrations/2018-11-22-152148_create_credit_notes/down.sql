@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+DELETE FROM stock_movements WHERE document = 'credit_note';
+DROP TABLE credit_note_products;
+DROP TABLE credit_notes;
@@ -0,0 +1,33 @@
+-- Your SQL goes here
+
+CREATE TABLE credit_notes(
+    id SERIAL PRIMARY KEY,
+    sale_id INTEGER NOT NULL REFERENCES sales(id),
+    credit_note_date DATE NOT NULL,
+    sub_total INTEGER NOT NULL,
+    sub_total_without_discount INTEGER NOT NULL,
+    discount_calculated INTEGER NOT NULL,
+    taxes_calculated INTEGER NOT NULL,
+    total INTEGER NOT NULL,
+    observation TEXT
+);
+
+CREATE INDEX credit_notes_sale_id_idx ON credit_notes (sale_id);
+
+CREATE TABLE credit_note_products(
+    id SERIAL PRIMARY KEY,
+    credit_note_id INTEGER NOT NULL REFERENCES credit_notes(id),
+    sale_product_id INTEGER NOT NULL REFERENCES sale_products(id),
+    product_id INTEGER NOT NULL REFERENCES products(id),
+    amount FLOAT NOT NULL,
+    subtotal INTEGER NOT NULL,
+    sub_total_without_discount INTEGER NOT NULL,
+    discount_calculated INTEGER NOT NULL,
+    taxes_calculated INTEGER NOT NULL,
+    total INTEGER NOT NULL,
+    observation TEXT
+);
+
+CREATE INDEX credit_note_products_sale_product_id_idx ON credit_note_products (sale_product_id);
+
+ALTER TYPE stock_document ADD VALUE IF NOT EXISTS 'credit_note';
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX sales_number_idx;
+ALTER TABLE sales DROP COLUMN number;
+ALTER TABLE sales DROP COLUMN series_id;
+DROP TABLE numbering_series;
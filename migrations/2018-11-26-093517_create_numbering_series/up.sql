@@ -0,0 +1,19 @@
+-- Your SQL goes here
+
+CREATE TABLE numbering_series(
+    id SERIAL PRIMARY KEY,
+    name VARCHAR NOT NULL UNIQUE,
+    prefix VARCHAR NOT NULL DEFAULT '',
+    padding INTEGER NOT NULL DEFAULT 8 CHECK (padding >= 0),
+    next_number INTEGER NOT NULL DEFAULT 1 CHECK (next_number > 0),
+    default_series BOOLEAN NOT NULL DEFAULT false
+);
+
+CREATE UNIQUE INDEX numbering_series_default_series_idx ON numbering_series (default_series) WHERE default_series;
+
+INSERT INTO numbering_series (name, default_series) VALUES ('default', true);
+
+ALTER TABLE sales ADD COLUMN series_id INTEGER REFERENCES numbering_series(id);
+ALTER TABLE sales ADD COLUMN number VARCHAR;
+
+CREATE UNIQUE INDEX sales_number_idx ON sales (number);
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE sale_products DROP COLUMN exchange_rate;
+ALTER TABLE sale_products DROP COLUMN price_id;
+ALTER TABLE sales DROP COLUMN exchange_rate;
+ALTER TABLE prices DROP COLUMN currency_id;
+DROP TABLE exchange_rates;
@@ -0,0 +1,17 @@
+-- Your SQL goes here
+
+CREATE TABLE exchange_rates(
+    id SERIAL PRIMARY KEY,
+    from_currency_id INTEGER NOT NULL REFERENCES currencies(id),
+    to_currency_id INTEGER NOT NULL REFERENCES currencies(id),
+    rate FLOAT NOT NULL CHECK (rate > 0),
+    effective_date DATE NOT NULL,
+    UNIQUE (from_currency_id, to_currency_id, effective_date)
+);
+
+ALTER TABLE prices ADD COLUMN currency_id INTEGER REFERENCES currencies(id);
+
+ALTER TABLE sales ADD COLUMN exchange_rate FLOAT NOT NULL DEFAULT 1.0;
+
+ALTER TABLE sale_products ADD COLUMN price_id INTEGER REFERENCES prices(id);
+ALTER TABLE sale_products ADD COLUMN exchange_rate FLOAT NOT NULL DEFAULT 1.0;
@@ -0,0 +1,58 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE sales
+    ALTER COLUMN sub_total TYPE INTEGER,
+    ALTER COLUMN sub_total_without_discount TYPE INTEGER,
+    ALTER COLUMN discount_calculated TYPE INTEGER,
+    ALTER COLUMN taxes_calculated TYPE INTEGER,
+    ALTER COLUMN total TYPE INTEGER;
+
+ALTER TABLE sale_products
+    ALTER COLUMN tax TYPE INTEGER,
+    ALTER COLUMN price TYPE INTEGER,
+    ALTER COLUMN discount TYPE INTEGER,
+    ALTER COLUMN subtotal TYPE INTEGER,
+    ALTER COLUMN sub_total_without_discount TYPE INTEGER,
+    ALTER COLUMN discount_calculated TYPE INTEGER,
+    ALTER COLUMN taxes_calculated TYPE INTEGER,
+    ALTER COLUMN total TYPE INTEGER;
+
+ALTER TABLE purchases
+    ALTER COLUMN sub_total TYPE INTEGER,
+    ALTER COLUMN sub_total_without_discount TYPE INTEGER,
+    ALTER COLUMN discount_calculated TYPE INTEGER,
+    ALTER COLUMN taxes_calculated TYPE INTEGER,
+    ALTER COLUMN total TYPE INTEGER;
+
+ALTER TABLE purchase_products
+    ALTER COLUMN tax TYPE INTEGER,
+    ALTER COLUMN price TYPE INTEGER,
+    ALTER COLUMN discount TYPE INTEGER,
+    ALTER COLUMN subtotal TYPE INTEGER,
+    ALTER COLUMN sub_total_without_discount TYPE INTEGER,
+    ALTER COLUMN discount_calculated TYPE INTEGER,
+    ALTER COLUMN taxes_calculated TYPE INTEGER,
+    ALTER COLUMN total TYPE INTEGER;
+
+ALTER TABLE credit_notes
+    ALTER COLUMN sub_total TYPE INTEGER,
+    ALTER COLUMN sub_total_without_discount TYPE INTEGER,
+    ALTER COLUMN discount_calculated TYPE INTEGER,
+    ALTER COLUMN taxes_calculated TYPE INTEGER,
+    ALTER COLUMN total TYPE INTEGER;
+
+ALTER TABLE credit_note_products
+    ALTER COLUMN subtotal TYPE INTEGER,
+    ALTER COLUMN sub_total_without_discount TYPE INTEGER,
+    ALTER COLUMN discount_calculated TYPE INTEGER,
+    ALTER COLUMN taxes_calculated TYPE INTEGER,
+    ALTER COLUMN total TYPE INTEGER;
+
+ALTER TABLE payments
+    ALTER COLUMN amount TYPE INTEGER;
+
+ALTER TABLE product_prices
+    ALTER COLUMN price TYPE INTEGER;
+
+ALTER TABLE product_costs
+    ALTER COLUMN cost TYPE INTEGER;
@@ -0,0 +1,58 @@
+-- Your SQL goes here
+
+ALTER TABLE sales
+    ALTER COLUMN sub_total TYPE BIGINT,
+    ALTER COLUMN sub_total_without_discount TYPE BIGINT,
+    ALTER COLUMN discount_calculated TYPE BIGINT,
+    ALTER COLUMN taxes_calculated TYPE BIGINT,
+    ALTER COLUMN total TYPE BIGINT;
+
+ALTER TABLE sale_products
+    ALTER COLUMN tax TYPE BIGINT,
+    ALTER COLUMN price TYPE BIGINT,
+    ALTER COLUMN discount TYPE BIGINT,
+    ALTER COLUMN subtotal TYPE BIGINT,
+    ALTER COLUMN sub_total_without_discount TYPE BIGINT,
+    ALTER COLUMN discount_calculated TYPE BIGINT,
+    ALTER COLUMN taxes_calculated TYPE BIGINT,
+    ALTER COLUMN total TYPE BIGINT;
+
+ALTER TABLE purchases
+    ALTER COLUMN sub_total TYPE BIGINT,
+    ALTER COLUMN sub_total_without_discount TYPE BIGINT,
+    ALTER COLUMN discount_calculated TYPE BIGINT,
+    ALTER COLUMN taxes_calculated TYPE BIGINT,
+    ALTER COLUMN total TYPE BIGINT;
+
+ALTER TABLE purchase_products
+    ALTER COLUMN tax TYPE BIGINT,
+    ALTER COLUMN price TYPE BIGINT,
+    ALTER COLUMN discount TYPE BIGINT,
+    ALTER COLUMN subtotal TYPE BIGINT,
+    ALTER COLUMN sub_total_without_discount TYPE BIGINT,
+    ALTER COLUMN discount_calculated TYPE BIGINT,
+    ALTER COLUMN taxes_calculated TYPE BIGINT,
+    ALTER COLUMN total TYPE BIGINT;
+
+ALTER TABLE credit_notes
+    ALTER COLUMN sub_total TYPE BIGINT,
+    ALTER COLUMN sub_total_without_discount TYPE BIGINT,
+    ALTER COLUMN discount_calculated TYPE BIGINT,
+    ALTER COLUMN taxes_calculated TYPE BIGINT,
+    ALTER COLUMN total TYPE BIGINT;
+
+ALTER TABLE credit_note_products
+    ALTER COLUMN subtotal TYPE BIGINT,
+    ALTER COLUMN sub_total_without_discount TYPE BIGINT,
+    ALTER COLUMN discount_calculated TYPE BIGINT,
+    ALTER COLUMN taxes_calculated TYPE BIGINT,
+    ALTER COLUMN total TYPE BIGINT;
+
+ALTER TABLE payments
+    ALTER COLUMN amount TYPE BIGINT;
+
+ALTER TABLE product_prices
+    ALTER COLUMN price TYPE BIGINT;
+
+ALTER TABLE product_costs
+    ALTER COLUMN cost TYPE BIGINT;
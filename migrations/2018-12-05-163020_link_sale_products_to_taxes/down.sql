@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE sale_taxes;
+ALTER TABLE sale_products ALTER COLUMN tax DROP DEFAULT;
+ALTER TABLE sale_products DROP COLUMN tax_ids;
+DROP TABLE product_taxes;
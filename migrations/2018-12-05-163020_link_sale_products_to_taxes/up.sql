@@ -0,0 +1,21 @@
+-- Your SQL goes here
+
+CREATE TABLE product_taxes(
+    id SERIAL PRIMARY KEY,
+    product_id INTEGER NOT NULL REFERENCES products(id),
+    tax_id INTEGER NOT NULL REFERENCES taxes(id),
+    UNIQUE (product_id, tax_id)
+);
+
+ALTER TABLE sale_products ADD COLUMN tax_ids INTEGER[] NOT NULL DEFAULT '{}';
+ALTER TABLE sale_products ALTER COLUMN tax SET DEFAULT 0;
+
+CREATE TABLE sale_taxes(
+    id SERIAL PRIMARY KEY,
+    sale_id INTEGER NOT NULL REFERENCES sales(id),
+    tax_id INTEGER REFERENCES taxes(id),
+    name VARCHAR NOT NULL,
+    percentage BIGINT NOT NULL,
+    base BIGINT NOT NULL,
+    amount BIGINT NOT NULL
+);
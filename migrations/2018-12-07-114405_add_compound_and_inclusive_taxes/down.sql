@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE sale_products DROP COLUMN tax_included;
+ALTER TABLE sale_products DROP COLUMN discount_amount;
+ALTER TABLE taxes DROP COLUMN compound;
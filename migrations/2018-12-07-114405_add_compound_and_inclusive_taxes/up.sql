@@ -0,0 +1,6 @@
+-- Your SQL goes here
+
+ALTER TABLE taxes ADD COLUMN compound BOOLEAN NOT NULL DEFAULT false;
+
+ALTER TABLE sale_products ADD COLUMN discount_amount BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE sale_products ADD COLUMN tax_included BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE sale_products DROP COLUMN adjustment;
+ALTER TABLE sales DROP COLUMN adjustments_calculated;
+DROP TABLE sale_adjustments;
+DROP TYPE adjustment_kind;
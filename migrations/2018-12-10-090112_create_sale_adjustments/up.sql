@@ -0,0 +1,21 @@
+-- Your SQL goes here
+
+CREATE TYPE adjustment_kind AS ENUM ('discount',
+                                     'surcharge',
+                                     'shipping');
+
+CREATE TABLE sale_adjustments(
+    id SERIAL PRIMARY KEY,
+    sale_id INTEGER NOT NULL REFERENCES sales(id),
+    kind adjustment_kind NOT NULL,
+    percentage BIGINT,
+    amount BIGINT,
+    description VARCHAR,
+    calculated BIGINT NOT NULL DEFAULT 0,
+    CHECK ((percentage IS NULL) <> (amount IS NULL))
+);
+
+CREATE INDEX sale_adjustments_sale_id_idx ON sale_adjustments (sale_id);
+
+ALTER TABLE sales ADD COLUMN adjustments_calculated BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE sale_products ADD COLUMN adjustment BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+
+ALTER TABLE clients ADD COLUMN price_id INTEGER REFERENCES prices(id);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE sale_products DROP COLUMN pricing_rule_id;
+DROP TABLE pricing_rules;
+DROP TYPE pricing_rule_kind;
@@ -0,0 +1,24 @@
+-- Your SQL goes here
+
+CREATE TYPE pricing_rule_kind AS ENUM ('percentage_discount',
+                                       'multi_buy',
+                                       'volume_price');
+
+CREATE TABLE pricing_rules(
+    id SERIAL PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    kind pricing_rule_kind NOT NULL,
+    product_id INTEGER REFERENCES products(id),
+    starts_on DATE,
+    ends_on DATE,
+    percentage BIGINT,
+    buy_amount FLOAT8,
+    pay_amount FLOAT8,
+    min_amount FLOAT8,
+    price BIGINT,
+    active BOOLEAN NOT NULL DEFAULT TRUE
+);
+
+CREATE INDEX pricing_rules_product_id_idx ON pricing_rules (product_id);
+
+ALTER TABLE sale_products ADD COLUMN pricing_rule_id INTEGER REFERENCES pricing_rules(id);
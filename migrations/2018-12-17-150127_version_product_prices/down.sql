@@ -0,0 +1,10 @@
+-- This file should undo anything in `up.sql`
+
+DELETE FROM product_prices WHERE valid_from > CURRENT_DATE OR valid_to <= CURRENT_DATE;
+
+DROP INDEX product_prices_product_price_ids_valid_from;
+CREATE UNIQUE INDEX product_prices_product_price_ids ON product_prices (product_id, price_id);
+
+ALTER TABLE product_prices DROP CONSTRAINT product_prices_valid_range;
+ALTER TABLE product_prices DROP COLUMN valid_to;
+ALTER TABLE product_prices DROP COLUMN valid_from;
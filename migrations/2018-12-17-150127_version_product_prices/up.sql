@@ -0,0 +1,9 @@
+-- Your SQL goes here
+
+ALTER TABLE product_prices ADD COLUMN valid_from DATE NOT NULL DEFAULT '1970-01-01';
+ALTER TABLE product_prices ALTER COLUMN valid_from SET DEFAULT CURRENT_DATE;
+ALTER TABLE product_prices ADD COLUMN valid_to DATE;
+ALTER TABLE product_prices ADD CONSTRAINT product_prices_valid_range CHECK (valid_to IS NULL OR valid_to > valid_from);
+
+DROP INDEX product_prices_product_price_ids;
+CREATE UNIQUE INDEX product_prices_product_price_ids_valid_from ON product_prices (product_id, price_id, valid_from);
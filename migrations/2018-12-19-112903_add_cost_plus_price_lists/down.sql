@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE prices DROP COLUMN markup;
+ALTER TABLE costs DROP COLUMN primary_cost;
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+
+ALTER TABLE costs ADD COLUMN primary_cost BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE prices ADD COLUMN markup BIGINT;
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE pricing_rules DROP COLUMN category_id;
+DROP TABLE product_categories;
+DROP TABLE categories;
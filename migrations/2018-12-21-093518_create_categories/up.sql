@@ -0,0 +1,18 @@
+-- Your SQL goes here
+
+CREATE TABLE categories(
+    id SERIAL PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    parent_id INTEGER REFERENCES categories(id)
+);
+
+CREATE INDEX categories_parent_id_idx ON categories (parent_id);
+
+CREATE TABLE product_categories(
+    id SERIAL PRIMARY KEY,
+    product_id INTEGER NOT NULL REFERENCES products(id),
+    category_id INTEGER NOT NULL REFERENCES categories(id),
+    UNIQUE (product_id, category_id)
+);
+
+ALTER TABLE pricing_rules ADD COLUMN category_id INTEGER REFERENCES categories(id);
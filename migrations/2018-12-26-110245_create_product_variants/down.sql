@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE stock_movements DROP COLUMN variant_id;
+ALTER TABLE sale_products DROP COLUMN variant_id;
+
+DROP INDEX product_prices_product_variant_price_ids_valid_from;
+DELETE FROM product_prices WHERE variant_id IS NOT NULL;
+ALTER TABLE product_prices DROP COLUMN variant_id;
+CREATE UNIQUE INDEX product_prices_product_price_ids_valid_from ON product_prices (product_id, price_id, valid_from);
+
+DROP TABLE product_variants;
@@ -0,0 +1,18 @@
+-- Your SQL goes here
+
+CREATE TABLE product_variants(
+    id SERIAL PRIMARY KEY,
+    product_id INTEGER NOT NULL REFERENCES products(id),
+    code VARCHAR UNIQUE,
+    size VARCHAR,
+    color VARCHAR,
+    UNIQUE (product_id, size, color)
+);
+
+ALTER TABLE product_prices ADD COLUMN variant_id INTEGER REFERENCES product_variants(id);
+DROP INDEX product_prices_product_price_ids_valid_from;
+CREATE UNIQUE INDEX product_prices_product_variant_price_ids_valid_from ON product_prices (product_id, COALESCE(variant_id, 0), price_id, valid_from);
+
+ALTER TABLE sale_products ADD COLUMN variant_id INTEGER REFERENCES product_variants(id);
+ALTER TABLE stock_movements ADD COLUMN variant_id INTEGER REFERENCES product_variants(id);
+CREATE INDEX stock_movements_variant_id_idx ON stock_movements (variant_id);
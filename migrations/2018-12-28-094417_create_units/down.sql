@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE purchase_products DROP COLUMN unit_id;
+ALTER TABLE sale_products DROP COLUMN unit_id;
+ALTER TABLE products DROP COLUMN unit_id;
+DROP TABLE units;
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+
+CREATE TABLE units(
+    id SERIAL PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    symbol VARCHAR NOT NULL,
+    base_unit_id INTEGER REFERENCES units(id),
+    factor DOUBLE PRECISION NOT NULL DEFAULT 1 CHECK (factor > 0)
+);
+
+ALTER TABLE products ADD COLUMN unit_id INTEGER REFERENCES units(id);
+ALTER TABLE sale_products ADD COLUMN unit_id INTEGER REFERENCES units(id);
+ALTER TABLE purchase_products ADD COLUMN unit_id INTEGER REFERENCES units(id);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE product_barcodes;
+DROP INDEX products_code;
@@ -0,0 +1,31 @@
+-- Your SQL goes here
+
+-- Codes were free text until now. Products sharing a code have to be given
+-- their own codes by hand before the unique index can be built.
+DO $$
+DECLARE
+    duplicate_codes TEXT;
+BEGIN
+    SELECT string_agg(code, ', ' ORDER BY code)
+    INTO duplicate_codes
+    FROM (SELECT code
+          FROM products
+          WHERE code IS NOT NULL
+          GROUP BY code
+          HAVING COUNT(*) > 1) AS duplicates;
+
+    IF duplicate_codes IS NOT NULL THEN
+        RAISE EXCEPTION 'Several products share the codes %, give each product its own code before migrating', duplicate_codes;
+    END IF;
+END
+$$;
+
+CREATE UNIQUE INDEX products_code ON products (code);
+
+CREATE TABLE product_barcodes(
+    id SERIAL PRIMARY KEY,
+    product_id INTEGER NOT NULL REFERENCES products(id),
+    code VARCHAR NOT NULL UNIQUE
+);
+
+CREATE INDEX product_barcodes_product_id_idx ON product_barcodes (product_id);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX sales_series_id_number_idx;
+
+CREATE UNIQUE INDEX sales_number_idx ON sales (number);
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+
+UPDATE sales
+SET series_id = (SELECT id FROM numbering_series WHERE default_series)
+WHERE number IS NOT NULL AND series_id IS NULL;
+
+DROP INDEX sales_number_idx;
+
+CREATE UNIQUE INDEX sales_series_id_number_idx ON sales (series_id, number);
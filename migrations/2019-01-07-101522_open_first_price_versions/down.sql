@@ -0,0 +1,9 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX product_prices_product_variant_price_ids_valid_from;
+CREATE UNIQUE INDEX product_prices_product_variant_price_ids_valid_from ON product_prices (product_id, COALESCE(variant_id, 0), price_id, valid_from);
+
+UPDATE product_prices SET valid_from = '1970-01-01' WHERE valid_from IS NULL;
+
+ALTER TABLE product_prices ALTER COLUMN valid_from SET DEFAULT CURRENT_DATE;
+ALTER TABLE product_prices ALTER COLUMN valid_from SET NOT NULL;
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+
+ALTER TABLE product_prices ALTER COLUMN valid_from DROP NOT NULL;
+ALTER TABLE product_prices ALTER COLUMN valid_from DROP DEFAULT;
+
+-- Prices set before they were versioned have always been in force.
+UPDATE product_prices SET valid_from = NULL WHERE valid_from = '1970-01-01';
+
+DROP INDEX product_prices_product_variant_price_ids_valid_from;
+CREATE UNIQUE INDEX product_prices_product_variant_price_ids_valid_from ON product_prices (product_id, COALESCE(variant_id, 0), price_id, COALESCE(valid_from, '-infinity'));
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+
+ALTER TABLE purchase_products ADD COLUMN variant_id INTEGER REFERENCES product_variants(id);
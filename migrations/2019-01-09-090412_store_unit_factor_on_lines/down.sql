@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE purchase_products DROP COLUMN unit_factor;
+ALTER TABLE sale_products DROP COLUMN unit_factor;
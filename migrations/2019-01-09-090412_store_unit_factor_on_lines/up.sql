@@ -0,0 +1,20 @@
+-- Your SQL goes here
+
+ALTER TABLE sale_products ADD COLUMN unit_factor DOUBLE PRECISION NOT NULL DEFAULT 1;
+ALTER TABLE purchase_products ADD COLUMN unit_factor DOUBLE PRECISION NOT NULL DEFAULT 1;
+
+UPDATE sale_products
+SET unit_factor = (CASE WHEN units.base_unit_id IS NULL THEN 1 ELSE units.factor END)
+                / (CASE WHEN base_units.base_unit_id IS NULL THEN 1 ELSE base_units.factor END)
+FROM products, units, units AS base_units
+WHERE products.id = sale_products.product_id
+  AND units.id = sale_products.unit_id
+  AND base_units.id = products.unit_id;
+
+UPDATE purchase_products
+SET unit_factor = (CASE WHEN units.base_unit_id IS NULL THEN 1 ELSE units.factor END)
+                / (CASE WHEN base_units.base_unit_id IS NULL THEN 1 ELSE base_units.factor END)
+FROM products, units, units AS base_units
+WHERE products.id = purchase_products.product_id
+  AND units.id = purchase_products.unit_id
+  AND base_units.id = products.unit_id;
@@ -0,0 +1,19 @@
+extern crate focusvent;
+
+use std::process;
+use focusvent::models::clock::SystemClock;
+use focusvent::models::db_connection::establish_connection;
+use focusvent::models::overdue_sweep::OverdueSweep;
+
+fn main() {
+    match OverdueSweep::run(&establish_connection(), &SystemClock) {
+        Ok(sweep) => {
+            println!("Overdue sweep for {}: {} sale(s) marked as overdue {:?}",
+                     sweep.reference_date, sweep.sale_ids.len(), sweep.sale_ids);
+        },
+        Err(error) => {
+            eprintln!("Overdue sweep failed: {}", error);
+            process::exit(1);
+        }
+    }
+}
@@ -2,6 +2,8 @@ use std;
 use rocket::http::RawStr;
 use rocket::request::FromFormValue;
 use std::ops::Deref;
+use rocket::Outcome;
+use rocket::request::{ self, FromRequest, Request };
 
 #[derive(Debug)]
 pub struct Search<S>(pub S);
@@ -33,3 +35,16 @@ impl<S> Deref for Search<S> {
         &self.0
     }
 }
+
+/// Who performs a request, taken from the `X-User` header.
+#[derive(Debug)]
+pub struct Actor(pub String);
+
+impl<'a, 'r> FromRequest<'a, 'r> for Actor {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Actor, ()> {
+        let name = request.headers().get_one("X-User").unwrap_or("anonymous");
+        Outcome::Success(Actor(name.to_string()))
+    }
+}
@@ -64,14 +64,25 @@ macro_rules! basic_handler_actions {
                 }
                 obj
             };
-            let responder = index(rocket_param_params);
+            #[allow(non_snake_case, unreachable_patterns)]
+            let rocket_param_conn: crate::models::db_connection::DbConn =
+                match ::rocket::request::FromRequest::from_request(__req) {
+                    ::rocket::Outcome::Success(d) => d,
+                    ::rocket::Outcome::Forward(_) => return ::rocket::Outcome::Forward(__data),
+                    ::rocket::Outcome::Failure((code, _)) => {
+                        return ::rocket::Outcome::Failure(code);
+                    }
+                };
+            let responder = index(rocket_param_params, rocket_param_conn);
             ::rocket::handler::Outcome::from(__req, responder)
         }
 
         pub fn index(
             params: GetTransactionParams<$search_model>,
+            conn: crate::models::db_connection::DbConn,
         ) -> Result<Json<Vec<$model>>, status::Custom<String>> {
-            $model::list(params.limit.unwrap_or(10),
+            $model::list(&conn,
+                         params.limit.unwrap_or(10),
                          params.offset.unwrap_or(0),
                          params.search)
                 .map(|records| Json(records))
@@ -146,12 +157,21 @@ macro_rules! basic_handler_actions {
                     return ::rocket::Outcome::Forward(__data);
                 }
             };
-            let responder = show(rocket_param_id);
+            #[allow(non_snake_case, unreachable_patterns)]
+            let rocket_param_conn: crate::models::db_connection::DbConn =
+                match ::rocket::request::FromRequest::from_request(__req) {
+                    ::rocket::Outcome::Success(d) => d,
+                    ::rocket::Outcome::Forward(_) => return ::rocket::Outcome::Forward(__data),
+                    ::rocket::Outcome::Failure((code, _)) => {
+                        return ::rocket::Outcome::Failure(code);
+                    }
+                };
+            let responder = show(rocket_param_id, rocket_param_conn);
             ::rocket::handler::Outcome::from(__req, responder)
         }
 
-        pub fn show(id: i32) -> Result<Json<$model>, status::Custom<String>> {
-            $model::show(id)
+        pub fn show(id: i32, conn: crate::models::db_connection::DbConn) -> Result<Json<$model>, status::Custom<String>> {
+            $model::show(&conn, id)
                 .map(|record| Json(record))
                 .map_err(|error| status::Custom(Status::NotFound, error.to_string()))
         }
@@ -182,6 +202,15 @@ macro_rules! basic_handler_actions {
             __data: ::rocket::Data,
         ) -> ::rocket::handler::Outcome<'_b> {
             #[allow(non_snake_case, unreachable_patterns)]
+            let rocket_param_conn: crate::models::db_connection::DbConn =
+                match ::rocket::request::FromRequest::from_request(__req) {
+                    ::rocket::Outcome::Success(d) => d,
+                    ::rocket::Outcome::Forward(_) => return ::rocket::Outcome::Forward(__data),
+                    ::rocket::Outcome::Failure((code, _)) => {
+                        return ::rocket::Outcome::Failure(code);
+                    }
+                };
+            #[allow(non_snake_case, unreachable_patterns)]
             let rocket_param_record: $new_model =
                 match ::rocket::data::FromData::from_data(__req, __data) {
                     ::rocket::Outcome::Success(d) => d,
@@ -190,12 +219,12 @@ macro_rules! basic_handler_actions {
                         return ::rocket::Outcome::Failure(code);
                     }
                 };
-            let responder = create(rocket_param_record);
+            let responder = create(rocket_param_record, rocket_param_conn);
             ::rocket::handler::Outcome::from(__req, responder)
         }
 
-        pub fn create(new: $new_model) -> Result<Json<$model>, status::Custom<String>> {
-            $model::create(new) 
+        pub fn create(new: $new_model, conn: crate::models::db_connection::DbConn) -> Result<Json<$model>, status::Custom<String>> {
+            $model::create(&conn, new)
                 .map(|record| Json(record))
                 .map_err(|error| status::Custom(Status::InternalServerError, error.to_string()))
         }
@@ -269,6 +298,15 @@ macro_rules! basic_handler_actions {
                 }
             };
             #[allow(non_snake_case, unreachable_patterns)]
+            let rocket_param_conn: crate::models::db_connection::DbConn =
+                match ::rocket::request::FromRequest::from_request(__req) {
+                    ::rocket::Outcome::Success(d) => d,
+                    ::rocket::Outcome::Forward(_) => return ::rocket::Outcome::Forward(__data),
+                    ::rocket::Outcome::Failure((code, _)) => {
+                        return ::rocket::Outcome::Failure(code);
+                    }
+                };
+            #[allow(non_snake_case, unreachable_patterns)]
             let rocket_param_record: $model =
                 match ::rocket::data::FromData::from_data(__req, __data) {
                     ::rocket::Outcome::Success(d) => d,
@@ -277,12 +315,12 @@ macro_rules! basic_handler_actions {
                         return ::rocket::Outcome::Failure(code);
                     }
                 };
-            let responder = update(rocket_param_id, rocket_param_record);
+            let responder = update(rocket_param_id, rocket_param_record, rocket_param_conn);
             ::rocket::handler::Outcome::from(__req, responder)
         }
 
-        pub fn update(id: i32, edit: $model) -> Result<Json<$model>, status::Custom<String>> {
-            $model::update(id, edit)
+        pub fn update(id: i32, edit: $model, conn: crate::models::db_connection::DbConn) -> Result<Json<$model>, status::Custom<String>> {
+            $model::update(&conn, id, edit)
                 .map(|record| Json(record))
                 .map_err(|error| status::Custom(Status::InternalServerError, error.to_string()))
         }
@@ -355,12 +393,21 @@ macro_rules! basic_handler_actions {
                     return ::rocket::Outcome::Forward(__data);
                 }
             };
-            let responder = delete(rocket_param_id);
+            #[allow(non_snake_case, unreachable_patterns)]
+            let rocket_param_conn: crate::models::db_connection::DbConn =
+                match ::rocket::request::FromRequest::from_request(__req) {
+                    ::rocket::Outcome::Success(d) => d,
+                    ::rocket::Outcome::Forward(_) => return ::rocket::Outcome::Forward(__data),
+                    ::rocket::Outcome::Failure((code, _)) => {
+                        return ::rocket::Outcome::Failure(code);
+                    }
+                };
+            let responder = delete(rocket_param_id, rocket_param_conn);
             ::rocket::handler::Outcome::from(__req, responder)
         }
 
-        pub fn delete(id: i32) -> Result<Json<usize>, status::Custom<String>> {
-            $model::delete(id)
+        pub fn delete(id: i32, conn: crate::models::db_connection::DbConn) -> Result<Json<usize>, status::Custom<String>> {
+            $model::delete(&conn, id)
                 .map(|success| Json(success))
                 .map_err(|error| status::Custom(Status::InternalServerError, error.to_string()))
         }
@@ -0,0 +1,11 @@
+use crate::handlers::base::GetTransactionParams;
+use rocket::response::status;
+use rocket::http::Status;
+use rocket_contrib::Json;
+use crate::models::category::Category;
+use crate::models::category::SearchCategory;
+use crate::models::category::NewCategory;
+use crate::models::category::BasicModelActions;
+use crate::basic_handler_actions;
+
+basic_handler_actions!("categories", Category, NewCategory, SearchCategory);
@@ -1,4 +1,5 @@
 use crate::handlers::base::GetTransactionParams;
+use crate::models::db_connection::DbConn;
 use rocket::response::status;
 use rocket::http::Status;
 use rocket_contrib::Json;
@@ -6,6 +7,14 @@ use crate::models::client::Client;
 use crate::models::client::SearchClient;
 use crate::models::client::NewClient;
 use crate::models::client::BasicModelActions;
+use crate::models::client_balance::ClientBalance;
 use crate::basic_handler_actions;
 
 basic_handler_actions!("clients", Client, NewClient, SearchClient);
+
+#[get("/clients/<id>/balance", format="application/json")]
+pub fn balance(id: i32, conn: DbConn) -> Result<Json<ClientBalance>, status::Custom<String>> {
+    ClientBalance::show(&conn, id)
+        .map(|client_balance| Json(client_balance))
+        .map_err(|error| status::Custom(Status::NotFound, error.to_string()))
+}
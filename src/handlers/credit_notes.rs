@@ -0,0 +1,43 @@
+use crate::handlers::base::Actor;
+use crate::models::db_connection::DbConn;
+use rocket::response::status;
+use rocket::http::Status;
+use rocket_contrib::Json;
+use crate::models::credit_note::CreditNote;
+use crate::models::credit_note::CreditNoteError;
+use crate::models::credit_note::FullCreditNote;
+use crate::models::credit_note::FullNewCreditNote;
+
+#[get("/sales/<id>/credit_notes", format="application/json")]
+pub fn index(id: i32, conn: DbConn) -> Result<Json<Vec<CreditNote>>, status::Custom<String>> {
+    CreditNote::list(&conn, id)
+        .map(|credit_notes| Json(credit_notes))
+        .map_err(|error| status::Custom(Status::NotFound, error.to_string()))
+}
+
+#[get("/credit_notes/<id>", format="application/json")]
+pub fn show(id: i32, conn: DbConn) -> Result<Json<FullCreditNote>, status::Custom<String>> {
+    CreditNote::show(&conn, id)
+        .map(|credit_note| Json(credit_note))
+        .map_err(|error| status::Custom(Status::NotFound, error.to_string()))
+}
+
+#[post("/sales/<id>/credit_notes", format="application/json", data="<credit_note>")]
+pub fn create(id: i32, credit_note: FullNewCreditNote, actor: Actor, conn: DbConn) -> Result<Json<CreditNote>, status::Custom<Json<CreditNoteError>>> {
+    CreditNote::create(&conn, id, credit_note, &actor.0)
+        .map(|credit_note| Json(credit_note))
+        .map_err(credit_note_error)
+}
+
+fn credit_note_error(error: CreditNoteError) -> status::Custom<Json<CreditNoteError>> {
+    let status = match error {
+        CreditNoteError::SaleNotFound { .. } => Status::NotFound,
+        CreditNoteError::NotCreditable { .. } => Status::Conflict,
+        CreditNoteError::UnknownSaleProduct { .. } => Status::UnprocessableEntity,
+        CreditNoteError::InvalidAmount { .. } => Status::UnprocessableEntity,
+        CreditNoteError::AmountExceeded { .. } => Status::UnprocessableEntity,
+        CreditNoteError::Overflow => Status::UnprocessableEntity,
+        CreditNoteError::Database { .. } => Status::InternalServerError,
+    };
+    status::Custom(status, Json(error))
+}
@@ -0,0 +1,11 @@
+use crate::handlers::base::GetTransactionParams;
+use rocket::response::status;
+use rocket::http::Status;
+use rocket_contrib::Json;
+use crate::models::exchange_rate::ExchangeRate;
+use crate::models::exchange_rate::SearchExchangeRate;
+use crate::models::exchange_rate::NewExchangeRate;
+use crate::models::exchange_rate::BasicModelActions;
+use crate::basic_handler_actions;
+
+basic_handler_actions!("exchange_rates", ExchangeRate, NewExchangeRate, SearchExchangeRate);
@@ -6,6 +6,15 @@ pub mod clients;
 pub mod currencies;
 pub mod taxes;
 pub mod sales;
+pub mod purchases;
+pub mod payments;
+pub mod credit_notes;
+pub mod numbering_series;
+pub mod exchange_rates;
+pub mod pricing_rules;
+pub mod categories;
+pub mod product_variants;
+pub mod units;
 pub mod base;
 
 #[macro_use]
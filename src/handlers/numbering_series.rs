@@ -0,0 +1,11 @@
+use crate::handlers::base::GetTransactionParams;
+use rocket::response::status;
+use rocket::http::Status;
+use rocket_contrib::Json;
+use crate::models::numbering_series::NumberingSeries;
+use crate::models::numbering_series::SearchNumberingSeries;
+use crate::models::numbering_series::NewNumberingSeries;
+use crate::models::numbering_series::BasicModelActions;
+use crate::basic_handler_actions;
+
+basic_handler_actions!("numbering_series", NumberingSeries, NewNumberingSeries, SearchNumberingSeries);
@@ -0,0 +1,35 @@
+use crate::handlers::base::Actor;
+use crate::models::db_connection::DbConn;
+use rocket::response::status;
+use rocket::http::Status;
+use rocket_contrib::Json;
+use crate::models::payment::Payment;
+use crate::models::payment::NewPayment;
+use crate::models::payment::PaymentError;
+
+#[get("/sales/<id>/payments", format="application/json")]
+pub fn index(id: i32, conn: DbConn) -> Result<Json<Vec<Payment>>, status::Custom<String>> {
+    Payment::list(&conn, id)
+        .map(|payments| Json(payments))
+        .map_err(|error| status::Custom(Status::NotFound, error.to_string()))
+}
+
+#[post("/sales/<id>/payments", format="application/json", data="<payment>")]
+pub fn create(id: i32, payment: NewPayment, actor: Actor, conn: DbConn) -> Result<Json<Payment>, status::Custom<Json<PaymentError>>> {
+    Payment::create(&conn, id, payment, &actor.0)
+        .map(|payment| Json(payment))
+        .map_err(|error| payment_error(error))
+}
+
+fn payment_error(error: PaymentError) -> status::Custom<Json<PaymentError>> {
+    let status = match error {
+        PaymentError::NotFound { .. } => Status::NotFound,
+        PaymentError::NotPayable { .. } => Status::UnprocessableEntity,
+        PaymentError::InvalidAmount { .. } => Status::UnprocessableEntity,
+        PaymentError::CurrencyMismatch { .. } => Status::UnprocessableEntity,
+        PaymentError::Overpayment { .. } => Status::UnprocessableEntity,
+        PaymentError::Database { .. } => Status::InternalServerError,
+    };
+
+    status::Custom(status, Json(error))
+}
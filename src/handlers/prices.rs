@@ -1,4 +1,5 @@
 use crate::handlers::base::GetTransactionParams;
+use crate::models::db_connection::DbConn;
 use rocket::response::status;
 use rocket::http::Status;
 use rocket_contrib::Json;
@@ -6,6 +7,23 @@ use crate::models::price::Price;
 use crate::models::price::SearchPrice;
 use crate::models::price::NewPrice;
 use crate::models::price::BasicModelActions;
+use crate::models::price_update::PriceUpdate;
+use crate::models::price_update::PriceChange;
+use crate::models::price_update::PriceUpdateError;
 use crate::basic_handler_actions;
 
 basic_handler_actions!("prices", Price, NewPrice, SearchPrice);
+
+#[post("/prices/<id>/bulk_update", format="application/json", data="<price_update>")]
+pub fn bulk_update(id: i32, price_update: PriceUpdate, conn: DbConn) -> Result<Json<Vec<PriceChange>>, status::Custom<Json<PriceUpdateError>>> {
+    price_update.run(&conn, id)
+        .map(|price_changes| Json(price_changes))
+        .map_err(|error| {
+            let status = match error {
+                PriceUpdateError::InvalidChange { .. } => Status::UnprocessableEntity,
+                PriceUpdateError::InvalidPrice { .. } => Status::UnprocessableEntity,
+                PriceUpdateError::Database { .. } => Status::InternalServerError,
+            };
+            status::Custom(status, Json(error))
+        })
+}
@@ -0,0 +1,11 @@
+use crate::handlers::base::GetTransactionParams;
+use rocket::response::status;
+use rocket::http::Status;
+use rocket_contrib::Json;
+use crate::models::pricing_rule::PricingRule;
+use crate::models::pricing_rule::SearchPricingRule;
+use crate::models::pricing_rule::NewPricingRule;
+use crate::models::pricing_rule::BasicModelActions;
+use crate::basic_handler_actions;
+
+basic_handler_actions!("pricing_rules", PricingRule, NewPricingRule, SearchPricingRule);
@@ -0,0 +1,49 @@
+use crate::handlers::base::GetTransactionParams;
+use crate::handlers::products::product_error;
+use crate::models::db_connection::DbConn;
+use rocket::response::status;
+use rocket::http::Status;
+use rocket_contrib::Json;
+use crate::models::product::ProductError;
+use crate::models::product_variant::ProductVariant;
+use crate::models::product_variant::SearchProductVariant;
+use crate::models::product_variant::NewProductVariant;
+use crate::models::product_variant::BasicModelActions;
+
+#[get("/product_variants?<params>")]
+pub fn index(params: GetTransactionParams<SearchProductVariant>, conn: DbConn) -> Result<Json<Vec<ProductVariant>>, status::Custom<String>> {
+    ProductVariant::list(&conn,
+                         params.limit.unwrap_or(10),
+                         params.offset.unwrap_or(0),
+                         params.search)
+        .map(|variants| Json(variants))
+        .map_err(|error| status::Custom(Status::NotFound, error.to_string()))
+}
+
+#[get("/product_variants/<id>", format="application/json")]
+pub fn show(id: i32, conn: DbConn) -> Result<Json<ProductVariant>, status::Custom<String>> {
+    ProductVariant::show(&conn, id)
+        .map(|variant| Json(variant))
+        .map_err(|error| status::Custom(Status::NotFound, error.to_string()))
+}
+
+#[post("/product_variants", format="application/json", data="<variant>")]
+pub fn create(variant: NewProductVariant, conn: DbConn) -> Result<Json<ProductVariant>, status::Custom<Json<ProductError>>> {
+    ProductVariant::create(&conn, variant)
+        .map(|variant| Json(variant))
+        .map_err(|error| product_error(error))
+}
+
+#[put("/product_variants/<id>", format="application/json", data="<variant>")]
+pub fn update(id: i32, variant: ProductVariant, conn: DbConn) -> Result<Json<ProductVariant>, status::Custom<Json<ProductError>>> {
+    ProductVariant::update(&conn, id, variant)
+        .map(|variant| Json(variant))
+        .map_err(|error| product_error(error))
+}
+
+#[delete("/product_variants/<id>", format="application/json")]
+pub fn delete(id: i32, conn: DbConn) -> Result<Json<usize>, status::Custom<String>> {
+    ProductVariant::delete(&conn, id)
+        .map(|success| Json(success))
+        .map_err(|error| status::Custom(Status::InternalServerError, error.to_string()))
+}
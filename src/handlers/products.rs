@@ -1,4 +1,5 @@
 use crate::handlers::base::GetTransactionParams;
+use crate::models::db_connection::DbConn;
 use rocket::response::status;
 use rocket::http::Status;
 use rocket_contrib::Json;
@@ -6,10 +7,14 @@ use crate::models::product::FullProduct;
 use crate::models::product::Product;
 use crate::models::product::SearchProduct;
 use crate::models::product::FullNewProduct;
+use crate::models::product::ProductError;
+use crate::models::stock_movement::StockMovement;
+use crate::models::product_price::ProductPrice;
 
 #[get("/products?<params>")]
-pub fn index(params: GetTransactionParams<SearchProduct>) -> Result<Json<Vec<Product>>, status::Custom<String>> {
-    Product::list(params.limit.unwrap_or(10),
+pub fn index(params: GetTransactionParams<SearchProduct>, conn: DbConn) -> Result<Json<Vec<Product>>, status::Custom<String>> {
+    Product::list(&conn,
+                  params.limit.unwrap_or(10),
                   params.offset.unwrap_or(0),
                   params.search) 
         .map(|products| Json(products))
@@ -17,29 +22,69 @@ pub fn index(params: GetTransactionParams<SearchProduct>) -> Result<Json<Vec<Pro
 }
 
 #[get("/products/<id>", format="application/json")]
-pub fn show(id: i32) -> Result<Json<FullProduct>, status::Custom<String>> {
-    Product::show(id)
+pub fn show(id: i32, conn: DbConn) -> Result<Json<FullProduct>, status::Custom<String>> {
+    Product::show(&conn, id)
+        .map(|product| Json(product))
+        .map_err(|error| status::Custom(Status::NotFound, error.to_string()))
+}
+
+#[get("/products/by-code/<code>", format="application/json", rank=2)]
+pub fn by_code(code: String, conn: DbConn) -> Result<Json<FullProduct>, status::Custom<String>> {
+    Product::find_by_code(&conn, &code)
         .map(|product| Json(product))
         .map_err(|error| status::Custom(Status::NotFound, error.to_string()))
 }
 
 #[post("/products", format="application/json", data="<request>")]
-pub fn create(request: FullNewProduct) -> Result<Json<Product>, status::Custom<String>> {
-    Product::create(request)
+pub fn create(request: FullNewProduct, conn: DbConn) -> Result<Json<Product>, status::Custom<Json<ProductError>>> {
+    Product::create(&conn, request)
         .map(|product| Json(product))
-        .map_err(|error| status::Custom(Status::InternalServerError, error.to_string()))
+        .map_err(|error| product_error(error))
 }
 
 #[put("/products/<id>", format="application/json", data="<request>")]
-pub fn update(id: i32, request: FullNewProduct) -> Result<Json<Product>, status::Custom<String>> {
-    Product::update(id, request)
+pub fn update(id: i32, request: FullNewProduct, conn: DbConn) -> Result<Json<Product>, status::Custom<Json<ProductError>>> {
+    Product::update(&conn, id, request)
         .map(|product| Json(product))
-        .map_err(|error| status::Custom(Status::InternalServerError, error.to_string()))
+        .map_err(|error| product_error(error))
 }
 
 #[delete("/products/<id>", format="application/json")]
-pub fn delete(id: i32) -> Result<Json<usize>, status::Custom<String>> {
-    Product::delete(id)
+pub fn delete(id: i32, conn: DbConn) -> Result<Json<usize>, status::Custom<String>> {
+    Product::delete(&conn, id)
         .map(|success| Json(success))
         .map_err(|error| status::Custom(Status::InternalServerError, error.to_string()))
 }
+
+#[get("/products/<id>/stock_movements", format="application/json")]
+pub fn stock_movements(id: i32, conn: DbConn) -> Result<Json<Vec<StockMovement>>, status::Custom<String>> {
+    StockMovement::list(&conn, id)
+        .map(|stock_movements| Json(stock_movements))
+        .map_err(|error| status::Custom(Status::NotFound, error.to_string()))
+}
+
+#[put("/products/<id>/reconcile_stock", format="application/json")]
+pub fn reconcile_stock(id: i32, conn: DbConn) -> Result<Json<Product>, status::Custom<String>> {
+    StockMovement::reconcile(&conn, id)
+        .map(|product| Json(product))
+        .map_err(|error| status::Custom(Status::InternalServerError, error.to_string()))
+}
+
+#[get("/products/<id>/prices/history", format="application/json")]
+pub fn price_history(id: i32, conn: DbConn) -> Result<Json<Vec<ProductPrice>>, status::Custom<String>> {
+    ProductPrice::history(&conn, id)
+        .map(|product_prices| Json(product_prices))
+        .map_err(|error| status::Custom(Status::NotFound, error.to_string()))
+}
+
+pub fn product_error(error: ProductError) -> status::Custom<Json<ProductError>> {
+    let status = match error {
+        ProductError::InvalidBarcode { .. } => Status::UnprocessableEntity,
+        ProductError::DuplicateCode { .. } => Status::Conflict,
+        ProductError::RepeatedCode { .. } => Status::UnprocessableEntity,
+        ProductError::UnitLocked { .. } => Status::Conflict,
+        ProductError::Database { .. } => Status::InternalServerError,
+    };
+
+    status::Custom(status, Json(error))
+}
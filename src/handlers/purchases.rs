@@ -0,0 +1,75 @@
+use crate::handlers::base::GetTransactionParams;
+use crate::models::db_connection::DbConn;
+use rocket::response::status;
+use rocket::http::Status;
+use rocket_contrib::Json;
+use crate::models::purchase::Purchase;
+use crate::models::purchase::FullPurchase;
+use crate::models::purchase::FullNewPurchase;
+use crate::models::purchase::SearchPurchase;
+use crate::models::purchase::PurchaseError;
+use crate::models::purchase_status::PurchaseStatus;
+
+#[get("/purchases?<params>")]
+pub fn index(params: GetTransactionParams<SearchPurchase>, conn: DbConn) -> Result<Json<Vec<Purchase>>, status::Custom<String>> {
+    Purchase::list(&conn,
+                   params.limit.unwrap_or(10),
+                   params.offset.unwrap_or(0),
+                   params.search)
+        .map(|purchases| Json(purchases))
+        .map_err(|error| status::Custom(Status::NotFound, error.to_string()))
+}
+
+#[get("/purchases/<id>", format="application/json")]
+pub fn show(id: i32, conn: DbConn) -> Result<Json<FullPurchase>, status::Custom<String>> {
+    Purchase::show(&conn, id)
+        .map(|purchase| Json(purchase))
+        .map_err(|error| status::Custom(Status::NotFound, error.to_string()))
+}
+
+#[post("/purchases", format="application/json", data="<purchase>")]
+pub fn create(purchase: FullNewPurchase, conn: DbConn) -> Result<Purchase, status::Custom<Json<PurchaseError>>> {
+    Purchase::create(&conn, purchase)
+        .map_err(|error| purchase_error(error))
+}
+
+#[put("/purchases/<id>", format="application/json", data="<purchase>")]
+pub fn update(id: i32, purchase: FullNewPurchase, conn: DbConn) -> Result<Json<Purchase>, status::Custom<Json<PurchaseError>>> {
+    Purchase::update(&conn, id, purchase)
+        .map(|purchase| Json(purchase))
+        .map_err(|error| purchase_error(error))
+}
+
+#[put("/purchases/<id>/receive", format="application/json")]
+pub fn receive(id: i32, conn: DbConn) -> Result<Json<bool>, status::Custom<String>> {
+    PurchaseStatus::to_received(&conn, id)
+        .map(|success| Json(success))
+        .map_err(|error| status::Custom(Status::InternalServerError, error.to_string()))
+}
+
+#[put("/purchases/<id>/cancel", format="application/json")]
+pub fn cancel(id: i32, conn: DbConn) -> Result<Json<bool>, status::Custom<String>> {
+    PurchaseStatus::to_cancelled(&conn, id)
+        .map(|success| Json(success))
+        .map_err(|error| status::Custom(Status::InternalServerError, error.to_string()))
+}
+
+#[delete("/purchases/<id>", format="application/json")]
+pub fn delete(id: i32, conn: DbConn) -> Result<Json<usize>, status::Custom<Json<PurchaseError>>> {
+    Purchase::delete(&conn, id)
+        .map(|success| Json(success))
+        .map_err(|error| purchase_error(error))
+}
+
+fn purchase_error(error: PurchaseError) -> status::Custom<Json<PurchaseError>> {
+    let status = match error {
+        PurchaseError::Locked { .. } => Status::Conflict,
+        PurchaseError::PurchaseNotFound { .. } => Status::NotFound,
+        PurchaseError::UnknownVariant { .. } => Status::UnprocessableEntity,
+        PurchaseError::IncompatibleUnit { .. } => Status::UnprocessableEntity,
+        PurchaseError::Overflow => Status::UnprocessableEntity,
+        PurchaseError::Database { .. } => Status::InternalServerError,
+    };
+
+    status::Custom(status, Json(error))
+}
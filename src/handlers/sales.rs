@@ -1,4 +1,6 @@
 use crate::handlers::base::GetTransactionParams;
+use crate::handlers::base::Actor;
+use crate::models::db_connection::DbConn;
 use rocket::response::status;
 use rocket::http::Status;
 use rocket_contrib::Json;
@@ -6,11 +8,15 @@ use crate::models::sale::Sale;
 use crate::models::sale::FullSale;
 use crate::models::sale::FullNewSale;
 use crate::models::sale::SearchSale;
+use crate::models::sale::SaleError;
 use crate::models::sale_status::SaleStatus;
+use crate::models::sale_status::SaleStatusError;
+use crate::models::sale_status_history::SaleStatusHistory;
 
 #[get("/sales?<params>")]
-pub fn index(params: GetTransactionParams<SearchSale>) -> Result<Json<Vec<Sale>>, status::Custom<String>> {
-    Sale::list(params.limit.unwrap_or(10),
+pub fn index(params: GetTransactionParams<SearchSale>, conn: DbConn) -> Result<Json<Vec<Sale>>, status::Custom<String>> {
+    Sale::list(&conn,
+               params.limit.unwrap_or(10),
                params.offset.unwrap_or(0),
                params.search)
         .map(|sales| Json(sales))
@@ -18,42 +24,85 @@ pub fn index(params: GetTransactionParams<SearchSale>) -> Result<Json<Vec<Sale>>
 }
 
 #[get("/sales/<id>", format="application/json")]
-pub fn show(id: i32) -> Result<Json<FullSale>, status::Custom<String>> {
-    Sale::show(id)
+pub fn show(id: i32, conn: DbConn) -> Result<Json<FullSale>, status::Custom<String>> {
+    Sale::show(&conn, id)
         .map(|sale| Json(sale))
         .map_err(|error| status::Custom(Status::NotFound, error.to_string()))
 }
 
 #[post("/sales", format="application/json", data="<sale>")]
-pub fn create(sale: FullNewSale) -> Result<Sale, status::Custom<String>> {
-    Sale::create(sale)
-        .map_err(|error| status::Custom(Status::InternalServerError, error.to_string()))
+pub fn create(sale: FullNewSale, conn: DbConn) -> Result<Sale, status::Custom<Json<SaleError>>> {
+    Sale::create(&conn, sale)
+        .map_err(|error| sale_error(error))
 }
 
 #[put("/sales/<id>", format="application/json", data="<sale>")]
-pub fn update(id: i32, sale: FullNewSale) -> Result<Json<Sale>, status::Custom<String>> {
-    Sale::update(id, sale)
+pub fn update(id: i32, sale: FullNewSale, conn: DbConn) -> Result<Json<Sale>, status::Custom<Json<SaleError>>> {
+    Sale::update(&conn, id, sale)
         .map(|sale| Json(sale))
-        .map_err(|error| status::Custom(Status::InternalServerError, error.to_string()))
+        .map_err(|error| sale_error(error))
 }
 
 #[put("/sales/<id>/save", format="application/json")]
-pub fn save(id: i32) -> Result<Json<bool>, status::Custom<String>> {
-    SaleStatus::to_saved(id)
-        .map(|success| Json(success))
-        .map_err(|error| status::Custom(Status::InternalServerError, error.to_string()))
+pub fn save(id: i32, actor: Actor, conn: DbConn) -> Result<Json<Sale>, status::Custom<Json<SaleStatusError>>> {
+    SaleStatus::to_saved(&conn, id, &actor.0)
+        .map(|sale| Json(sale))
+        .map_err(|error| status_error(error))
+}
+
+#[put("/sales/<id>/activate", format="application/json")]
+pub fn activate(id: i32, actor: Actor, conn: DbConn) -> Result<Json<Sale>, status::Custom<Json<SaleStatusError>>> {
+    SaleStatus::to_active(&conn, id, &actor.0)
+        .map(|sale| Json(sale))
+        .map_err(|error| status_error(error))
 }
 
 #[put("/sales/<id>/cancel", format="application/json")]
-pub fn cancel(id: i32) -> Result<Json<bool>, status::Custom<String>> {
-    SaleStatus::to_cancelled(id)
-        .map(|success| Json(success))
-        .map_err(|error| status::Custom(Status::InternalServerError, error.to_string()))
+pub fn cancel(id: i32, actor: Actor, conn: DbConn) -> Result<Json<Sale>, status::Custom<Json<SaleStatusError>>> {
+    SaleStatus::to_cancelled(&conn, id, &actor.0)
+        .map(|sale| Json(sale))
+        .map_err(|error| status_error(error))
+}
+
+#[get("/sales/<id>/history", format="application/json")]
+pub fn history(id: i32, conn: DbConn) -> Result<Json<Vec<SaleStatusHistory>>, status::Custom<String>> {
+    SaleStatusHistory::list(&conn, id)
+        .map(|history| Json(history))
+        .map_err(|error| status::Custom(Status::NotFound, error.to_string()))
 }
 
 #[delete("/sales/<id>", format="application/json")]
-pub fn delete(id: i32) -> Result<Json<usize>, status::Custom<String>> {
-    Sale::delete(id)
+pub fn delete(id: i32, conn: DbConn) -> Result<Json<usize>, status::Custom<Json<SaleError>>> {
+    Sale::delete(&conn, id)
         .map(|success| Json(success))
-        .map_err(|error| status::Custom(Status::InternalServerError, error.to_string()))
+        .map_err(|error| sale_error(error))
+}
+
+fn status_error(error: SaleStatusError) -> status::Custom<Json<SaleStatusError>> {
+    let status = match error {
+        SaleStatusError::InvalidTransition { .. } => Status::Conflict,
+        SaleStatusError::SaleNotFound { .. } => Status::NotFound,
+        SaleStatusError::MissingSeries { .. } => Status::UnprocessableEntity,
+        SaleStatusError::Database { .. } => Status::InternalServerError,
+    };
+
+    status::Custom(status, Json(error))
+}
+
+fn sale_error(error: SaleError) -> status::Custom<Json<SaleError>> {
+    let status = match error {
+        SaleError::Locked { .. } => Status::Conflict,
+        SaleError::SaleNotFound { .. } => Status::NotFound,
+        SaleError::MissingExchangeRate { .. } => Status::UnprocessableEntity,
+        SaleError::TaxNotFound { .. } => Status::UnprocessableEntity,
+        SaleError::InvalidAdjustment { .. } => Status::UnprocessableEntity,
+        SaleError::MissingPrice { .. } => Status::UnprocessableEntity,
+        SaleError::UnknownVariant { .. } => Status::UnprocessableEntity,
+        SaleError::DuplicateLine { .. } => Status::UnprocessableEntity,
+        SaleError::IncompatibleUnit { .. } => Status::UnprocessableEntity,
+        SaleError::Overflow => Status::UnprocessableEntity,
+        SaleError::Database { .. } => Status::InternalServerError,
+    };
+
+    status::Custom(status, Json(error))
 }
@@ -0,0 +1,57 @@
+use crate::handlers::base::GetTransactionParams;
+use crate::models::db_connection::DbConn;
+use rocket::response::status;
+use rocket::http::Status;
+use rocket_contrib::Json;
+use crate::models::unit::Unit;
+use crate::models::unit::SearchUnit;
+use crate::models::unit::NewUnit;
+use crate::models::unit::UnitError;
+use crate::models::unit::BasicModelActions;
+
+#[get("/units?<params>")]
+pub fn index(params: GetTransactionParams<SearchUnit>, conn: DbConn) -> Result<Json<Vec<Unit>>, status::Custom<String>> {
+    Unit::list(&conn,
+               params.limit.unwrap_or(10),
+               params.offset.unwrap_or(0),
+               params.search)
+        .map(|units| Json(units))
+        .map_err(|error| status::Custom(Status::NotFound, error.to_string()))
+}
+
+#[get("/units/<id>", format="application/json")]
+pub fn show(id: i32, conn: DbConn) -> Result<Json<Unit>, status::Custom<String>> {
+    Unit::show(&conn, id)
+        .map(|unit| Json(unit))
+        .map_err(|error| status::Custom(Status::NotFound, error.to_string()))
+}
+
+#[post("/units", format="application/json", data="<unit>")]
+pub fn create(unit: NewUnit, conn: DbConn) -> Result<Json<Unit>, status::Custom<Json<UnitError>>> {
+    Unit::create(&conn, unit)
+        .map(|unit| Json(unit))
+        .map_err(|error| unit_error(error))
+}
+
+#[put("/units/<id>", format="application/json", data="<unit>")]
+pub fn update(id: i32, unit: Unit, conn: DbConn) -> Result<Json<Unit>, status::Custom<Json<UnitError>>> {
+    Unit::update(&conn, id, unit)
+        .map(|unit| Json(unit))
+        .map_err(|error| unit_error(error))
+}
+
+#[delete("/units/<id>", format="application/json")]
+pub fn delete(id: i32, conn: DbConn) -> Result<Json<usize>, status::Custom<String>> {
+    Unit::delete(&conn, id)
+        .map(|success| Json(success))
+        .map_err(|error| status::Custom(Status::InternalServerError, error.to_string()))
+}
+
+fn unit_error(error: UnitError) -> status::Custom<Json<UnitError>> {
+    let status = match error {
+        UnitError::InvalidFactor { .. } => Status::UnprocessableEntity,
+        UnitError::Database { .. } => Status::InternalServerError,
+    };
+
+    status::Custom(status, Json(error))
+}
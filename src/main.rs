@@ -29,5 +29,8 @@ mod models;
 mod routes;
 
 fn main() {
-    rocket::ignite().mount("/", routes::routes()).launch();
+    rocket::ignite()
+        .manage(models::db_connection::init_pool())
+        .mount("/", routes::routes())
+        .launch();
 }
@@ -1,34 +1,164 @@
-use crate::models::money::Money;
-use crate::models::item_calculation::ItemCalculation;
+use crate::models::money::{ Money, RoundingMode };
+use crate::models::item_calculation::{ ItemCalculation, TaxBreakdown };
+
+/// A change to the whole sale, negative for discounts. Percentages apply to
+/// each line, fixed amounts are shared among the lines in proportion to
+/// their discounted subtotals.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Adjustment {
+    Percentage(Money),
+    Amount(Money)
+}
 
 pub struct Calculation {
-    items: Vec<ItemCalculation>
+    items: Vec<ItemCalculation>,
+    adjustments: Vec<Money>
 }
 
 impl Calculation {
     pub fn new(
         items: Vec<ItemCalculation>
     ) -> Self {
-        Calculation { items }
+        Calculation { items, adjustments: vec![] }
+    }
+
+    /// Spreads the adjustments over the lines, so each line is taxed on
+    /// its adjusted base. None when an amount doesn't fit.
+    pub fn with_adjustments(mut self, adjustments: &[Adjustment]) -> Option<Self> {
+        let rounding = RoundingMode::from_env();
+        let bases = self.items
+            .iter()
+            .map(|item| item.subtotal_without_discount())
+            .collect::<Option<Vec<Money>>>()?;
+        let mut line_adjustments = vec![Money(0); self.items.len()];
+
+        for adjustment in adjustments {
+            let shares = match adjustment {
+                Adjustment::Percentage(percentage) =>
+                    bases
+                        .iter()
+                        .map(|base| base.percentage(percentage, rounding))
+                        .collect::<Option<Vec<Money>>>()?,
+                Adjustment::Amount(amount) => Self::apportion(amount, &bases)
+            };
+
+            for (line_adjustment, share) in line_adjustments.iter_mut().zip(shares.iter()) {
+                *line_adjustment = line_adjustment.checked_add(share)?;
+            }
+            self.adjustments.push(Money::checked_sum(&shares)?);
+        }
+
+        self.items = self.items
+            .into_iter()
+            .zip(line_adjustments.iter())
+            .map(|(item, line_adjustment)| item.with_adjustment(line_adjustment))
+            .collect();
+        Some(self)
     }
 
-    pub fn subtotal(&self) -> Money {
-        self.items.iter().map(|item| item.subtotal()).collect::<Vec<Money>>().iter().sum()
+    pub fn subtotal(&self) -> Option<Money> {
+        self.sum_items(|item| item.subtotal())
     }
 
-    pub fn subtotal_without_discount(&self) -> Money {
-        self.subtotal() - self.calculate_discount()
+    pub fn subtotal_without_discount(&self) -> Option<Money> {
+        self.subtotal()?.checked_sub(&self.calculate_discount()?)
     }
 
-    pub fn calculate_total(&self) -> Money {
-        self.subtotal_without_discount() + self.calculate_taxes()
+    pub fn calculate_total(&self) -> Option<Money> {
+        self.subtotal_without_discount()?
+            .checked_add(&self.calculate_adjustments()?)?
+            .checked_add(&self.calculate_taxes()?)
     }
 
-    pub fn calculate_discount(&self) -> Money {
-        self.items.iter().map(|item| item.calculate_discount()).collect::<Vec<Money>>().iter().sum()
+    pub fn calculate_discount(&self) -> Option<Money> {
+        self.sum_items(|item| item.calculate_discount())
     }
 
-    pub fn calculate_taxes(&self) -> Money {
-        self.items.iter().map(|item| item.calculate_taxes()).collect::<Vec<Money>>().iter().sum()
+    pub fn calculate_adjustments(&self) -> Option<Money> {
+        self.sum_items(|item| Some(item.calculate_adjustment()))
+    }
+
+    /// What each adjustment came to, in the order they were applied.
+    pub fn adjustment_amounts(&self) -> Vec<Money> {
+        self.adjustments.clone()
+    }
+
+    pub fn line_adjustments(&self) -> Vec<Money> {
+        self.items.iter().map(|item| item.calculate_adjustment()).collect()
+    }
+
+    pub fn calculate_taxes(&self) -> Option<Money> {
+        self.sum_items(|item| item.calculate_taxes())
+    }
+
+    /// Groups the line taxes by tax, adding up the taxed bases and amounts,
+    /// in the order they first appear on the lines.
+    pub fn tax_breakdown(&self) -> Option<Vec<TaxBreakdown>> {
+        let mut breakdown: Vec<TaxBreakdown> = vec![];
+
+        for item in &self.items {
+            for line_tax in item.tax_breakdown()? {
+                let existing = breakdown
+                    .iter_mut()
+                    .find(|tax| tax.tax_id == line_tax.tax_id && tax.percentage == line_tax.percentage);
+
+                match existing {
+                    Some(tax) => {
+                        tax.base = tax.base.checked_add(&line_tax.base)?;
+                        tax.amount = tax.amount.checked_add(&line_tax.amount)?;
+                    },
+                    None => breakdown.push(line_tax)
+                }
+            }
+        }
+
+        Some(breakdown)
+    }
+
+    /// Adds up an amount over the lines, None when any of them or the total
+    /// doesn't fit.
+    fn sum_items<F>(&self, amount: F) -> Option<Money>
+    where
+        F: Fn(&ItemCalculation) -> Option<Money>
+    {
+        self.items
+            .iter()
+            .try_fold(Money(0), |total, item| total.checked_add(&amount(item)?))
+    }
+
+    /// Splits `amount` in proportion to `weights` without losing cents: the
+    /// cents left after rounding down go to the largest remainders. With
+    /// nothing to weigh, the first line takes it all.
+    fn apportion(amount: &Money, weights: &[Money]) -> Vec<Money> {
+        let mut shares = vec![Money(0); weights.len()];
+        let total_weight = weights.iter().map(|weight| weight.0.max(0) as i128).sum::<i128>();
+
+        if shares.is_empty() {
+            return shares;
+        }
+        if total_weight == 0 {
+            shares[0] = amount.clone();
+            return shares;
+        }
+
+        let sign = amount.0.signum() as i128;
+        let magnitude = (amount.0 as i128).abs();
+        let mut remainders = vec![];
+        let mut left = magnitude;
+
+        for (index, weight) in weights.iter().enumerate() {
+            let weighted = magnitude * weight.0.max(0) as i128;
+            let share = weighted / total_weight;
+            left -= share;
+            remainders.push((weighted % total_weight, index));
+            shares[index] = Money((sign * share) as i64);
+        }
+
+        remainders.sort_by(|first, second| second.0.cmp(&first.0).then(first.1.cmp(&second.1)));
+        for &(_, index) in remainders.iter().take(left as usize) {
+            shares[index] = Money(shares[index].0 + sign as i64);
+        }
+
+        shares
     }
 }
@@ -0,0 +1,94 @@
+use diesel;
+use diesel::prelude::*;
+use diesel::sql_types;
+use diesel::pg::PgConnection;
+use crate::schema;
+use crate::schema::categories;
+use crate::handlers::base::Search;
+use crate::basic_model_actions;
+
+type BoxedQuery<'a> =
+    diesel::query_builder::BoxedSelectStatement<'a, (sql_types::Integer,
+                                                     sql_types::Text,
+                                                     sql_types::Nullable<sql_types::Integer>),
+                                                     schema::categories::table, diesel::pg::Pg>;
+
+/// A catalog category, nested under `parent_id` when it has one.
+#[derive(Serialize, Deserialize, Queryable, Eq, PartialEq,
+         Debug, Clone, AsChangeset, FromForm, FromData, Responder)]
+#[table_name="categories"]
+pub struct Category {
+    pub id: i32,
+    pub name: String,
+    pub parent_id: Option<i32>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, FromForm, FromData)]
+pub struct SearchCategory {
+    pub id: Option<i32>,
+    pub name: Option<String>,
+    pub parent_id: Option<i32>
+}
+
+#[derive(Serialize, Deserialize, Insertable, Debug, FromData, Responder)]
+#[table_name="categories"]
+pub struct NewCategory {
+    pub name: String,
+    pub parent_id: Option<i32>
+}
+
+#[derive(QueryableByName)]
+struct CategoryId {
+    #[sql_type="sql_types::Integer"]
+    id: i32
+}
+
+impl Category {
+    /// SQL selecting the id of the category and of every category below it.
+    pub fn descendants_query(category_id: i32) -> String {
+        format!("WITH RECURSIVE descendants(id) AS ( \
+                     SELECT id FROM categories WHERE id = {} \
+                     UNION \
+                     SELECT categories.id FROM categories \
+                     INNER JOIN descendants ON categories.parent_id = descendants.id \
+                 ) SELECT id FROM descendants", category_id)
+    }
+
+    /// Ids of the categories the product is in and of every category above
+    /// them.
+    pub fn ancestor_ids(connection: &PgConnection, product_id: i32) -> Result<Vec<i32>, diesel::result::Error> {
+        diesel::sql_query("WITH RECURSIVE ancestors(id) AS ( \
+                               SELECT category_id FROM product_categories WHERE product_id = $1 \
+                               UNION \
+                               SELECT categories.parent_id FROM categories \
+                               INNER JOIN ancestors ON categories.id = ancestors.id \
+                               WHERE categories.parent_id IS NOT NULL \
+                           ) SELECT id FROM ancestors")
+            .bind::<sql_types::Integer, _>(product_id)
+            .load::<CategoryId>(connection)
+            .map(|category_ids| category_ids.into_iter().map(|category_id| category_id.id).collect())
+    }
+
+    fn searching_records<'a>(search: Option<Search<SearchCategory>>) -> BoxedQuery<'a> {
+        use crate::schema::categories::dsl::*;
+
+        let mut query = schema::categories::table.into_boxed::<diesel::pg::Pg>();
+
+        if let Some(search_category) = search {
+            let Search(category) = search_category;
+            if let Some(category_id) = category.id {
+                query = query.filter(id.eq(category_id));
+            }
+            if let Some(category_name) = category.name {
+                query = query.filter(name.like(category_name));
+            }
+            if let Some(category_parent_id) = category.parent_id {
+                query = query.filter(parent_id.eq(category_parent_id));
+            }
+        }
+
+        query
+    }
+}
+
+basic_model_actions!(categories, Category, NewCategory, SearchCategory);
@@ -15,7 +15,8 @@ type BoxedQuery<'a> =
                                                      sql_types::Nullable<sql_types::Text>,
                                                      sql_types::Nullable<sql_types::Text>,
                                                      sql_types::Nullable<sql_types::Text>,
-                                                     sql_types::Nullable<sql_types::Text>),
+                                                     sql_types::Nullable<sql_types::Text>,
+                                                     sql_types::Nullable<sql_types::Integer>),
                                                      schema::clients::table, diesel::pg::Pg>;
 
 #[derive(Serialize, Deserialize, Clone, Queryable,
@@ -26,7 +27,8 @@ pub struct Client {
     pub last_name: Option<String>,
     pub company_name: Option<String>,
     pub email: Option<String>,
-    pub phone: Option<String>
+    pub phone: Option<String>,
+    pub price_id: Option<i32>
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, FromForm, Responder)]
@@ -36,7 +38,8 @@ pub struct SearchClient {
     pub last_name: Option<String>,
     pub company_name: Option<String>,
     pub email: Option<String>,
-    pub phone: Option<String>
+    pub phone: Option<String>,
+    pub price_id: Option<i32>
 }
 
 #[derive(Serialize, Deserialize, Insertable, Debug, FromData, Responder)]
@@ -46,7 +49,8 @@ pub struct NewClient {
     pub last_name: Option<String>,
     pub company_name: Option<String>,
     pub email: Option<String>,
-    pub phone: Option<String>
+    pub phone: Option<String>,
+    pub price_id: Option<i32>
 }
 
 impl Client {
@@ -76,6 +80,9 @@ impl Client {
             if let Some(clients_phone) = client.phone {
                 query = query.filter(phone.like(clients_phone));
             }
+            if let Some(clients_price_id) = client.price_id {
+                query = query.filter(price_id.eq(clients_price_id));
+            }
         }
 
         query
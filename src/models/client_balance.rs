@@ -0,0 +1,60 @@
+use diesel;
+use diesel::prelude::*;
+use diesel::pg::PgConnection;
+use crate::models::money::Money;
+use crate::models::payment::Payment;
+use crate::models::credit_note::CreditNote;
+use crate::models::sale::Sale;
+use crate::models::sale_status::SaleStatus;
+use crate::models::client::Client;
+use crate::models::client::BasicModelActions;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenSale {
+    pub sale: Sale,
+    pub payed: Money,
+    pub credited: Money,
+    pub owed: Money
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClientBalance {
+    pub client: Client,
+    pub owed: Money,
+    pub open_sales: Vec<OpenSale>
+}
+
+impl ClientBalance {
+    pub fn show(connection: &PgConnection, client_id: i32) -> Result<ClientBalance, String> {
+        use crate::schema::sales::dsl;
+
+        let client = Client::show(connection, client_id).map_err(|error| error.to_string())?;
+
+        let vec_sales = dsl::sales
+            .filter(dsl::client_id.eq(client_id))
+            .filter(dsl::status.eq_any(SaleStatus::open_statuses()))
+            .order(dsl::sale_date)
+            .load::<Sale>(connection)
+            .map_err(|error| error.to_string())?;
+
+        let mut open_sales = vec![];
+        for sale in vec_sales {
+            let payed = Payment::payed_amount(connection, sale.id).map_err(|error| error.to_string())?;
+            let credited = CreditNote::credited_amount(connection, sale.id).map_err(|error| error.to_string())?;
+            let owed = sale.total
+                .checked_sub(&payed)
+                .and_then(|owed| owed.checked_sub(&credited))
+                .ok_or_else(|| format!("Sale {} owes more than an amount holds", sale.id))?;
+            open_sales.push(OpenSale { sale, payed, credited, owed });
+        }
+
+        let owed = Money::checked_sum(open_sales.iter().map(|open_sale| &open_sale.owed))
+            .ok_or_else(|| format!("Client {} owes more than an amount holds", client_id))?;
+
+        Ok(ClientBalance {
+            client,
+            owed,
+            open_sales
+        })
+    }
+}
@@ -0,0 +1,22 @@
+use chrono::Local;
+use chrono::NaiveDate;
+
+pub trait Clock {
+    fn today(&self) -> NaiveDate;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn today(&self) -> NaiveDate {
+        Local::today().naive_local()
+    }
+}
+
+pub struct FixedClock(pub NaiveDate);
+
+impl Clock for FixedClock {
+    fn today(&self) -> NaiveDate {
+        self.0
+    }
+}
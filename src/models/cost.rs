@@ -7,14 +7,16 @@ use crate::handlers::base::Search;
 use crate::basic_model_actions;
 
 type BoxedQuery<'a> = 
-    diesel::query_builder::BoxedSelectStatement<'a, (sql_types::Integer, sql_types::Text),
+    diesel::query_builder::BoxedSelectStatement<'a, (sql_types::Integer, sql_types::Text, sql_types::Bool),
                                                      schema::costs::table, diesel::pg::Pg>;
 
 #[derive(Serialize, Deserialize, Queryable, Eq, PartialEq, Hash,
          Debug, AsChangeset, FromForm, FromData, Responder)]
 pub struct Cost {
     pub id: i32,
-    pub name: String
+    pub name: String,
+    #[serde(default)]
+    pub primary_cost: bool
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, FromForm, Responder)]
@@ -27,7 +29,8 @@ pub struct SearchCost {
          Debug, FromData, Responder)]
 #[table_name="costs"]
 pub struct NewCost {
-    pub name: String
+    pub name: String,
+    pub primary_cost: Option<bool>
 }
  
 impl Cost {
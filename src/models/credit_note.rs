@@ -0,0 +1,292 @@
+use std::fmt;
+use std::io::Read;
+use std::collections::HashMap;
+use diesel;
+use diesel::prelude::*;
+use diesel::dsl::sql;
+use diesel::sql_types;
+use diesel::pg::PgConnection;
+use crate::models::naive_date_form::NaiveDateForm;
+use crate::models::credit_note_product::CreditNoteProduct;
+use crate::models::credit_note_product::NewCreditNoteProduct;
+use crate::models::calculation::Calculation;
+use crate::models::money::Money;
+use crate::models::tax::Tax;
+use crate::models::payment::Payment;
+use crate::models::sale::Sale;
+use crate::models::sale_product::SaleProduct;
+use crate::models::sale_status::SaleStatus;
+use crate::models::sale_status::SaleStatusError;
+use crate::models::stock_movement::StockMovement;
+use crate::models::stock_movement::NewStockMovement;
+use crate::models::stock_movement::StockDocument;
+use rocket::{ Request, Data };
+use rocket::data:: { FromData, Outcome };
+use rocket::http::Status;
+use rocket::Outcome::{ Failure, Success };
+use crate::schema::credit_notes;
+use crate::schema::credit_note_products;
+use serde_json;
+
+#[derive(Serialize, Deserialize, Clone, Queryable, Debug)]
+pub struct CreditNote {
+    pub id: i32,
+    pub sale_id: i32,
+    pub credit_note_date: NaiveDateForm,
+    pub sub_total: Money,
+    pub sub_total_without_discount: Money,
+    pub discount_calculated: Money,
+    pub taxes_calculated: Money,
+    pub total: Money,
+    pub observation: Option<String>
+}
+
+#[derive(Insertable, Serialize, Deserialize, Clone, Debug)]
+#[table_name="credit_notes"]
+pub struct NewCreditNote {
+    pub sale_id: Option<i32>,
+    pub credit_note_date: NaiveDateForm,
+    pub sub_total: Option<Money>,
+    pub sub_total_without_discount: Option<Money>,
+    pub discount_calculated: Option<Money>,
+    pub taxes_calculated: Option<Money>,
+    pub total: Option<Money>,
+    pub observation: Option<String>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FullCreditNote {
+    pub credit_note: CreditNote,
+    pub credit_note_products: Vec<CreditNoteProduct>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FullNewCreditNote {
+    credit_note: NewCreditNote,
+    credit_note_products: Vec<NewCreditNoteProduct>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "error", rename_all = "snake_case")]
+pub enum CreditNoteError {
+    SaleNotFound { sale_id: i32 },
+    NotCreditable { sale_id: i32, status: SaleStatus },
+    UnknownSaleProduct { sale_product_id: i32 },
+    InvalidAmount { sale_product_id: i32, amount: f64 },
+    AmountExceeded { sale_product_id: i32, available: f64 },
+    Overflow,
+    Database { message: String },
+}
+
+impl fmt::Display for CreditNoteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CreditNoteError::SaleNotFound { sale_id } =>
+                write!(f, "Sale {} not found", sale_id),
+            CreditNoteError::NotCreditable { sale_id, status } =>
+                write!(f, "Sale {} is {:?} and can't be credited", sale_id, status),
+            CreditNoteError::UnknownSaleProduct { sale_product_id } =>
+                write!(f, "Sale product {} doesn't belong to the sale", sale_product_id),
+            CreditNoteError::InvalidAmount { sale_product_id, amount } =>
+                write!(f, "Can't return {} of sale product {}, amounts must be positive", amount, sale_product_id),
+            CreditNoteError::AmountExceeded { sale_product_id, available } =>
+                write!(f, "Only {} left to return for sale product {}", available, sale_product_id),
+            CreditNoteError::Overflow =>
+                write!(f, "The credit note amounts are too large"),
+            CreditNoteError::Database { message } =>
+                write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<diesel::result::Error> for CreditNoteError {
+    fn from(error: diesel::result::Error) -> Self {
+        CreditNoteError::Database { message: error.to_string() }
+    }
+}
+
+impl From<SaleStatusError> for CreditNoteError {
+    fn from(error: SaleStatusError) -> Self {
+        CreditNoteError::Database { message: error.to_string() }
+    }
+}
+
+impl CreditNote {
+    pub fn list(connection: &PgConnection, sale_id: i32) -> Result<Vec<CreditNote>, diesel::result::Error> {
+        use crate::schema::credit_notes::dsl;
+
+        dsl::credit_notes
+            .filter(dsl::sale_id.eq(sale_id))
+            .order(dsl::id)
+            .load::<CreditNote>(connection)
+    }
+
+    pub fn show(connection: &PgConnection, request_id: i32) -> Result<FullCreditNote, diesel::result::Error> {
+        use crate::schema::credit_notes::dsl::*;
+
+        let credit_note_result = credit_notes
+            .find(request_id)
+            .get_result::<CreditNote>(connection)?;
+
+        let credit_note_products_result = credit_note_products::dsl::credit_note_products
+            .filter(credit_note_products::dsl::credit_note_id.eq(credit_note_result.id))
+            .load::<CreditNoteProduct>(connection)?;
+
+        Ok(FullCreditNote {
+            credit_note: credit_note_result,
+            credit_note_products: credit_note_products_result
+        })
+    }
+
+    /// Returns part of a sale: each line is bounded by what is left of the
+    /// original sale product, the returned goods go back to stock and the
+    /// credited total counts against what the client owes.
+    pub fn create(connection: &PgConnection, sale_id: i32, full_new_credit_note: FullNewCreditNote, changed_by: &str) -> Result<CreditNote, CreditNoteError> {
+        use crate::schema::sales;
+
+        connection.transaction(|| {
+            let sale = sales::dsl::sales
+                .find(sale_id)
+                .for_update()
+                .get_result::<Sale>(connection)
+                .map_err(|error| match error {
+                    diesel::result::Error::NotFound => CreditNoteError::SaleNotFound { sale_id },
+                    error => CreditNoteError::from(error),
+                })?;
+
+            if !Self::creditable_statuses().contains(&sale.status) {
+                return Err(CreditNoteError::NotCreditable { sale_id, status: sale.status });
+            }
+
+            let lines = Self::returned_lines(connection, &sale, full_new_credit_note.credit_note_products)?;
+            let line_taxes = lines
+                .iter()
+                .map(|(sale_product, _line)| Tax::find_all(connection, &sale_product.tax_ids))
+                .collect::<Result<Vec<Vec<Tax>>, diesel::result::Error>>()?;
+            let calculation = Calculation::new(
+                lines
+                    .iter()
+                    .zip(line_taxes.iter())
+                    .map(|((sale_product, line), taxes)| sale_product.to_item_calc_method(line.amount, taxes))
+                    .collect()
+            );
+
+            let mut new_credit_note = full_new_credit_note.credit_note;
+            new_credit_note.sale_id = Some(sale_id);
+            new_credit_note.sub_total = Some(calculation.subtotal().ok_or(CreditNoteError::Overflow)?);
+            new_credit_note.sub_total_without_discount =
+                Some(calculation.subtotal_without_discount().ok_or(CreditNoteError::Overflow)?);
+            new_credit_note.discount_calculated = Some(calculation.calculate_discount().ok_or(CreditNoteError::Overflow)?);
+            new_credit_note.taxes_calculated = Some(calculation.calculate_taxes().ok_or(CreditNoteError::Overflow)?);
+            new_credit_note.total = Some(calculation.calculate_total().ok_or(CreditNoteError::Overflow)?);
+
+            let credit_note = diesel::insert_into(credit_notes::table)
+                .values(&new_credit_note)
+                .get_result::<CreditNote>(connection)?;
+
+            for ((sale_product, line), taxes) in lines.into_iter().zip(line_taxes) {
+                diesel::insert_into(credit_note_products::table)
+                    .values(&line
+                            .with_calculations(credit_note.id, &sale_product, &taxes)
+                            .ok_or(CreditNoteError::Overflow)?)
+                    .execute(connection)?;
+
+                let amount = line.amount * sale_product.unit_factor;
+                StockMovement::register(connection, &NewStockMovement {
+                    product_id: sale_product.product_id,
+                    amount: StockDocument::CreditNote.sign() * amount,
+                    document: StockDocument::CreditNote,
+                    document_id: Some(credit_note.id),
+                    variant_id: sale_product.variant_id
+                })?;
+            }
+
+            let settled = Payment::payed_amount(connection, sale_id)?.0 as i128 +
+                Self::credited_amount(connection, sale_id)?.0 as i128;
+
+            if SaleStatus::open_statuses().contains(&sale.status) && settled >= sale.total.0 as i128 {
+                SaleStatus::apply_transition(connection, sale_id, SaleStatus::Payed, changed_by)?;
+            }
+
+            Ok(credit_note)
+        })
+    }
+
+    /// Added up by the database, which fails instead of wrapping around
+    /// when the total doesn't fit.
+    pub fn credited_amount(connection: &PgConnection, sale_id: i32) -> Result<Money, diesel::result::Error> {
+        use crate::schema::credit_notes::dsl;
+
+        dsl::credit_notes
+            .filter(dsl::sale_id.eq(sale_id))
+            .select(sql::<sql_types::BigInt>("COALESCE(SUM(total), 0)::BIGINT"))
+            .first::<Money>(connection)
+    }
+
+    pub fn creditable_statuses() -> Vec<SaleStatus> {
+        vec![SaleStatus::Saved, SaleStatus::Active, SaleStatus::Overdue, SaleStatus::Payed]
+    }
+
+    fn returned_lines(
+        connection: &PgConnection,
+        sale: &Sale,
+        vec_new_credit_note_products: Vec<NewCreditNoteProduct>,
+    ) -> Result<Vec<(SaleProduct, NewCreditNoteProduct)>, CreditNoteError> {
+        use crate::schema::sale_products::dsl;
+
+        let mut requested: HashMap<i32, f64> = HashMap::new();
+        let mut lines = vec![];
+
+        for new_credit_note_product in vec_new_credit_note_products {
+            let sale_product_id = new_credit_note_product.sale_product_id;
+            let sale_product = dsl::sale_products
+                .find(sale_product_id)
+                .filter(dsl::sale_id.eq(sale.id))
+                .first::<SaleProduct>(connection)
+                .optional()?
+                .ok_or(CreditNoteError::UnknownSaleProduct { sale_product_id })?;
+
+            let already_requested = requested.entry(sale_product_id).or_insert(0.0);
+            let available = sale_product.amount -
+                CreditNoteProduct::returned_amount(connection, sale_product_id)? -
+                *already_requested;
+
+            if new_credit_note_product.amount <= 0.0 {
+                return Err(CreditNoteError::InvalidAmount { sale_product_id, amount: new_credit_note_product.amount });
+            }
+
+            if new_credit_note_product.amount > available {
+                return Err(CreditNoteError::AmountExceeded { sale_product_id, available });
+            }
+
+            *already_requested += new_credit_note_product.amount;
+            lines.push((sale_product, new_credit_note_product));
+        }
+
+        Ok(lines)
+    }
+}
+
+impl FromData for FullNewCreditNote {
+    type Error = String;
+
+    fn from_data( _: &Request, data: Data) -> Outcome<Self, String> {
+        let mut string_data = String::new();
+        if let Err(e) = data.open().read_to_string(&mut string_data) {
+            return Failure((Status::InternalServerError, format!("{:?}", e)));
+        }
+
+        let maybe_full_new_credit_note = serde_json::from_str(&string_data);
+
+        match maybe_full_new_credit_note {
+            Ok(ok_full_new_credit_note) => {
+                let full_new_credit_note: FullNewCreditNote = ok_full_new_credit_note;
+                if full_new_credit_note.credit_note_products.is_empty() {
+                    return Failure(( Status::UnprocessableEntity, "No products selected!".to_string() ));
+                }
+                Success(full_new_credit_note)
+            },
+            Err(err) => Failure((Status::BadRequest, err.to_string()))
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use diesel;
+use diesel::RunQueryDsl;
+use diesel::QueryDsl;
+use diesel::ExpressionMethods;
+use diesel::pg::PgConnection;
+use crate::schema::credit_note_products;
+use crate::models::money::Money;
+use crate::models::tax::Tax;
+use crate::models::sale_product::SaleProduct;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Identifiable, Associations,
+         Queryable, Debug)]
+pub struct CreditNoteProduct {
+    pub id: i32,
+    pub credit_note_id: i32,
+    pub sale_product_id: i32,
+    pub product_id: i32,
+    pub amount: f64,
+    pub subtotal: Money,
+    pub sub_total_without_discount: Money,
+    pub discount_calculated: Money,
+    pub taxes_calculated: Money,
+    pub total: Money,
+    pub observation: Option<String>,
+}
+
+#[derive(Insertable, Serialize, Deserialize, Clone, Debug)]
+#[table_name="credit_note_products"]
+pub struct NewCreditNoteProduct {
+    pub credit_note_id: Option<i32>,
+    pub sale_product_id: i32,
+    #[serde(skip_deserializing)]
+    pub product_id: Option<i32>,
+    pub amount: f64,
+    pub subtotal: Option<Money>,
+    pub sub_total_without_discount: Option<Money>,
+    pub discount_calculated: Option<Money>,
+    pub taxes_calculated: Option<Money>,
+    pub total: Option<Money>,
+    pub observation: Option<String>,
+}
+
+impl CreditNoteProduct {
+    pub fn returned_amount(connection: &PgConnection, sale_product_id: i32) -> Result<f64, diesel::result::Error> {
+        use crate::schema::credit_note_products::dsl;
+
+        let returned = dsl::credit_note_products
+            .filter(dsl::sale_product_id.eq(sale_product_id))
+            .select(diesel::dsl::sum(dsl::amount))
+            .first::<Option<f64>>(connection)?;
+
+        Ok(returned.unwrap_or(0.0))
+    }
+}
+
+impl NewCreditNoteProduct {
+    /// Fills in the calculated fields by reversing the returned amount with
+    /// the price, discount and taxes of the original sale line.
+    /// None when the amounts don't fit.
+    pub fn with_calculations(&self, credit_note_id: i32, sale_product: &SaleProduct, taxes: &[Tax]) -> Option<Self> {
+        let item_calc = sale_product.to_item_calc_method(self.amount, taxes);
+        let mut new_credit_note_product = self.clone();
+        new_credit_note_product.credit_note_id = Some(credit_note_id);
+        new_credit_note_product.product_id = Some(sale_product.product_id);
+        new_credit_note_product.subtotal = Some(item_calc.subtotal()?);
+        new_credit_note_product.sub_total_without_discount = Some(item_calc.subtotal_without_discount()?);
+        new_credit_note_product.discount_calculated = Some(item_calc.calculate_discount()?);
+        new_credit_note_product.taxes_calculated = Some(item_calc.calculate_taxes()?);
+        new_credit_note_product.total = Some(item_calc.calculate_total()?);
+        Some(new_credit_note_product)
+    }
+}
@@ -3,7 +3,7 @@ use diesel;
 use diesel::prelude::*;
 use diesel::sql_types;
 use crate::handlers::base::Search;
-use crate::models::db_connection;
+use diesel::pg::PgConnection;
 use crate::schema;
 use crate::schema::currencies;
 
@@ -57,28 +57,36 @@ pub struct NewCurrency {
 }
 
 impl Currency {
-    pub fn get_currency() -> Self {
+    pub fn get_currency(connection: &PgConnection) -> Self {
         use crate::schema::currencies::dsl::*;
-        let connection = db_connection::establish_connection();
 
         let maybe_currency = currencies
             .filter(in_use.eq(true))
-            .get_result::<Currency>(&connection);
+            .get_result::<Currency>(connection);
 
         match maybe_currency {
             Ok(currency) => currency,
-            Err(_) => Self::get_default_currency()
+            Err(_) => Self::get_default_currency(connection)
         }
 
     }
 
-    fn get_default_currency() -> Self {
+    pub fn default_currency_id(connection: &PgConnection) -> Option<i32> {
+        use crate::schema::currencies::dsl::*;
+
+        currencies
+            .filter(default_currency.eq(true))
+            .select(id)
+            .first::<i32>(connection)
+            .ok()
+    }
+
+    fn get_default_currency(connection: &PgConnection) -> Self {
         use crate::schema::currencies::dsl::*;
-        let connection = db_connection::establish_connection();
 
         let default = currencies
             .filter(default_currency.eq(true))
-            .get_result::<Currency>(&connection);
+            .get_result::<Currency>(connection);
 
         match default {
             Ok(currency) => currency,
@@ -1,9 +1,20 @@
 use diesel::prelude::*;
 use diesel::pg::PgConnection;
+use diesel::r2d2::{ ConnectionManager, Pool, PooledConnection };
 use dotenv::dotenv;
+use rocket::{ Request, State, Outcome };
+use rocket::http::Status;
+use rocket::request::{ self, FromRequest };
 use std::env;
+use std::ops::Deref;
+use std::time::Duration;
 
-pub fn establish_connection() -> PgConnection {
+pub type PgPool = Pool<ConnectionManager<PgConnection>>;
+
+const DEFAULT_POOL_SIZE: u32 = 10;
+const DEFAULT_POOL_TIMEOUT: u64 = 30;
+
+fn database_url() -> String {
     dotenv().ok();
 
     let database_url_env_var_name = if cfg!(test) {
@@ -12,9 +23,58 @@ pub fn establish_connection() -> PgConnection {
         "TEST_DATABASE_URL"
     };
 
-    let database_url = env::var(database_url_env_var_name)
-        .expect("DATABASE_URL must be set");
+    env::var(database_url_env_var_name)
+        .expect("DATABASE_URL must be set")
+}
+
+pub fn establish_connection() -> PgConnection {
+    let database_url = database_url();
 
     PgConnection::establish(&database_url)
         .expect(&format!("Error connecting to {}", database_url))
-}
\ No newline at end of file
+}
+
+/// Builds the pool Rocket manages. `DATABASE_POOL_SIZE` sets the maximum
+/// number of connections and `DATABASE_POOL_TIMEOUT` the seconds a request
+/// waits for a free one.
+pub fn init_pool() -> PgPool {
+    let database_url = database_url();
+
+    let pool_size = env::var("DATABASE_POOL_SIZE")
+        .ok()
+        .and_then(|size| size.parse::<u32>().ok())
+        .unwrap_or(DEFAULT_POOL_SIZE);
+
+    let pool_timeout = env::var("DATABASE_POOL_TIMEOUT")
+        .ok()
+        .and_then(|timeout| timeout.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_POOL_TIMEOUT);
+
+    Pool::builder()
+        .max_size(pool_size)
+        .connection_timeout(Duration::from_secs(pool_timeout))
+        .build(ConnectionManager::<PgConnection>::new(database_url.as_str()))
+        .expect(&format!("Error creating the pool for {}", database_url))
+}
+
+pub struct DbConn(pub PooledConnection<ConnectionManager<PgConnection>>);
+
+impl<'a, 'r> FromRequest<'a, 'r> for DbConn {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<DbConn, ()> {
+        let pool = request.guard::<State<PgPool>>()?;
+        match pool.get() {
+            Ok(connection) => Outcome::Success(DbConn(connection)),
+            Err(_) => Outcome::Failure((Status::ServiceUnavailable, ()))
+        }
+    }
+}
+
+impl Deref for DbConn {
+    type Target = PgConnection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
@@ -0,0 +1,106 @@
+use diesel;
+use diesel::prelude::*;
+use diesel::sql_types;
+use diesel::pg::PgConnection;
+use crate::schema;
+use crate::schema::exchange_rates;
+use crate::models::naive_date_form::NaiveDateForm;
+use crate::handlers::base::Search;
+use crate::basic_model_actions;
+
+type BoxedQuery<'a> =
+    diesel::query_builder::BoxedSelectStatement<'a, (sql_types::Integer,
+                                                     sql_types::Integer,
+                                                     sql_types::Integer,
+                                                     sql_types::Double,
+                                                     sql_types::Date),
+                                                     schema::exchange_rates::table, diesel::pg::Pg>;
+
+#[derive(Serialize, Deserialize, Queryable,
+         Debug, Clone, AsChangeset, FromForm, FromData, Responder)]
+#[table_name="exchange_rates"]
+pub struct ExchangeRate {
+    pub id: i32,
+    pub from_currency_id: i32,
+    pub to_currency_id: i32,
+    pub rate: f64,
+    pub effective_date: NaiveDateForm
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, FromForm, FromData)]
+pub struct SearchExchangeRate {
+    pub id: Option<i32>,
+    pub from_currency_id: Option<i32>,
+    pub to_currency_id: Option<i32>,
+    pub effective_date: Option<NaiveDateForm>
+}
+
+#[derive(Serialize, Deserialize, Insertable, Debug, FromData, Responder)]
+#[table_name="exchange_rates"]
+pub struct NewExchangeRate {
+    pub from_currency_id: i32,
+    pub to_currency_id: i32,
+    pub rate: f64,
+    pub effective_date: NaiveDateForm
+}
+
+impl ExchangeRate {
+    /// Rate to multiply an amount in `from` by to get it in `to`, taken
+    /// from the latest rate effective on `date`. A rate registered the
+    /// other way around is used inverted.
+    pub fn rate(connection: &PgConnection, from: i32, to: i32, date: &NaiveDateForm) -> Result<f64, diesel::result::Error> {
+        use crate::schema::exchange_rates::dsl::*;
+
+        if from == to {
+            return Ok(1.0);
+        }
+
+        let direct_rate = exchange_rates
+            .filter(from_currency_id.eq(from))
+            .filter(to_currency_id.eq(to))
+            .filter(effective_date.le(date))
+            .order(effective_date.desc())
+            .select(rate)
+            .first::<f64>(connection)
+            .optional()?;
+
+        if let Some(direct_rate) = direct_rate {
+            return Ok(direct_rate);
+        }
+
+        exchange_rates
+            .filter(from_currency_id.eq(to))
+            .filter(to_currency_id.eq(from))
+            .filter(effective_date.le(date))
+            .order(effective_date.desc())
+            .select(rate)
+            .first::<f64>(connection)
+            .map(|inverse_rate| 1.0 / inverse_rate)
+    }
+
+    fn searching_records<'a>(search: Option<Search<SearchExchangeRate>>) -> BoxedQuery<'a> {
+        use crate::schema::exchange_rates::dsl::*;
+
+        let mut query = schema::exchange_rates::table.into_boxed::<diesel::pg::Pg>();
+
+        if let Some(search_exchange_rate) = search {
+            let Search(exchange_rate) = search_exchange_rate;
+            if let Some(exchange_rate_id) = exchange_rate.id {
+                query = query.filter(id.eq(exchange_rate_id));
+            }
+            if let Some(exchange_rate_from_currency_id) = exchange_rate.from_currency_id {
+                query = query.filter(from_currency_id.eq(exchange_rate_from_currency_id));
+            }
+            if let Some(exchange_rate_to_currency_id) = exchange_rate.to_currency_id {
+                query = query.filter(to_currency_id.eq(exchange_rate_to_currency_id));
+            }
+            if let Some(exchange_rate_effective_date) = exchange_rate.effective_date {
+                query = query.filter(effective_date.eq(exchange_rate_effective_date));
+            }
+        }
+
+        query
+    }
+}
+
+basic_model_actions!(exchange_rates, ExchangeRate, NewExchangeRate, SearchExchangeRate);
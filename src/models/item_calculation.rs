@@ -1,11 +1,35 @@
-use crate::models::money::Money;
+use crate::models::money::{ Money, RoundingMode, PERCENTAGE_SCALE };
+use crate::models::tax::Tax;
+
+/// One of the taxes charged on a line. Lines with a free-form percentage
+/// carry a single tax without id.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ItemTax {
+    pub tax_id: Option<i32>,
+    pub name: String,
+    pub percentage: Money,
+    pub compound: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TaxBreakdown {
+    pub tax_id: Option<i32>,
+    pub name: String,
+    pub percentage: Money,
+    pub base: Money,
+    pub amount: Money,
+}
 
 #[derive(Clone, Debug)]
 pub struct ItemCalculation {
-    tax: Money,
+    taxes: Vec<ItemTax>,
     discount: Money,
+    discount_amount: Money,
+    adjustment: Money,
     price: Money,
     amount: f64,
+    tax_included: bool,
+    rounding: RoundingMode,
 }
 
 impl ItemCalculation {
@@ -15,31 +39,189 @@ impl ItemCalculation {
         price: &Money,
         amount: f64,
     ) -> Self {
+        let taxes =
+            if tax.0 == 0 {
+                vec![]
+            } else {
+                vec![ItemTax { tax_id: None, name: format!("{}%", tax), percentage: tax.clone(), compound: false }]
+            };
+
         ItemCalculation {
-            tax: (*tax).clone(),
+            taxes,
             discount: (*discount).clone(),
+            discount_amount: Money(0),
+            adjustment: Money(0),
             price: (*price).clone(),
             amount,
+            tax_included: false,
+            rounding: RoundingMode::from_env(),
+        }
+    }
+
+    pub fn with_rounding(mut self, rounding: RoundingMode) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// Charges the given taxes, in order, instead of the free-form
+    /// percentage. Lines without linked taxes keep the percentage they were
+    /// created with.
+    pub fn with_taxes(mut self, taxes: &[Tax]) -> Self {
+        if !taxes.is_empty() {
+            self.taxes = taxes
+                .iter()
+                .map(|tax| ItemTax {
+                    tax_id: Some(tax.id),
+                    name: tax.name.clone(),
+                    percentage: Money(tax.percentage as i64),
+                    compound: tax.compound
+                })
+                .collect();
+        }
+        self
+    }
+
+    /// A fixed discount for the whole line, taken after the percentage one.
+    pub fn with_discount_amount(mut self, discount_amount: &Money) -> Self {
+        self.discount_amount = discount_amount.clone();
+        self
+    }
+
+    /// The price already includes the taxes, which are worked out backwards
+    /// from what the client pays.
+    pub fn with_tax_included(mut self, tax_included: bool) -> Self {
+        self.tax_included = tax_included;
+        self
+    }
+
+    /// The line's share of the sale adjustments, added to its taxed base.
+    pub fn with_adjustment(mut self, adjustment: &Money) -> Self {
+        self.adjustment = adjustment.clone();
+        self
+    }
+
+    pub fn subtotal(&self) -> Option<Money> {
+        let gross = self.gross()?;
+        if self.tax_included {
+            self.without_taxes(&gross)
+        } else {
+            Some(gross)
+        }
+    }
+
+    pub fn subtotal_without_discount(&self) -> Option<Money> {
+        let discounted = self.gross()?.checked_sub(&self.gross_discount()?)?;
+        if self.tax_included {
+            self.without_taxes(&discounted)
+        } else {
+            Some(discounted)
         }
     }
 
-    pub fn subtotal(&self) -> Money {
-        self.clone().price * self.amount
+    pub fn calculate_total(&self) -> Option<Money> {
+        self.subtotal_without_discount()?
+            .checked_add(&self.calculate_adjustment())?
+            .checked_add(&self.calculate_taxes()?)
     }
 
-    pub fn subtotal_without_discount(&self) -> Money {
-        self.subtotal() - self.calculate_discount()
+    pub fn calculate_adjustment(&self) -> Money {
+        self.adjustment.clone()
     }
 
-    pub fn calculate_total(&self) -> Money {
-        self.subtotal_without_discount() + self.calculate_taxes()
+    pub fn calculate_discount(&self) -> Option<Money> {
+        if self.tax_included {
+            self.subtotal()?.checked_sub(&self.subtotal_without_discount()?)
+        } else {
+            self.gross_discount()
+        }
     }
 
-    pub fn calculate_discount(&self) -> Money {
-        (self.clone().discount * self.subtotal()) / 10000
+    pub fn calculate_taxes(&self) -> Option<Money> {
+        Money::checked_sum(self.tax_breakdown()?.iter().map(|tax| &tax.amount))
     }
 
-    pub fn calculate_taxes(&self) -> Money {
-        (self.subtotal_without_discount() * self.clone().tax) / 10000
+    /// Each tax is applied to the discounted and adjusted subtotal, plus
+    /// the taxes before it when compound, and rounded on its own so the
+    /// line taxes always add up to its breakdown. On tax-inclusive lines
+    /// without adjustments the last tax absorbs the rounding, so the total
+    /// is exactly the price paid. None when an amount doesn't fit.
+    pub fn tax_breakdown(&self) -> Option<Vec<TaxBreakdown>> {
+        let base = self.subtotal_without_discount()?.checked_add(&self.calculate_adjustment())?;
+        let mut previous_taxes = Money(0);
+        let mut breakdown = vec![];
+
+        for tax in &self.taxes {
+            let taxed_base =
+                if tax.compound {
+                    base.checked_add(&previous_taxes)?
+                } else {
+                    base.clone()
+                };
+            let amount = taxed_base.percentage(&tax.percentage, self.rounding)?;
+            previous_taxes = previous_taxes.checked_add(&amount)?;
+
+            breakdown.push(TaxBreakdown {
+                tax_id: tax.tax_id,
+                name: tax.name.clone(),
+                percentage: tax.percentage.clone(),
+                base: taxed_base,
+                amount
+            });
+        }
+
+        if self.tax_included && self.adjustment.0 == 0 {
+            let included_taxes = self.gross()?
+                .checked_sub(&self.gross_discount()?)?
+                .checked_sub(&base)?;
+            if let Some(last_tax) = breakdown.last_mut() {
+                last_tax.amount = last_tax.amount
+                    .checked_add(&included_taxes)?
+                    .checked_sub(&previous_taxes)?;
+            }
+        }
+
+        Some(breakdown)
+    }
+
+    /// Price times amount as entered, with taxes when they're included.
+    fn gross(&self) -> Option<Money> {
+        self.price.times(self.amount, self.rounding)
+    }
+
+    /// Both discounts over the gross amount, never more than the line.
+    fn gross_discount(&self) -> Option<Money> {
+        let gross = self.gross()?;
+        let discount = gross
+            .percentage(&self.discount, self.rounding)?
+            .checked_add(&self.discount_amount)?;
+
+        if gross.0 >= 0 && discount > gross {
+            Some(gross)
+        } else {
+            Some(discount)
+        }
+    }
+
+    /// Takes the included taxes out of an amount.
+    fn without_taxes(&self, amount: &Money) -> Option<Money> {
+        let (numerator, denominator) = self.tax_factor()?;
+        amount.checked_mul_div(denominator, numerator, self.rounding)
+    }
+
+    /// What a base is multiplied by once all the taxes are added, as a
+    /// fraction, with compound taxes also applied over the previous ones.
+    fn tax_factor(&self) -> Option<(i128, i128)> {
+        let mut taxes_numerator: i128 = 0;
+        let mut denominator: i128 = 1;
+
+        for tax in &self.taxes {
+            let taxed_base = if tax.compound { denominator.checked_add(taxes_numerator)? } else { denominator };
+            taxes_numerator = taxes_numerator
+                .checked_mul(PERCENTAGE_SCALE)?
+                .checked_add((tax.percentage.0 as i128).checked_mul(taxed_base)?)?;
+            denominator = denominator.checked_mul(PERCENTAGE_SCALE)?;
+        }
+
+        Some((denominator.checked_add(taxes_numerator)?, denominator))
     }
 }
@@ -15,6 +15,29 @@ pub mod db_connection;
 pub mod money;
 pub mod currency;
 pub mod sale_status;
+pub mod sale_status_history;
+pub mod stock_movement;
+pub mod purchase;
+pub mod purchase_product;
+pub mod purchase_status;
+pub mod payment;
+pub mod client_balance;
+pub mod clock;
+pub mod overdue_sweep;
+pub mod credit_note;
+pub mod credit_note_product;
+pub mod numbering_series;
+pub mod exchange_rate;
+pub mod product_tax;
+pub mod sale_tax;
+pub mod sale_adjustment;
+pub mod pricing_rule;
+pub mod price_update;
+pub mod category;
+pub mod product_category;
+pub mod product_barcode;
+pub mod product_variant;
+pub mod unit;
 
 #[macro_use]
 pub mod basic_model_actions;
@@ -1,30 +1,230 @@
-use std::{ fmt, str };
-use std::num::ParseFloatError;
+use std::{ env, fmt };
+use std::cmp::Ordering;
 use serde::de::{ self, Deserialize, Deserializer, Visitor, Unexpected };
 use serde::ser::{ Serialize, Serializer };
 use rocket::request::FromFormValue;
 use rocket::http::RawStr;
+use crate::models::currency::Currency;
+
+/// Quantities are multiplied as thousandths, the precision of the amounts
+/// entered on a line.
+const QUANTITY_SCALE: i128 = 1_000;
+/// Exchange rates are applied with six decimals.
+const RATE_SCALE: i128 = 1_000_000;
+/// Taxes and discounts are stored as hundredths of a percent.
+pub const PERCENTAGE_SCALE: i128 = 10_000;
 
 #[derive(DieselNewType)]
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct Money(pub i32);
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(pub i64);
+
+/// How a result that falls between two cents is settled. Set with
+/// MONEY_ROUNDING=half_up|half_even, half up when unset.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundingMode {
+    HalfUp,
+    HalfEven
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MoneyError {
+    Invalid(String),
+    Overflow
+}
+
+impl fmt::Display for MoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoneyError::Invalid(value) => write!(f, "{} is not a valid amount", value),
+            MoneyError::Overflow => write!(f, "The amount is too large"),
+        }
+    }
+}
+
+impl RoundingMode {
+    pub fn from_env() -> Self {
+        match env::var("MONEY_ROUNDING") {
+            Ok(ref mode) if mode == "half_even" => RoundingMode::HalfEven,
+            _ => RoundingMode::HalfUp
+        }
+    }
+
+    /// Integer division rounding the quotient to the nearest whole number,
+    /// ties settled by the mode. None when dividing by zero.
+    pub fn divide(self, numerator: i128, denominator: i128) -> Option<i128> {
+        if denominator == 0 {
+            return None;
+        }
+
+        let quotient = numerator / denominator;
+        let remainder = (numerator % denominator).abs();
+        if remainder == 0 {
+            return Some(quotient);
+        }
+
+        let away_from_zero = match remainder.cmp(&(denominator.abs() - remainder)) {
+            Ordering::Greater => true,
+            Ordering::Less => false,
+            Ordering::Equal => match self {
+                RoundingMode::HalfUp => true,
+                RoundingMode::HalfEven => quotient % 2 != 0
+            }
+        };
+
+        if !away_from_zero {
+            Some(quotient)
+        } else if (numerator < 0) != (denominator < 0) {
+            quotient.checked_sub(1)
+        } else {
+            quotient.checked_add(1)
+        }
+    }
+}
 
 impl Money {
-    fn new(value: i32) -> Self {
-        Money(value)
+    /// Parses a decimal amount such as "-1234.505" without going through
+    /// floats, rounding anything past the cents with the given mode.
+    pub fn parse(value: &str, rounding: RoundingMode) -> Result<Money, MoneyError> {
+        let invalid = || MoneyError::Invalid(value.to_string());
+        let trimmed = value.trim();
+        let (negative, digits) = match trimmed.chars().next() {
+            Some('-') => (true, &trimmed[1..]),
+            Some('+') => (false, &trimmed[1..]),
+            _ => (false, trimmed)
+        };
+
+        let mut parts = digits.splitn(2, '.');
+        let units = parts.next().unwrap_or("");
+        let decimals = parts.next().unwrap_or("");
+        if units.is_empty() && decimals.is_empty() {
+            return Err(invalid());
+        }
+        if !units.chars().chain(decimals.chars()).all(|digit| digit.is_ascii_digit()) {
+            return Err(invalid());
+        }
+
+        let mut numerator: i128 = 0;
+        let mut denominator: i128 = 1;
+        for digit in units.chars().chain(decimals.chars()) {
+            numerator = numerator
+                .checked_mul(10)
+                .and_then(|number| number.checked_add(digit.to_digit(10).unwrap() as i128))
+                .ok_or(MoneyError::Overflow)?;
+        }
+        for _ in 0..decimals.len() {
+            denominator = denominator.checked_mul(10).ok_or(MoneyError::Overflow)?;
+        }
+        if negative {
+            numerator = -numerator;
+        }
+
+        let cents = numerator
+            .checked_mul(100)
+            .and_then(|numerator| rounding.divide(numerator, denominator))
+            .ok_or(MoneyError::Overflow)?;
+        Self::from_i128(cents).ok_or(MoneyError::Overflow)
+    }
+
+    fn from_i128(cents: i128) -> Option<Money> {
+        if cents > i64::max_value() as i128 || cents < i64::min_value() as i128 {
+            None
+        } else {
+            Some(Money(cents as i64))
+        }
+    }
+
+    pub fn checked_add(&self, other: &Money) -> Option<Money> {
+        self.0.checked_add(other.0).map(Money)
+    }
+
+    pub fn checked_sub(&self, other: &Money) -> Option<Money> {
+        self.0.checked_sub(other.0).map(Money)
     }
 
-    fn to_i32_from_str(value: &str) -> Result<i32, ParseFloatError> {
-        let float_value = value.parse::<f64>()?;
-        Ok(Self::to_i32_from_f64(float_value))
+    /// Adds up the amounts, None when the total doesn't fit.
+    pub fn checked_sum<'a, I: IntoIterator<Item=&'a Money>>(amounts: I) -> Option<Money> {
+        amounts
+            .into_iter()
+            .try_fold(Money(0), |total, amount| total.checked_add(amount))
     }
 
-    fn to_i32_from_f64(value: f64) -> i32 {
-        (value * 100.0).round()  as i32
+    /// Multiplies by `numerator / denominator` with a single rounding at
+    /// the end.
+    pub fn checked_mul_div(&self, numerator: i128, denominator: i128, rounding: RoundingMode) -> Option<Money> {
+        (self.0 as i128)
+            .checked_mul(numerator)
+            .and_then(|product| rounding.divide(product, denominator))
+            .and_then(Self::from_i128)
     }
 
-    fn to_f64(&self) -> f64 {
-        self.0 as f64 / 100.0
+    /// The amount for `quantity` units priced at this amount.
+    pub fn times(&self, quantity: f64, rounding: RoundingMode) -> Option<Money> {
+        let scaled_quantity = (quantity * QUANTITY_SCALE as f64).round();
+        if !scaled_quantity.is_finite() {
+            return None;
+        }
+        self.checked_mul_div(scaled_quantity as i128, QUANTITY_SCALE, rounding)
+    }
+
+    /// The share of the amount that corresponds to `part` out of `whole`
+    /// units.
+    pub fn prorate(&self, part: f64, whole: f64, rounding: RoundingMode) -> Option<Money> {
+        let scaled_part = (part * QUANTITY_SCALE as f64).round();
+        let scaled_whole = (whole * QUANTITY_SCALE as f64).round();
+        if !scaled_part.is_finite() || !scaled_whole.is_finite() {
+            return None;
+        }
+        self.checked_mul_div(scaled_part as i128, scaled_whole as i128, rounding)
+    }
+
+    /// Applies a percentage stored as Money, where 12% is `Money(1200)`.
+    pub fn percentage(&self, percentage: &Money, rounding: RoundingMode) -> Option<Money> {
+        self.checked_mul_div(percentage.0 as i128, PERCENTAGE_SCALE, rounding)
+    }
+
+    /// Converts the amount with an exchange rate, rounding to the nearest
+    /// cent.
+    pub fn convert(&self, rate: f64) -> Option<Money> {
+        let scaled_rate = (rate * RATE_SCALE as f64).round();
+        if !scaled_rate.is_finite() {
+            return None;
+        }
+        self.checked_mul_div(scaled_rate as i128, RATE_SCALE, RoundingMode::from_env())
+    }
+
+    /// Renders the amount the way the currency writes it, with its symbol,
+    /// thousands grouping and always two decimals, e.g. "$ 1.234,50".
+    pub fn format(&self, currency: &Currency) -> String {
+        let cents = (self.0 as i128).abs();
+        let units = (cents / 100).to_string();
+
+        let mut grouped = String::new();
+        for (index, digit) in units.chars().enumerate() {
+            if index > 0 && (units.len() - index) % 3 == 0 {
+                grouped.push_str(&currency.thousands_separator);
+            }
+            grouped.push(digit);
+        }
+
+        format!("{} {}{}{}{:02}",
+                currency.symbol,
+                if self.0 < 0 { "-" } else { "" },
+                grouped,
+                currency.decimal_point,
+                cents % 100)
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cents = (self.0 as i128).abs();
+        let sign = if self.0 < 0 { "-" } else { "" };
+        match cents % 100 {
+            0 => write!(f, "{}{}", sign, cents / 100),
+            decimals if decimals % 10 == 0 => write!(f, "{}{}.{}", sign, cents / 100, decimals / 10),
+            decimals => write!(f, "{}{}.{:02}", sign, cents / 100, decimals)
+        }
     }
 }
 
@@ -46,10 +246,8 @@ impl<'de> Deserialize<'de> for Money {
             where
                 E: de::Error,
             {
-                let parsed_value = Money::to_i32_from_str(value)
-                    .map_err(|_val| de::Error::invalid_value(Unexpected::Str(value), &self))?;
-
-                Ok(Money::new(parsed_value))
+                Money::parse(value, RoundingMode::from_env())
+                    .map_err(|_val| de::Error::invalid_value(Unexpected::Str(value), &self))
             }
 
         }
@@ -63,7 +261,7 @@ impl Serialize for Money {
     where
         S: Serializer,
     {
-        serializer.serialize_str(&format!("{}", &self.to_f64()))
+        serializer.serialize_str(&self.to_string())
     }
 }
 
@@ -77,72 +275,3 @@ impl<'v> FromFormValue<'v> for Money {
         }
     }
 }
-
-/* Arithmetic Operations */
-use std::ops::Mul;
-use std::ops::Sub;
-use std::ops::Add;
-use std::ops::Div;
-use std::iter::Sum;
-
-impl Mul for Money {
-    type Output = Self;
-
-    fn mul(self, other: Self) -> Self {
-        Money(self.0 * other.0)
-    }
-}
-
-impl Mul<f64> for Money {
-    type Output = Self;
-
-    fn mul(self, other: f64) -> Self {
-        Money((self.0 as f64 * other) as i32)
-    }
-}
-
-impl Sub for Money {
-    type Output = Self;
-
-    fn sub(self, other: Self) -> Self {
-        Money(self.0 - other.0)
-    }
-}
-
-impl Add for Money {
-    type Output = Self;
-
-    fn add(self, other: Self) -> Self {
-        Money(self.0 + other.0)
-    }
-}
-
-impl<'a> Add<&'a Money> for Money {
-    type Output = Self;
-
-    fn add(self, other: &'a Self) -> Self {
-        Money(self.0 + other.0)
-    }
-}
-
-impl Div for Money {
-    type Output = Self;
-
-    fn div(self, other: Self) -> Self {
-        Money(self.0 / other.0)
-    }
-}
-
-impl Div<i32> for Money {
-    type Output = Self;
-
-    fn div(self, other: i32) -> Self {
-        Money(self.0 / other)
-    }
-}
-
-impl<'a> Sum<&'a Money> for Money {
-    fn sum<I: Iterator<Item=&'a Money>>(iter: I) -> Money {
-        iter.fold(Money(0), Add::add)
-    }
-}
@@ -6,6 +6,12 @@ use rocket::request::FromFormValue;
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct NaiveDateForm(NaiveDate);
 
+impl NaiveDateForm {
+    pub fn naive_date(&self) -> NaiveDate {
+        self.0
+    }
+}
+
 impl<'v> FromFormValue<'v> for NaiveDateForm {
     type Error = &'v RawStr;
 
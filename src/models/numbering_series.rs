@@ -0,0 +1,118 @@
+use diesel;
+use diesel::prelude::*;
+use diesel::query_builder::AsChangeset;
+use diesel::sql_types;
+use diesel::pg::PgConnection;
+use crate::schema;
+use crate::schema::numbering_series;
+use crate::handlers::base::Search;
+use crate::basic_model_actions;
+
+type BoxedQuery<'a> =
+    diesel::query_builder::BoxedSelectStatement<'a, (sql_types::Integer,
+                                                     sql_types::Text,
+                                                     sql_types::Text,
+                                                     sql_types::Integer,
+                                                     sql_types::Integer,
+                                                     sql_types::Bool),
+                                                     schema::numbering_series::table, diesel::pg::Pg>;
+
+#[derive(Serialize, Deserialize, Queryable, Eq, PartialEq, Hash,
+         Debug, Clone, FromForm, FromData, Responder)]
+pub struct NumberingSeries {
+    pub id: i32,
+    pub name: String,
+    pub prefix: String,
+    pub padding: i32,
+    pub next_number: i32,
+    pub default_series: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, FromForm, FromData)]
+pub struct SearchNumberingSeries {
+    pub id: Option<i32>,
+    pub name: Option<String>,
+    pub prefix: Option<String>,
+    pub default_series: Option<bool>
+}
+
+#[derive(Serialize, Deserialize, Insertable, Eq, PartialEq, Hash,
+         Debug, FromData, Responder)]
+#[table_name="numbering_series"]
+pub struct NewNumberingSeries {
+    pub name: String,
+    pub prefix: String,
+    pub padding: i32,
+    pub next_number: Option<i32>,
+    pub default_series: Option<bool>
+}
+
+/// Updates leave `next_number` alone, only `take_number` moves it forward.
+impl<'a> AsChangeset for &'a NumberingSeries {
+    type Target = numbering_series::table;
+    type Changeset = <(diesel::dsl::Eq<numbering_series::name, &'a String>,
+                       diesel::dsl::Eq<numbering_series::prefix, &'a String>,
+                       diesel::dsl::Eq<numbering_series::padding, &'a i32>,
+                       diesel::dsl::Eq<numbering_series::default_series, &'a bool>) as AsChangeset>::Changeset;
+
+    fn as_changeset(self) -> Self::Changeset {
+        (numbering_series::name.eq(&self.name),
+         numbering_series::prefix.eq(&self.prefix),
+         numbering_series::padding.eq(&self.padding),
+         numbering_series::default_series.eq(&self.default_series)).as_changeset()
+    }
+}
+
+impl NumberingSeries {
+    /// Takes the next number of the series, or of the default series when
+    /// none is given, along with the series it came from. The counter row
+    /// stays locked until the caller's transaction ends, so a rollback gives
+    /// the number back.
+    pub fn take_number(connection: &PgConnection, series_id: Option<i32>) -> Result<(i32, String), diesel::result::Error> {
+        use crate::schema::numbering_series::dsl::*;
+
+        let series_id = match series_id {
+            Some(series_id) => series_id,
+            None => numbering_series
+                .filter(default_series.eq(true))
+                .select(id)
+                .first::<i32>(connection)?
+        };
+
+        let series = diesel::update(numbering_series.find(series_id))
+            .set(next_number.eq(next_number + 1))
+            .get_result::<NumberingSeries>(connection)?;
+
+        Ok((series.id, series.format(series.next_number - 1)))
+    }
+
+    pub fn format(&self, number: i32) -> String {
+        format!("{}{:0width$}", self.prefix, number, width = self.padding as usize)
+    }
+
+    fn searching_records<'a>(search: Option<Search<SearchNumberingSeries>>) -> BoxedQuery<'a> {
+        use crate::schema::numbering_series::dsl::*;
+
+        let mut query = schema::numbering_series::table.into_boxed::<diesel::pg::Pg>();
+
+        if let Some(search_numbering_series) = search {
+            let Search(series) = search_numbering_series;
+            if let Some(series_id) = series.id {
+                query = query.filter(id.eq(series_id));
+            }
+            if let Some(series_name) = series.name {
+                query = query.filter(name.like(series_name));
+            }
+            if let Some(series_prefix) = series.prefix {
+                query = query.filter(prefix.eq(series_prefix));
+            }
+            if let Some(series_default_series) = series.default_series {
+                query = query.filter(default_series.eq(series_default_series));
+            }
+        }
+
+        query
+    }
+}
+
+basic_model_actions!(numbering_series, NumberingSeries, NewNumberingSeries, SearchNumberingSeries);
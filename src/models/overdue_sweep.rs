@@ -0,0 +1,60 @@
+use diesel;
+use diesel::prelude::*;
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use diesel::pg::PgConnection;
+use crate::models::clock::Clock;
+use crate::models::sale_status::SaleStatus;
+use crate::models::sale_status::SaleStatusError;
+use crate::schema::overdue_sweeps;
+
+const SWEEP_ACTOR: &str = "overdue_sweep";
+
+#[derive(Serialize, Deserialize, Clone, Queryable, Debug)]
+pub struct OverdueSweep {
+    pub id: i32,
+    pub reference_date: NaiveDate,
+    pub sale_ids: Vec<i32>,
+    pub swept_at: NaiveDateTime
+}
+
+#[derive(Insertable, Debug)]
+#[table_name="overdue_sweeps"]
+pub struct NewOverdueSweep {
+    pub reference_date: NaiveDate,
+    pub sale_ids: Vec<i32>
+}
+
+impl OverdueSweep {
+    /// Moves every sale that can become `Overdue` and whose `expiring_date`
+    /// is before the clock's current date, and records which sales were
+    /// touched.
+    pub fn run<C: Clock>(connection: &PgConnection, clock: &C) -> Result<OverdueSweep, SaleStatusError> {
+        use crate::schema::sales::dsl;
+        let today = clock.today();
+
+        connection.transaction(|| {
+            let expired_sale_ids = dsl::sales
+                .filter(dsl::status.eq_any(SaleStatus::statuses_reaching(SaleStatus::Overdue)))
+                .filter(dsl::expiring_date.lt(today))
+                .select(dsl::id)
+                .order(dsl::id)
+                .load::<i32>(connection)?;
+
+            for sale_id in &expired_sale_ids {
+                SaleStatus::apply_transition(connection, *sale_id, SaleStatus::Overdue, SWEEP_ACTOR)?;
+            }
+
+            let new_overdue_sweep = NewOverdueSweep {
+                reference_date: today,
+                sale_ids: expired_sale_ids
+            };
+
+            let overdue_sweep = diesel::insert_into(overdue_sweeps::table)
+                .values(&new_overdue_sweep)
+                .get_result::<OverdueSweep>(connection)?;
+
+            Ok(overdue_sweep)
+        })
+    }
+}
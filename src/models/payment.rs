@@ -0,0 +1,185 @@
+use std::fmt;
+use diesel;
+use diesel::prelude::*;
+use diesel::dsl::sql;
+use diesel::sql_types;
+use diesel::pg::PgConnection;
+use crate::models::naive_date_form::NaiveDateForm;
+use crate::models::money::Money;
+use crate::models::sale::Sale;
+use crate::models::credit_note::CreditNote;
+use crate::models::sale_status::SaleStatus;
+use crate::models::sale_status::SaleStatusError;
+use crate::schema::payments;
+use rocket::http::RawStr;
+use rocket::request::FromFormValue;
+use std::default::Default;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, DbEnum)]
+pub enum PaymentMethod {
+    Cash,
+    Card,
+    Transfer,
+    Check,
+    Other,
+}
+
+impl Default for PaymentMethod {
+    fn default() -> PaymentMethod {
+        PaymentMethod::Cash
+    }
+}
+
+impl<'v> FromFormValue<'v> for PaymentMethod {
+    type Error = &'v RawStr;
+
+    fn from_form_value(form_value: &'v RawStr) -> Result<PaymentMethod, &'v RawStr> {
+        match form_value.as_str() {
+            "cash" => Ok(PaymentMethod::Cash),
+            "card" => Ok(PaymentMethod::Card),
+            "transfer" => Ok(PaymentMethod::Transfer),
+            "check" => Ok(PaymentMethod::Check),
+            "other" => Ok(PaymentMethod::Other),
+            _ => Err(form_value),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Queryable, Debug)]
+pub struct Payment {
+    pub id: i32,
+    pub sale_id: i32,
+    pub payment_date: NaiveDateForm,
+    pub amount: Money,
+    pub currency_id: i32,
+    pub payment_method: PaymentMethod,
+    pub observation: Option<String>
+}
+
+#[derive(Insertable, Serialize, Deserialize, Clone, Debug, FromData)]
+#[table_name="payments"]
+pub struct NewPayment {
+    pub sale_id: Option<i32>,
+    pub payment_date: NaiveDateForm,
+    pub amount: Money,
+    pub currency_id: Option<i32>,
+    pub payment_method: Option<PaymentMethod>,
+    pub observation: Option<String>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "error", rename_all = "snake_case")]
+pub enum PaymentError {
+    NotFound { sale_id: i32 },
+    NotPayable { sale_id: i32, status: SaleStatus },
+    InvalidAmount { amount: Money },
+    CurrencyMismatch { currency_id: i32, sale_currency_id: i32 },
+    Overpayment { sale_id: i32, left_to_pay: Money },
+    Database { message: String },
+}
+
+impl fmt::Display for PaymentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PaymentError::NotFound { sale_id } =>
+                write!(f, "Sale {} not found", sale_id),
+            PaymentError::NotPayable { sale_id, status } =>
+                write!(f, "Sale {} is {:?} and isn't open for payments", sale_id, status),
+            PaymentError::InvalidAmount { amount } =>
+                write!(f, "Can't pay {}, amounts must be positive", amount.0),
+            PaymentError::CurrencyMismatch { currency_id, sale_currency_id } =>
+                write!(f, "Payment currency {} doesn't match the sale currency {}", currency_id, sale_currency_id),
+            PaymentError::Overpayment { sale_id, left_to_pay } =>
+                write!(f, "Only {} left to pay for sale {}", left_to_pay.0, sale_id),
+            PaymentError::Database { message } =>
+                write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<diesel::result::Error> for PaymentError {
+    fn from(error: diesel::result::Error) -> Self {
+        PaymentError::Database { message: error.to_string() }
+    }
+}
+
+impl From<SaleStatusError> for PaymentError {
+    fn from(error: SaleStatusError) -> Self {
+        PaymentError::Database { message: error.to_string() }
+    }
+}
+
+impl Payment {
+    pub fn list(connection: &PgConnection, sale_id: i32) -> Result<Vec<Payment>, diesel::result::Error> {
+        use crate::schema::payments::dsl;
+
+        dsl::payments
+            .filter(dsl::sale_id.eq(sale_id))
+            .order(dsl::payment_date)
+            .load::<Payment>(connection)
+    }
+
+    /// Pays part or all of what's left on the sale. The sale stays locked
+    /// until the payment is in, so payments made at the same time can't
+    /// both fit in what was left and overpay it together.
+    pub fn create(connection: &PgConnection, sale_id: i32, mut new_payment: NewPayment, changed_by: &str) -> Result<Payment, PaymentError> {
+        use crate::schema::sales;
+
+        connection.transaction(|| {
+            let sale = sales::dsl::sales
+                .find(sale_id)
+                .for_update()
+                .get_result::<Sale>(connection)
+                .map_err(|error| match error {
+                    diesel::result::Error::NotFound => PaymentError::NotFound { sale_id },
+                    error => PaymentError::from(error),
+                })?;
+
+            if !SaleStatus::open_statuses().contains(&sale.status) {
+                return Err(PaymentError::NotPayable { sale_id, status: sale.status });
+            }
+
+            if new_payment.amount.0 <= 0 {
+                return Err(PaymentError::InvalidAmount { amount: new_payment.amount });
+            }
+
+            let payed = Self::payed_amount(connection, sale_id)?;
+            let credited = CreditNote::credited_amount(connection, sale_id)?;
+
+            let left_to_pay = sale.total.0 as i128 - payed.0 as i128 - credited.0 as i128;
+            if new_payment.amount.0 as i128 > left_to_pay {
+                return Err(PaymentError::Overpayment { sale_id, left_to_pay: Money(left_to_pay.max(0) as i64) });
+            }
+
+            match new_payment.currency_id {
+                Some(payment_currency_id) if payment_currency_id != sale.currency_id => {
+                    return Err(PaymentError::CurrencyMismatch { currency_id: payment_currency_id, sale_currency_id: sale.currency_id });
+                },
+                _ => new_payment.currency_id = Some(sale.currency_id)
+            }
+
+            new_payment.sale_id = Some(sale_id);
+
+            let payment = diesel::insert_into(payments::table)
+                .values(&new_payment)
+                .get_result::<Payment>(connection)?;
+
+            if new_payment.amount.0 as i128 >= left_to_pay {
+                SaleStatus::apply_transition(connection, sale_id, SaleStatus::Payed, changed_by)?;
+            }
+
+            Ok(payment)
+        })
+    }
+
+    /// Added up by the database, which fails instead of wrapping around
+    /// when the total doesn't fit.
+    pub fn payed_amount(connection: &PgConnection, sale_id: i32) -> Result<Money, diesel::result::Error> {
+        use crate::schema::payments::dsl;
+
+        dsl::payments
+            .filter(dsl::sale_id.eq(sale_id))
+            .select(sql::<sql_types::BigInt>("COALESCE(SUM(amount), 0)::BIGINT"))
+            .first::<Money>(connection)
+    }
+}
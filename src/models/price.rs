@@ -3,31 +3,40 @@ use diesel::prelude::*;
 use diesel::sql_types;
 use crate::schema;
 use crate::schema::prices;
+use crate::models::money::Money;
 use crate::handlers::base::Search;
 use crate::basic_model_actions;
 
 type BoxedQuery<'a> = 
-    diesel::query_builder::BoxedSelectStatement<'a, (sql_types::Integer, sql_types::Text),
+    diesel::query_builder::BoxedSelectStatement<'a, (sql_types::Integer, sql_types::Text, sql_types::Nullable<sql_types::Integer>,
+                                                     sql_types::Nullable<sql_types::BigInt>),
                                                      schema::prices::table, diesel::pg::Pg>;
 
+/// A price list. With a `markup` its prices are worked out as the product
+/// cost plus that percentage whenever the cost changes.
 #[derive(Serialize, Deserialize, Queryable, Eq, PartialEq, Hash, 
          Debug, Clone, AsChangeset, FromForm, FromData, Responder)]
 pub struct Price {
     pub id: i32,
-    pub name: String
+    pub name: String,
+    pub currency_id: Option<i32>,
+    pub markup: Option<Money>
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, FromForm, Responder)]
 pub struct SearchPrice {
     pub id: Option<i32>,
-    pub name: Option<String>
+    pub name: Option<String>,
+    pub currency_id: Option<i32>
 }
 
 #[derive(Serialize, Deserialize, Insertable, Eq, PartialEq, Hash, 
          Debug, FromData, Responder)]
 #[table_name="prices"]
 pub struct NewPrice {
-    pub name: String
+    pub name: String,
+    pub currency_id: Option<i32>,
+    pub markup: Option<Money>
 }
 
 impl Price {
@@ -45,6 +54,9 @@ impl Price {
             if let Some(price_id) = price.id {
                 query = query.filter(id.eq(price_id));
             }
+            if let Some(price_currency_id) = price.currency_id {
+                query = query.filter(currency_id.eq(price_currency_id));
+            }
         }
 
         query
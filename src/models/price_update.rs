@@ -0,0 +1,146 @@
+use std::fmt;
+use diesel;
+use diesel::prelude::*;
+use diesel::pg::PgConnection;
+use chrono::NaiveDate;
+use crate::models::money::{ Money, RoundingMode };
+use crate::models::product::Product;
+use crate::models::product::SearchProduct;
+use crate::models::product_price::ProductPrice;
+use crate::models::product_price::EditableProductPrice;
+use crate::models::clock::{ Clock, SystemClock };
+use crate::handlers::base::Search;
+
+/// Changes every price of a price list, for the products matching `search`,
+/// by a percentage or a fixed amount. The new prices are rounded to a
+/// multiple of `round_to` and take effect on `valid_from`, today when left
+/// out. A dry run only returns the changes.
+#[derive(Serialize, Deserialize, Debug, FromData)]
+pub struct PriceUpdate {
+    pub percentage: Option<Money>,
+    pub amount: Option<Money>,
+    #[serde(default)]
+    pub search: Option<SearchProduct>,
+    #[serde(default)]
+    pub rounding: Option<RoundingMode>,
+    #[serde(default)]
+    pub round_to: Option<Money>,
+    #[serde(default)]
+    pub valid_from: Option<NaiveDate>,
+    #[serde(default)]
+    pub dry_run: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PriceChange {
+    pub product_id: i32,
+    pub name: String,
+    pub old_price: Money,
+    pub new_price: Money
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "error", rename_all = "snake_case")]
+pub enum PriceUpdateError {
+    InvalidChange { reason: String },
+    InvalidPrice { product_id: i32 },
+    Database { message: String },
+}
+
+impl fmt::Display for PriceUpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PriceUpdateError::InvalidChange { reason } =>
+                write!(f, "{}", reason),
+            PriceUpdateError::InvalidPrice { product_id } =>
+                write!(f, "The new price of product {} would be negative or too large", product_id),
+            PriceUpdateError::Database { message } =>
+                write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<diesel::result::Error> for PriceUpdateError {
+    fn from(error: diesel::result::Error) -> Self {
+        PriceUpdateError::Database { message: error.to_string() }
+    }
+}
+
+impl PriceUpdate {
+    /// Works out the changes and, unless it's a dry run, saves them as new
+    /// price versions, all or none.
+    pub fn run(self, connection: &PgConnection, price_id: i32) -> Result<Vec<PriceChange>, PriceUpdateError> {
+        let valid_from = self.valid_from.unwrap_or_else(|| SystemClock.today());
+
+        connection.transaction(|| {
+            let price_changes = self.price_changes(connection, price_id, valid_from)?;
+
+            if !self.dry_run {
+                for price_change in &price_changes {
+                    let editable_product_price = EditableProductPrice {
+                        product_id: None,
+                        price_id,
+                        price: price_change.new_price.0,
+                        valid_from: Some(valid_from),
+                        valid_to: None,
+                        variant_id: None
+                    };
+                    ProductPrice::batch_action(connection, vec![editable_product_price], price_change.product_id)?;
+                }
+            }
+
+            Ok(price_changes)
+        })
+    }
+
+    fn price_changes(&self, connection: &PgConnection, price_id: i32, valid_from: NaiveDate) -> Result<Vec<PriceChange>, PriceUpdateError> {
+        let search = self.search.clone().map(Search);
+        let mut price_changes = vec![];
+
+        for product in Product::search(connection, search)? {
+            if let Some(version) = ProductPrice::version(connection, product.id, price_id, valid_from)? {
+                let old_price = Money(version.price);
+                let new_price = self.new_price(&old_price)?
+                    .filter(|new_price| new_price.0 >= 0)
+                    .ok_or(PriceUpdateError::InvalidPrice { product_id: product.id })?;
+
+                price_changes.push(PriceChange {
+                    product_id: product.id,
+                    name: product.name,
+                    old_price,
+                    new_price
+                });
+            }
+        }
+
+        Ok(price_changes)
+    }
+
+    /// The changed price, None when it doesn't fit.
+    fn new_price(&self, old_price: &Money) -> Result<Option<Money>, PriceUpdateError> {
+        let rounding = self.rounding.unwrap_or_else(RoundingMode::from_env);
+
+        let changed = match (&self.percentage, &self.amount) {
+            (Some(percentage), None) =>
+                old_price.percentage(percentage, rounding).and_then(|change| old_price.checked_add(&change)),
+            (None, Some(amount)) => old_price.checked_add(amount),
+            _ => return Err(PriceUpdateError::InvalidChange {
+                reason: "The change needs either a percentage or an amount".to_string()
+            })
+        };
+
+        match &self.round_to {
+            Some(round_to) if round_to.0 <= 0 => Err(PriceUpdateError::InvalidChange {
+                reason: "Prices can only be rounded to a positive amount".to_string()
+            }),
+            Some(round_to) => Ok(changed.and_then(|changed| {
+                rounding
+                    .divide(changed.0 as i128, round_to.0 as i128)
+                    .map(|steps| steps * round_to.0 as i128)
+                    .filter(|rounded| *rounded >= 0 && *rounded <= i64::max_value() as i128)
+                    .map(|rounded| Money(rounded as i64))
+            })),
+            None => Ok(changed)
+        }
+    }
+}
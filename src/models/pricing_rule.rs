@@ -0,0 +1,170 @@
+use diesel;
+use diesel::prelude::*;
+use diesel::sql_types;
+use diesel::pg::PgConnection;
+use crate::schema;
+use crate::schema::pricing_rules;
+use crate::models::money::{ Money, RoundingMode };
+use crate::models::naive_date_form::NaiveDateForm;
+use crate::models::sale_product::NewSaleProduct;
+use crate::models::category::Category;
+use crate::handlers::base::Search;
+use crate::basic_model_actions;
+
+type BoxedQuery<'a> =
+    diesel::query_builder::BoxedSelectStatement<'a, (sql_types::Integer,
+                                                     sql_types::Text,
+                                                     PricingRuleKindMapping,
+                                                     sql_types::Nullable<sql_types::Integer>,
+                                                     sql_types::Nullable<sql_types::Date>,
+                                                     sql_types::Nullable<sql_types::Date>,
+                                                     sql_types::Nullable<sql_types::BigInt>,
+                                                     sql_types::Nullable<sql_types::Double>,
+                                                     sql_types::Nullable<sql_types::Double>,
+                                                     sql_types::Nullable<sql_types::Double>,
+                                                     sql_types::Nullable<sql_types::BigInt>,
+                                                     sql_types::Bool,
+                                                     sql_types::Nullable<sql_types::Integer>),
+                                                     schema::pricing_rules::table, diesel::pg::Pg>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, DbEnum)]
+pub enum PricingRuleKind {
+    PercentageDiscount,
+    MultiBuy,
+    VolumePrice,
+}
+
+/// A promotion applied to sale lines while it's in force: a percentage off,
+/// buy `buy_amount` pay `pay_amount`, or a lower price from `min_amount`
+/// units up. It applies to one product, to a category and the categories
+/// below it, or to every product when it names neither.
+#[derive(Serialize, Deserialize, Queryable,
+         Debug, Clone, AsChangeset, FromData, Responder)]
+#[table_name="pricing_rules"]
+pub struct PricingRule {
+    pub id: i32,
+    pub name: String,
+    pub kind: PricingRuleKind,
+    pub product_id: Option<i32>,
+    pub starts_on: Option<NaiveDateForm>,
+    pub ends_on: Option<NaiveDateForm>,
+    pub percentage: Option<Money>,
+    pub buy_amount: Option<f64>,
+    pub pay_amount: Option<f64>,
+    pub min_amount: Option<f64>,
+    pub price: Option<Money>,
+    pub active: bool,
+    pub category_id: Option<i32>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, FromForm, FromData)]
+pub struct SearchPricingRule {
+    pub id: Option<i32>,
+    pub name: Option<String>,
+    pub product_id: Option<i32>,
+    pub category_id: Option<i32>,
+    pub active: Option<bool>
+}
+
+#[derive(Serialize, Deserialize, Insertable, Debug, FromData, Responder)]
+#[table_name="pricing_rules"]
+pub struct NewPricingRule {
+    pub name: String,
+    pub kind: PricingRuleKind,
+    pub product_id: Option<i32>,
+    pub starts_on: Option<NaiveDateForm>,
+    pub ends_on: Option<NaiveDateForm>,
+    pub percentage: Option<Money>,
+    pub buy_amount: Option<f64>,
+    pub pay_amount: Option<f64>,
+    pub min_amount: Option<f64>,
+    pub price: Option<Money>,
+    pub active: Option<bool>,
+    pub category_id: Option<i32>
+}
+
+impl PricingRule {
+    /// Active rules for the product, for a category it falls under, or for
+    /// every product, in force on `date`.
+    pub fn applicable(connection: &PgConnection, param_product_id: i32, date: &NaiveDateForm) -> Result<Vec<PricingRule>, diesel::result::Error> {
+        use crate::schema::pricing_rules::dsl::*;
+
+        let category_ids = Category::ancestor_ids(connection, param_product_id)?;
+
+        pricing_rules
+            .filter(active.eq(true))
+            .filter(product_id.is_null().or(product_id.eq(param_product_id)))
+            .filter(category_id.is_null().or(category_id.eq_any(category_ids)))
+            .filter(starts_on.is_null().or(starts_on.le(date)))
+            .filter(ends_on.is_null().or(ends_on.ge(date)))
+            .order(id)
+            .load::<PricingRule>(connection)
+    }
+
+    /// The line with the rule applied and recorded on it, None when the
+    /// rule doesn't apply to it. Rule amounts and prices are in the
+    /// product's unit: amounts are checked against `product_amount`, all of
+    /// the product the sale takes counted in that unit, free units are
+    /// shared among the product's lines, and rule prices are scaled by
+    /// `unit_factor` to the unit the line is sold in.
+    pub fn apply(&self, sale_product: &NewSaleProduct, unit_factor: f64, product_amount: f64) -> Option<NewSaleProduct> {
+        let mut priced = sale_product.clone();
+
+        match self.kind {
+            PricingRuleKind::PercentageDiscount => {
+                priced.discount = Some(self.percentage.clone()?);
+            },
+            PricingRuleKind::MultiBuy => {
+                let (buy_amount, pay_amount) = (self.buy_amount?, self.pay_amount?);
+                if buy_amount <= 0.0 || pay_amount >= buy_amount {
+                    return None;
+                }
+
+                let free_amount = (product_amount / buy_amount).floor() * (buy_amount - pay_amount);
+                if free_amount <= 0.0 {
+                    return None;
+                }
+                let line_free_amount = free_amount * sale_product.amount / product_amount;
+                priced.discount_amount = Some(sale_product.price.as_ref()?.times(line_free_amount, RoundingMode::from_env())?);
+            },
+            PricingRuleKind::VolumePrice => {
+                if product_amount < self.min_amount? {
+                    return None;
+                }
+                priced.price = Some(self.price.as_ref()?.times(unit_factor, RoundingMode::from_env())?);
+            }
+        }
+
+        priced.pricing_rule_id = Some(self.id);
+        Some(priced)
+    }
+
+    fn searching_records<'a>(search: Option<Search<SearchPricingRule>>) -> BoxedQuery<'a> {
+        use crate::schema::pricing_rules::dsl::*;
+
+        let mut query = schema::pricing_rules::table.into_boxed::<diesel::pg::Pg>();
+
+        if let Some(search_pricing_rule) = search {
+            let Search(pricing_rule) = search_pricing_rule;
+            if let Some(pricing_rule_id) = pricing_rule.id {
+                query = query.filter(id.eq(pricing_rule_id));
+            }
+            if let Some(pricing_rule_name) = pricing_rule.name {
+                query = query.filter(name.like(pricing_rule_name));
+            }
+            if let Some(pricing_rule_product_id) = pricing_rule.product_id {
+                query = query.filter(product_id.eq(pricing_rule_product_id));
+            }
+            if let Some(pricing_rule_category_id) = pricing_rule.category_id {
+                query = query.filter(category_id.eq(pricing_rule_category_id));
+            }
+            if let Some(pricing_rule_active) = pricing_rule.active {
+                query = query.filter(active.eq(pricing_rule_active));
+            }
+        }
+
+        query
+    }
+}
+
+basic_model_actions!(pricing_rules, PricingRule, NewPricingRule, SearchPricingRule);
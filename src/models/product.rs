@@ -2,7 +2,7 @@ use diesel;
 use diesel::prelude::*;
 use diesel::sql_types;
 use crate::handlers::base::Search;
-use crate::models::db_connection::*;
+use diesel::pg::PgConnection;
 use crate::models::product_price::ProductPrice;
 use crate::models::product_price::EditableProductPrice;
 use crate::models::product_price::FullProductPrice;
@@ -12,6 +12,18 @@ use crate::models::product_cost::EditableProductCost;
 use crate::models::product_cost::FullProductCost;
 use crate::models::cost::Cost;
 use crate::models::supplier::Supplier;
+use crate::models::tax::Tax;
+use crate::models::product_tax::ProductTax;
+use crate::models::category::Category;
+use crate::models::product_category::ProductCategory;
+use crate::models::product_barcode::ProductBarcode;
+use crate::models::product_variant::{ ProductVariant, FullProductVariant };
+use crate::models::stock_movement::StockMovement;
+use crate::models::unit::Unit;
+use crate::models::unit::BasicModelActions;
+use crate::models::currency::Currency;
+use crate::models::money::Money;
+use crate::models::clock::{ Clock, SystemClock };
 use crate::schema;
 use crate::schema::products;
 
@@ -21,16 +33,18 @@ pub struct Product {
     pub name: String,
     pub description: Option<String>,
     pub stock: Option<f64>,
-    pub code: Option<String>
+    pub code: Option<String>,
+    pub unit_id: Option<i32>
 }
 
-#[derive(Serialize, Deserialize, Debug, FromForm, Responder)]
+#[derive(Serialize, Deserialize, Debug, Clone, FromForm, Responder)]
 pub struct SearchProduct {
     pub id: Option<i32>,
     pub name: Option<String>,
     pub description: Option<String>,
     pub stock: Option<f64>,
-    pub code: Option<String>
+    pub code: Option<String>,
+    pub category_id: Option<i32>
 }
 
 #[derive(Serialize, Deserialize, Insertable, Debug, Clone, Responder)]
@@ -38,21 +52,87 @@ pub struct SearchProduct {
 pub struct NewProduct {
     pub name: String,
     pub description: Option<String>,
-    pub code: Option<String>
+    pub code: Option<String>,
+    pub unit_id: Option<i32>
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, FromData)]
 pub struct FullNewProduct {
     product: NewProduct,
     prices: Vec<EditableProductPrice>,
-    costs: Vec<EditableProductCost>
+    costs: Vec<EditableProductCost>,
+    #[serde(default)]
+    taxes: Option<Vec<i32>>,
+    #[serde(default)]
+    categories: Option<Vec<i32>>,
+    #[serde(default)]
+    barcodes: Option<Vec<String>>
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FullProduct {
     pub product: Product,
     pub prices: Vec<FullProductPrice>,
-    pub costs: Vec<FullProductCost>
+    pub costs: Vec<FullProductCost>,
+    pub taxes: Vec<Tax>,
+    pub categories: Vec<Category>,
+    pub variants: Vec<FullProductVariant>,
+    pub unit: Option<Unit>,
+    pub barcodes: Vec<String>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "error", rename_all = "snake_case")]
+pub enum ProductError {
+    InvalidBarcode { code: String },
+    DuplicateCode { code: String, product_id: Option<i32> },
+    RepeatedCode { code: String },
+    UnitLocked { product_id: i32 },
+    Database { message: String },
+}
+
+impl fmt::Display for ProductError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProductError::InvalidBarcode { code } =>
+                write!(f, "{} isn't a valid EAN-13 or UPC-A barcode", code),
+            ProductError::DuplicateCode { code, product_id: Some(product_id) } =>
+                write!(f, "Code {} already belongs to product {}", code, product_id),
+            ProductError::DuplicateCode { code, product_id: None } =>
+                write!(f, "Code {} already belongs to another product", code),
+            ProductError::RepeatedCode { code } =>
+                write!(f, "Code {} is given more than once", code),
+            ProductError::UnitLocked { product_id } =>
+                write!(f, "Product {} already moved stock and its unit can't change", product_id),
+            ProductError::Database { message } =>
+                write!(f, "{}", message),
+        }
+    }
+}
+
+/// The advisory lock key taken while codes are checked and saved, "codes"
+/// in ASCII.
+const CODES_LOCK: i64 = 0x636f646573;
+
+/// The unique indexes on product, barcode and variant codes.
+const CODE_CONSTRAINTS: [&str; 3] = ["products_code", "product_barcodes_code_key", "product_variants_code_key"];
+
+impl From<diesel::result::Error> for ProductError {
+    fn from(error: diesel::result::Error) -> Self {
+        use diesel::result::{ Error, DatabaseErrorKind };
+
+        if let Error::DatabaseError(DatabaseErrorKind::UniqueViolation, ref info) = error {
+            if info.constraint_name().map_or(false, |constraint| CODE_CONSTRAINTS.contains(&constraint)) {
+                // Postgres details read "Key (code)=(<code>) already exists."
+                let code = info.details()
+                    .and_then(|details| details.trim_start_matches("Key (code)=(").rsplitn(2, ") already exists").nth(1))
+                    .unwrap_or_default();
+                return ProductError::DuplicateCode { code: code.to_string(), product_id: None };
+            }
+        }
+
+        ProductError::Database { message: error.to_string() }
+    }
 }
 
 type BoxedQuery<'a> = 
@@ -60,23 +140,31 @@ type BoxedQuery<'a> =
                                                      sql_types::Text,
                                                      sql_types::Nullable<sql_types::Text>,
                                                      sql_types::Nullable<sql_types::Double>,
-                                                     sql_types::Nullable<sql_types::Text>),
+                                                     sql_types::Nullable<sql_types::Text>,
+                                                     sql_types::Nullable<sql_types::Integer>),
                                                      schema::products::table, diesel::pg::Pg>;
 
 impl Product {
-    pub fn list(limit: i64, offset: i64, search: Option<Search<SearchProduct>>) ->
+    pub fn list(connection: &PgConnection, limit: i64, offset: i64, search: Option<Search<SearchProduct>>) ->
         Result<Vec<Product>, diesel::result::Error> {
-            let connection = establish_connection();
-            
             let query = Self::searching_product(search);
 
             query
                 .limit(limit)
                 .offset(offset)
-                .load(&connection)
+                .load(connection)
     }
 
-    pub fn show(request_id: i32) -> Result<FullProduct, diesel::result::Error> {
+    /// Every product matching the search, unpaginated.
+    pub fn search(connection: &PgConnection, search: Option<Search<SearchProduct>>) -> Result<Vec<Product>, diesel::result::Error> {
+        use crate::schema::products::dsl::*;
+
+        Self::searching_product(search)
+            .order(id)
+            .load(connection)
+    }
+
+    pub fn show(connection: &PgConnection, request_id: i32) -> Result<FullProduct, diesel::result::Error> {
         use crate::schema::products::dsl::*;
         use crate::schema::product_prices;
         use crate::schema::product_costs;
@@ -84,38 +172,71 @@ impl Product {
         use crate::schema::costs;
         use crate::schema::prices;
 
-        let connection = establish_connection();
         let mut full_product: FullProduct =
             FullProduct { 
                 product: Product::blank_product(),
                 prices: vec![],
-                costs: vec![]
+                costs: vec![],
+                taxes: vec![],
+                categories: vec![],
+                variants: vec![],
+                unit: None,
+                barcodes: vec![]
             };
         let db_product = products
             .find(request_id)
-            .get_result::<Product>(&connection)?;
+            .get_result::<Product>(connection)?;
 
         let vec_product_costs = product_costs::dsl::product_costs
             .filter(product_costs::dsl::product_id.eq(db_product.id))
             .inner_join(costs::dsl::costs)
             .inner_join(suppliers::dsl::suppliers)
             .order(costs::name)
-            .load::<(ProductCost, Cost, Supplier)>(&connection)?;
+            .load::<(ProductCost, Cost, Supplier)>(connection)?;
 
+        let today = SystemClock.today();
         let vec_product_prices = product_prices::dsl::product_prices
             .filter(product_prices::dsl::product_id.eq(db_product.id))
+            .filter(product_prices::dsl::valid_from.is_null().or(product_prices::dsl::valid_from.le(today)))
+            .filter(product_prices::dsl::valid_to.is_null().or(product_prices::dsl::valid_to.gt(today)))
             .inner_join(prices::dsl::prices)
             .order(prices::name)
-            .load::<(ProductPrice, Price)>(&connection)?;
+            .load::<(ProductPrice, Price)>(connection)?;
 
+        let cost_basis = ProductCost::cost_basis(connection, db_product.id)?;
         full_product.product = db_product;
+        let currency = Currency::get_currency(connection);
+        let full_product_price = |product_price: &ProductPrice, price: &Price| {
+            let amount = Money(product_price.price);
+            FullProductPrice {
+                price_id: price.id,
+                price: product_price.price,
+                formatted_price: amount.format(&currency),
+                name: price.name.clone(),
+                margin: cost_basis.as_ref().and_then(|cost| FullProductPrice::margin(&amount, cost)),
+                markup: cost_basis.as_ref().and_then(|cost| FullProductPrice::markup(&amount, cost))
+            }
+        };
+
+        for (product_price, price) in vec_product_prices.iter().filter(|(product_price, _)| product_price.variant_id.is_none()) {
+            full_product.prices.push(full_product_price(product_price, price));
+        }
 
-        for (product_price, price) in vec_product_prices {
-            full_product.prices.push(
-                FullProductPrice {
-                    price_id: price.id,
-                    price: product_price.price,
-                    name: price.name
+        for variant in ProductVariant::variants(connection, full_product.product.id)? {
+            let mut variant_prices = full_product.prices.clone();
+            for (product_price, price) in vec_product_prices.iter().filter(|(product_price, _)| product_price.variant_id == Some(variant.id)) {
+                let variant_price = full_product_price(product_price, price);
+                match variant_prices.iter_mut().find(|full_price| full_price.price_id == price.id) {
+                    Some(full_price) => *full_price = variant_price,
+                    None => variant_prices.push(variant_price)
+                }
+            }
+
+            full_product.variants.push(
+                FullProductVariant {
+                    stock: StockMovement::variant_stock(connection, variant.id)?,
+                    variant,
+                    prices: variant_prices
                 }
             );
         }
@@ -130,48 +251,150 @@ impl Product {
                 }
             );
         }
+
+        full_product.taxes = ProductTax::taxes(connection, full_product.product.id)?;
+        full_product.categories = ProductCategory::categories(connection, full_product.product.id)?;
+        if let Some(unit_id) = full_product.product.unit_id {
+            full_product.unit = Some(Unit::show(connection, unit_id)?);
+        }
+        full_product.barcodes = ProductBarcode::codes(connection, full_product.product.id)?;
         Ok(full_product)
     }
 
-    pub fn create(full_new_product: FullNewProduct) -> Result<Product, diesel::result::Error> {
-        let connection = establish_connection();
+    /// The product whose code, barcode or variant code is exactly `param_code`.
+    pub fn find_by_code(connection: &PgConnection, param_code: &str) -> Result<FullProduct, diesel::result::Error> {
+        let product_id = Self::code_owner(connection, param_code)?
+            .ok_or(diesel::result::Error::NotFound)?;
+
+        Self::show(connection, product_id)
+    }
 
-        let product: Result<Product, diesel::result::Error> = diesel::insert_into(products::table)
-            .values(&full_new_product.product)
-            .get_result(&connection);
+    pub fn create(connection: &PgConnection, full_new_product: FullNewProduct) -> Result<Product, ProductError> {
+        connection.transaction(|| {
+            full_new_product.check_codes(connection, None)?;
 
-        if let Ok(db_product) = &product {
-            ProductPrice::batch_action(full_new_product.prices, db_product.id)?;
-            ProductCost::batch_action(full_new_product.costs, db_product.id)?;
-        }
+            let product = diesel::insert_into(products::table)
+                .values(&full_new_product.product)
+                .get_result::<Product>(connection)?;
+
+            ProductPrice::batch_action(connection, full_new_product.prices, product.id)?;
+            ProductCost::batch_action(connection, full_new_product.costs, product.id)?;
+            if let Some(tax_ids) = full_new_product.taxes {
+                ProductTax::batch_action(connection, tax_ids, product.id)?;
+            }
+            if let Some(category_ids) = full_new_product.categories {
+                ProductCategory::batch_action(connection, category_ids, product.id)?;
+            }
+            if let Some(codes) = full_new_product.barcodes {
+                ProductBarcode::batch_action(connection, codes, product.id)?;
+            }
 
-        product
+            Ok(product)
+        })
     }
 
-    pub fn update(param_id: i32, full_product: FullNewProduct) -> Result<Product, diesel::result::Error> {
+    pub fn update(connection: &PgConnection, param_id: i32, full_product: FullNewProduct) -> Result<Product, ProductError> {
         use crate::schema::products::dsl::*;
-        let connection = establish_connection();
 
-        let product = diesel::update(products.find(param_id))
-            .set((name.eq(full_product.product.name),
-                  code.eq(full_product.product.code),
-                  description.eq(full_product.product.description)))
-            .get_result::<Product>(&connection);
+        connection.transaction(|| {
+            full_product.check_codes(connection, Some(param_id))?;
+            Self::check_unit(connection, param_id, full_product.product.unit_id)?;
+
+            let product = diesel::update(products.find(param_id))
+                .set((name.eq(full_product.product.name),
+                      code.eq(full_product.product.code),
+                      unit_id.eq(full_product.product.unit_id),
+                      description.eq(full_product.product.description)))
+                .get_result::<Product>(connection)?;
+
+            ProductPrice::batch_action(connection, full_product.prices, product.id)?;
+            ProductCost::batch_action(connection, full_product.costs, product.id)?;
+            if let Some(tax_ids) = full_product.taxes {
+                ProductTax::batch_action(connection, tax_ids, product.id)?;
+            }
+            if let Some(category_ids) = full_product.categories {
+                ProductCategory::batch_action(connection, category_ids, product.id)?;
+            }
+            if let Some(codes) = full_product.barcodes {
+                ProductBarcode::batch_action(connection, codes, product.id)?;
+            }
+
+            Ok(product)
+        })
+    }
 
-        if let Ok(db_product) = &product {
-            ProductPrice::batch_action(full_product.prices, db_product.id)?;
-            ProductCost::batch_action(full_product.costs, db_product.id)?;
+    /// Stock is kept in the product's unit, so the unit stays as it is once
+    /// any stock has moved.
+    fn check_unit(connection: &PgConnection, param_id: i32, new_unit_id: Option<i32>) -> Result<(), ProductError> {
+        use crate::schema::products::dsl::*;
+        use crate::schema::stock_movements;
+
+        let current_unit_id = products
+            .find(param_id)
+            .select(unit_id)
+            .first::<Option<i32>>(connection)?;
+        if current_unit_id == new_unit_id {
+            return Ok(());
+        }
+
+        let moved = diesel::select(diesel::dsl::exists(
+                stock_movements::table.filter(stock_movements::product_id.eq(param_id))))
+            .get_result::<bool>(connection)?;
+        if moved {
+            return Err(ProductError::UnitLocked { product_id: param_id });
         }
 
-        product
+        Ok(())
     }
 
-    pub fn delete(param_id: i32) -> Result<usize, diesel::result::Error> {
+    pub fn delete(connection: &PgConnection, param_id: i32) -> Result<usize, diesel::result::Error> {
         use crate::schema::products::dsl::*;
-        let connection = establish_connection();
 
         diesel::delete(products.find(param_id))
-            .execute(&connection)
+            .execute(connection)
+    }
+
+    /// Codes are unique across products, barcodes and variants, which no
+    /// single index covers. Writes that check codes take this lock in turn
+    /// until their transaction ends, so two of them can't both find a code
+    /// free and save it.
+    pub fn lock_codes(connection: &PgConnection) -> Result<(), diesel::result::Error> {
+        diesel::sql_query("SELECT pg_advisory_xact_lock($1)")
+            .bind::<sql_types::BigInt, _>(CODES_LOCK)
+            .execute(connection)
+            .map(|_| ())
+    }
+
+    /// The product a code belongs to, whether it's the product's own code,
+    /// one of its barcodes or one of its variants' codes.
+    pub fn code_owner(connection: &PgConnection, param_code: &str) -> Result<Option<i32>, diesel::result::Error> {
+        use crate::schema::products::dsl::*;
+        use crate::schema::product_barcodes;
+        use crate::schema::product_variants;
+
+        let owner = products
+            .filter(code.eq(param_code))
+            .select(id)
+            .first::<i32>(connection)
+            .optional()?;
+        if owner.is_some() {
+            return Ok(owner);
+        }
+
+        let owner = product_barcodes::table
+            .filter(product_barcodes::code.eq(param_code))
+            .select(product_barcodes::product_id)
+            .first::<i32>(connection)
+            .optional()?;
+        if owner.is_some() {
+            return Ok(owner);
+        }
+
+        product_variants::table
+            .filter(product_variants::code.eq(param_code))
+            .select(product_variants::product_id)
+            .first::<i32>(connection)
+            .optional()
     }
 
     fn searching_product<'a>(search: Option<Search<SearchProduct>>) -> BoxedQuery<'a> {
@@ -196,6 +419,11 @@ impl Product {
             if let Some(product_stock) = product.stock {
                 query = query.filter(stock.eq(product_stock));
             }
+            if let Some(product_category_id) = product.category_id {
+                query = query.filter(diesel::dsl::sql::<sql_types::Bool>(&format!(
+                    "products.id IN (SELECT product_id FROM product_categories WHERE category_id IN ({}))",
+                    Category::descendants_query(product_category_id))));
+            }
         }
 
         query
@@ -207,14 +435,54 @@ impl Product {
             code: None,
             name: "".to_string(),
             description: None,
-            stock: None
+            stock: None,
+            unit_id: None
         }
     }
 }
 
+use std::fmt;
 use std::str::FromStr;
+use std::collections::HashSet;
 use serde_json;
 
+impl FullNewProduct {
+    /// Checks the product's code and barcodes: barcodes must be EAN-13 or
+    /// UPC-A, a code shaped like one must carry the right check digit, each
+    /// code is given once, and none of them can belong to another product.
+    fn check_codes(&self, connection: &PgConnection, product_id: Option<i32>) -> Result<(), ProductError> {
+        let barcodes = self.barcodes.clone().unwrap_or_default();
+
+        if let Some(ref code) = self.product.code {
+            if ProductBarcode::looks_like_barcode(code) && !ProductBarcode::valid(code) {
+                return Err(ProductError::InvalidBarcode { code: code.clone() });
+            }
+        }
+        if let Some(code) = barcodes.iter().find(|code| !ProductBarcode::valid(code)) {
+            return Err(ProductError::InvalidBarcode { code: code.clone() });
+        }
+
+        Product::lock_codes(connection)?;
+
+        let mut seen = HashSet::new();
+        for code in self.product.code.iter().chain(barcodes.iter()) {
+            if !seen.insert(code) {
+                return Err(ProductError::RepeatedCode { code: code.clone() });
+            }
+        }
+
+        for code in self.product.code.iter().chain(barcodes.iter()) {
+            if let Some(owner) = Product::code_owner(connection, code)? {
+                if Some(owner) != product_id {
+                    return Err(ProductError::DuplicateCode { code: code.clone(), product_id: Some(owner) });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
 impl FromStr for Product {
     type Err = serde_json::Error;
 
@@ -0,0 +1,79 @@
+use diesel;
+use diesel::RunQueryDsl;
+use diesel::QueryDsl;
+use diesel::ExpressionMethods;
+use diesel::BoolExpressionMethods;
+use diesel::pg::PgConnection;
+use crate::schema::product_barcodes;
+
+#[derive(Identifiable, Serialize, Deserialize, Queryable, Debug, Clone, PartialEq)]
+#[table_name="product_barcodes"]
+pub struct ProductBarcode {
+    pub id: i32,
+    pub product_id: i32,
+    pub code: String
+}
+
+#[derive(Serialize, Deserialize, Insertable, Debug, Clone)]
+#[table_name="product_barcodes"]
+pub struct NewProductBarcode {
+    pub product_id: i32,
+    pub code: String
+}
+
+impl ProductBarcode {
+    /// Leaves the product with exactly the given barcodes.
+    pub fn batch_action(connection: &PgConnection, codes: Vec<String>, product_id: i32) -> Result<bool, diesel::result::Error> {
+        use crate::schema::product_barcodes::dsl;
+
+        diesel::delete(dsl::product_barcodes
+                .filter(dsl::product_id.eq(product_id).and(dsl::code.ne_all(&codes))))
+            .execute(connection)?;
+
+        let current_codes = Self::codes(connection, product_id)?;
+
+        for code in codes {
+            if !current_codes.contains(&code) {
+                diesel::insert_into(product_barcodes::table)
+                    .values(&NewProductBarcode { product_id, code })
+                    .execute(connection)?;
+            }
+        }
+
+        Ok(true)
+    }
+
+    pub fn codes(connection: &PgConnection, product_id: i32) -> Result<Vec<String>, diesel::result::Error> {
+        use crate::schema::product_barcodes::dsl;
+
+        dsl::product_barcodes
+            .filter(dsl::product_id.eq(product_id))
+            .order(dsl::id)
+            .select(dsl::code)
+            .load::<String>(connection)
+    }
+
+    /// Whether the code is an EAN-13 or a UPC-A with the right check digit.
+    pub fn valid(code: &str) -> bool {
+        if !Self::looks_like_barcode(code) {
+            return false;
+        }
+
+        let digits = code.bytes().map(|digit| (digit - b'0') as u32).collect::<Vec<u32>>();
+        let (check_digit, payload) = digits.split_last().unwrap();
+        let sum: u32 = payload
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(position, digit)| if position % 2 == 0 { digit * 3 } else { *digit })
+            .sum();
+
+        (10 - sum % 10) % 10 == *check_digit
+    }
+
+    /// Whether the code has the shape of an EAN-13 or a UPC-A, twelve or
+    /// thirteen digits. Other codes are internal and aren't checked.
+    pub fn looks_like_barcode(code: &str) -> bool {
+        (code.len() == 12 || code.len() == 13) && code.bytes().all(|digit| digit.is_ascii_digit())
+    }
+}
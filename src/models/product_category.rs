@@ -0,0 +1,68 @@
+use diesel;
+use diesel::RunQueryDsl;
+use diesel::QueryDsl;
+use diesel::ExpressionMethods;
+use diesel::BoolExpressionMethods;
+use diesel::pg::PgConnection;
+use crate::schema::product_categories;
+use crate::models::category::Category;
+
+#[derive(Identifiable, Serialize, Deserialize, Queryable, Debug, Clone, PartialEq)]
+#[table_name="product_categories"]
+pub struct ProductCategory {
+    pub id: i32,
+    pub product_id: i32,
+    pub category_id: i32
+}
+
+#[derive(Serialize, Deserialize, Insertable, Debug, Clone)]
+#[table_name="product_categories"]
+pub struct NewProductCategory {
+    pub product_id: i32,
+    pub category_id: i32
+}
+
+impl ProductCategory {
+    /// Leaves the product in exactly the given categories.
+    pub fn batch_action(connection: &PgConnection, category_ids: Vec<i32>, product_id: i32) -> Result<bool, diesel::result::Error> {
+        use crate::schema::product_categories::dsl;
+
+        diesel::delete(dsl::product_categories
+                .filter(dsl::product_id.eq(product_id).and(dsl::category_id.ne_all(&category_ids))))
+            .execute(connection)?;
+
+        let current_category_ids = Self::category_ids(connection, product_id)?;
+
+        for category_id in category_ids {
+            if !current_category_ids.contains(&category_id) {
+                diesel::insert_into(product_categories::table)
+                    .values(&NewProductCategory { product_id, category_id })
+                    .execute(connection)?;
+            }
+        }
+
+        Ok(true)
+    }
+
+    pub fn category_ids(connection: &PgConnection, product_id: i32) -> Result<Vec<i32>, diesel::result::Error> {
+        use crate::schema::product_categories::dsl;
+
+        dsl::product_categories
+            .filter(dsl::product_id.eq(product_id))
+            .order(dsl::id)
+            .select(dsl::category_id)
+            .load::<i32>(connection)
+    }
+
+    pub fn categories(connection: &PgConnection, product_id: i32) -> Result<Vec<Category>, diesel::result::Error> {
+        use crate::schema::product_categories::dsl;
+        use crate::schema::categories;
+
+        dsl::product_categories
+            .filter(dsl::product_id.eq(product_id))
+            .inner_join(categories::table)
+            .order(categories::name)
+            .select(categories::all_columns)
+            .load::<Category>(connection)
+    }
+}
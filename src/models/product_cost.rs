@@ -1,11 +1,15 @@
 use diesel;
 use diesel::RunQueryDsl;
+use diesel::OptionalExtension;
 use diesel::QueryDsl;
 use diesel::ExpressionMethods;
 use diesel::BoolExpressionMethods;
 use diesel::pg::PgConnection;
 use crate::schema::product_costs;
-use crate::models::db_connection::*;
+use crate::models::money::{ Money, RoundingMode };
+use crate::models::product_price::ProductPrice;
+use crate::models::purchase::Purchase;
+use crate::models::purchase_product::PurchaseProduct;
 
 #[derive(Identifiable, Associations, Serialize, Deserialize, Queryable, 
          Debug, FromData, Responder)]
@@ -14,7 +18,7 @@ pub struct ProductCost {
     pub product_id: i32,
     pub cost_id: i32,
     pub supplier_id: i32,
-    pub cost: i32
+    pub cost: i64
 }
 
 #[derive(Serialize, Deserialize, Insertable, Clone, Debug, Responder)]
@@ -23,21 +27,20 @@ pub struct EditableProductCost {
     pub product_id: Option<i32>,
     pub cost_id: i32,
     pub supplier_id: i32,
-    pub cost: i32
+    pub cost: i64
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Responder)]
 pub struct FullProductCost {
     pub cost_id: i32,
     pub supplier_id: i32,
-    pub cost: i32,
+    pub cost: i64,
     pub name: String
 }
 
 impl ProductCost {
-    pub fn batch_action(vec_costs: Vec<EditableProductCost>, product_id: i32) -> Result<bool, diesel::result::Error> {
+    pub fn batch_action(connection: &PgConnection, vec_costs: Vec<EditableProductCost>, product_id: i32) -> Result<bool, diesel::result::Error> {
         use crate::schema::product_costs::dsl;
-        let connection = establish_connection();
 
         for mut product_cost in vec_costs {
             product_cost.product_id = Some(product_id);
@@ -45,18 +48,72 @@ impl ProductCost {
             let result_edit_cost = 
                 dsl::product_costs
                     .filter(dsl::cost_id.eq(product_cost.cost_id).and(dsl::product_id.eq(product_id)))
-                    .first::<ProductCost>(&connection);
+                    .first::<ProductCost>(connection)
+                    .optional()?;
 
-            if let Ok(edit_cost) = result_edit_cost {
+            if let Some(edit_cost) = result_edit_cost {
                 diesel::update(dsl::product_costs.find(edit_cost.id))
                     .set((dsl::cost.eq(product_cost.cost),
                           dsl::supplier_id.eq(product_cost.supplier_id)))
-                    .get_result::<ProductCost>(&connection)?;
+                    .get_result::<ProductCost>(connection)?;
             } else {
-                ProductCost::create_product_cost(&connection, &product_cost)?;
+                ProductCost::create_product_cost(connection, &product_cost)?;
             }
         }
 
+        ProductPrice::apply_markups(connection, product_id)
+    }
+
+    /// What the product costs for margins and cost-plus prices: its primary
+    /// cost, or all its costs added up when none is primary. None when it
+    /// has no costs or they add up to more than an amount holds.
+    pub fn cost_basis(connection: &PgConnection, product_id: i32) -> Result<Option<Money>, diesel::result::Error> {
+        use crate::schema::product_costs::dsl;
+        use crate::schema::costs;
+
+        let vec_costs = dsl::product_costs
+            .filter(dsl::product_id.eq(product_id))
+            .inner_join(costs::table)
+            .order(dsl::id)
+            .select((dsl::cost, costs::primary_cost))
+            .load::<(i64, bool)>(connection)?;
+
+        if vec_costs.is_empty() {
+            return Ok(None);
+        }
+
+        let cost_basis = match vec_costs.iter().find(|&&(_, primary_cost)| primary_cost) {
+            Some(&(cost, _)) => Some(Money(cost)),
+            None => Money::checked_sum(&vec_costs.iter().map(|&(cost, _)| Money(cost)).collect::<Vec<Money>>())
+        };
+
+        Ok(cost_basis)
+    }
+
+    /// Takes the supplier's cost from what the purchase paid, per unit of
+    /// the product whatever unit it was bought in.
+    pub fn update_from_purchase(connection: &PgConnection, purchase: &Purchase) -> Result<bool, diesel::result::Error> {
+        use crate::schema::product_costs::dsl;
+        use crate::schema::purchase_products;
+
+        let vec_purchase_products = purchase_products::dsl::purchase_products
+            .filter(purchase_products::dsl::purchase_id.eq(purchase.id))
+            .load::<PurchaseProduct>(connection)?;
+
+        for purchase_product in vec_purchase_products {
+            let unit_amount = purchase_product.unit_factor;
+            let cost = purchase_product.price
+                .prorate(1.0, unit_amount, RoundingMode::from_env())
+                .ok_or(diesel::result::Error::NotFound)?;
+
+            diesel::update(dsl::product_costs
+                           .filter(dsl::product_id.eq(purchase_product.product_id)
+                                   .and(dsl::supplier_id.eq(purchase.supplier_id))))
+                .set(dsl::cost.eq(cost.0))
+                .execute(connection)?;
+            ProductPrice::apply_markups(connection, purchase_product.product_id)?;
+        }
+
         Ok(true)
     }
 
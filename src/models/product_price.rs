@@ -1,11 +1,17 @@
 use diesel;
 use diesel::RunQueryDsl;
+use diesel::OptionalExtension;
 use diesel::QueryDsl;
 use diesel::ExpressionMethods;
 use diesel::BoolExpressionMethods;
 use diesel::pg::PgConnection;
+use chrono::NaiveDate;
 use crate::schema::product_prices;
-use crate::models::db_connection::*;
+use crate::models::money::{ Money, RoundingMode, PERCENTAGE_SCALE };
+use crate::models::price::Price;
+use crate::models::product_cost::ProductCost;
+use crate::models::naive_date_form::NaiveDateForm;
+use crate::models::clock::{ Clock, SystemClock };
 
 #[derive(Identifiable, Associations, Serialize, Deserialize, Queryable, 
          Debug, Clone, FromData, Responder)]
@@ -13,52 +19,201 @@ pub struct ProductPrice {
     pub id: i32,
     pub product_id: i32,
     pub price_id: i32,
-    pub price: i32
+    pub price: i64,
+    pub valid_from: Option<NaiveDate>,
+    pub valid_to: Option<NaiveDate>,
+    pub variant_id: Option<i32>
 }
 
+/// A price for a product in a price list, taking effect on `valid_from`.
+/// When left out the first price is in force since always and later ones
+/// from today. With a `variant_id` it overrides the product's price for
+/// that variant.
 #[derive(Serialize, Deserialize, Insertable, Debug, Clone, FromData)]
 #[table_name="product_prices"]
 pub struct EditableProductPrice {
     pub product_id: Option<i32>,
     pub price_id: i32,
-    pub price: i32
+    pub price: i64,
+    #[serde(default)]
+    pub valid_from: Option<NaiveDate>,
+    #[serde(skip_deserializing)]
+    pub valid_to: Option<NaiveDate>,
+    #[serde(default)]
+    pub variant_id: Option<i32>
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Responder)]
 pub struct FullProductPrice {
     pub price_id: i32,
-    pub price: i32,
-    pub name: String
+    pub price: i64,
+    pub formatted_price: String,
+    pub name: String,
+    pub margin: Option<Money>,
+    pub markup: Option<Money>
 }
 
 impl ProductPrice {
-    pub fn batch_action(vec_prices: Vec<EditableProductPrice>, product_id: i32) -> Result<bool, diesel::result::Error> {
+    /// Keeps every price version: a new price closes the version in force
+    /// on the day it takes effect, a change on the same day corrects it.
+    pub fn batch_action(connection: &PgConnection, vec_prices: Vec<EditableProductPrice>, product_id: i32) -> Result<bool, diesel::result::Error> {
         use crate::schema::product_prices::dsl;
-        let connection = establish_connection();
+        let today = SystemClock.today();
 
         for mut editable_product_price in vec_prices {
             editable_product_price.product_id = Some(product_id);
 
-            let result_edit_price = 
-                dsl::product_prices
-                    .filter(dsl::price_id.eq(editable_product_price.price_id).and(dsl::product_id.eq(product_id)))
-                    .first::<ProductPrice>(&connection);
-
-            if let Ok(edit_price) = result_edit_price {
-                diesel::update(dsl::product_prices.find(edit_price.id))
-                    .set(dsl::price.eq(editable_product_price.price))
-                    .get_result::<ProductPrice>(&connection)?;
-            } else {
-                ProductPrice::create_product_price(&connection, editable_product_price)?;
+            let first_version = Self::versions(product_id, editable_product_price.variant_id, editable_product_price.price_id)
+                .first::<ProductPrice>(connection)
+                .optional()?
+                .is_none();
+            let valid_from = match editable_product_price.valid_from {
+                Some(valid_from) => valid_from,
+                None if first_version => {
+                    ProductPrice::create_product_price(connection, editable_product_price)?;
+                    continue;
+                },
+                None => today
+            };
+            editable_product_price.valid_from = Some(valid_from);
+
+            let version = Self::find_version(connection, product_id, editable_product_price.variant_id, editable_product_price.price_id, valid_from)?;
+
+            match version {
+                Some(ref version) if version.price == editable_product_price.price => {},
+                Some(ref version) if version.valid_from == Some(valid_from) => {
+                    diesel::update(dsl::product_prices.find(version.id))
+                        .set(dsl::price.eq(editable_product_price.price))
+                        .get_result::<ProductPrice>(connection)?;
+                },
+                Some(version) => {
+                    diesel::update(dsl::product_prices.find(version.id))
+                        .set(dsl::valid_to.eq(valid_from))
+                        .get_result::<ProductPrice>(connection)?;
+                    editable_product_price.valid_to = version.valid_to;
+                    ProductPrice::create_product_price(connection, editable_product_price)?;
+                },
+                None => {
+                    editable_product_price.valid_to = Self::versions(product_id, editable_product_price.variant_id, editable_product_price.price_id)
+                        .filter(dsl::valid_from.gt(valid_from))
+                        .order(dsl::valid_from)
+                        .first::<ProductPrice>(connection)
+                        .optional()?
+                        .and_then(|next_version| next_version.valid_from);
+                    ProductPrice::create_product_price(connection, editable_product_price)?;
+                }
             }
         }
 
         Ok(true)
     }
 
+    /// Reprices the product in the cost-plus price lists from its cost.
+    pub fn apply_markups(connection: &PgConnection, product_id: i32) -> Result<bool, diesel::result::Error> {
+        use crate::schema::prices;
+
+        let cost = match ProductCost::cost_basis(connection, product_id)? {
+            Some(cost) => cost,
+            None => return Ok(true)
+        };
+
+        let vec_prices = prices::table
+            .filter(prices::markup.is_not_null())
+            .load::<Price>(connection)?
+            .into_iter()
+            .filter_map(|price| {
+                let markup = cost.percentage(price.markup.as_ref()?, RoundingMode::from_env())?;
+                Some(EditableProductPrice {
+                    product_id: None,
+                    price_id: price.id,
+                    price: cost.checked_add(&markup)?.0,
+                    valid_from: None,
+                    valid_to: None,
+                    variant_id: None
+                })
+            })
+            .collect::<Vec<EditableProductPrice>>();
+
+        Self::batch_action(connection, vec_prices, product_id)
+    }
+
+    /// The version of the product's price in the price list in force on `date`.
+    pub fn version(connection: &PgConnection, product_id: i32, price_id: i32, date: NaiveDate) -> Result<Option<ProductPrice>, diesel::result::Error> {
+        Self::find_version(connection, product_id, None, price_id, date)
+    }
+
+    fn find_version(connection: &PgConnection, product_id: i32, variant_id: Option<i32>, price_id: i32, date: NaiveDate) -> Result<Option<ProductPrice>, diesel::result::Error> {
+        use crate::schema::product_prices::dsl;
+
+        Self::versions(product_id, variant_id, price_id)
+            .filter(dsl::valid_from.is_null().or(dsl::valid_from.le(date)))
+            .filter(dsl::valid_to.is_null().or(dsl::valid_to.gt(date)))
+            .first::<ProductPrice>(connection)
+            .optional()
+    }
+
+    pub fn history(connection: &PgConnection, product_id: i32) -> Result<Vec<ProductPrice>, diesel::result::Error> {
+        use crate::schema::product_prices::dsl;
+
+        dsl::product_prices
+            .filter(dsl::product_id.eq(product_id))
+            .order((dsl::price_id, dsl::variant_id, dsl::valid_from.is_not_null(), dsl::valid_from))
+            .load::<ProductPrice>(connection)
+    }
+
+    /// The product's price in the given price list on `date`, the variant's
+    /// own when it has one.
+    pub fn price(connection: &PgConnection, product_id: i32, variant_id: Option<i32>, price_id: i32, date: &NaiveDateForm) -> Result<Money, diesel::result::Error> {
+        let variant_version = match variant_id {
+            Some(_) => Self::find_version(connection, product_id, variant_id, price_id, date.naive_date())?,
+            None => None
+        };
+        let version = match variant_version {
+            Some(version) => Some(version),
+            None => Self::version(connection, product_id, price_id, date.naive_date())?
+        };
+
+        version
+            .map(|version| Money(version.price))
+            .ok_or(diesel::result::Error::NotFound)
+    }
+
+    /// Every version of the product's price in the price list, only the
+    /// variant's overrides when given a variant.
+    fn versions<'a>(product_id: i32, variant_id: Option<i32>, price_id: i32) -> product_prices::BoxedQuery<'a, diesel::pg::Pg> {
+        use crate::schema::product_prices::dsl;
+
+        let query = dsl::product_prices
+            .filter(dsl::price_id.eq(price_id).and(dsl::product_id.eq(product_id)))
+            .into_boxed::<diesel::pg::Pg>();
+
+        match variant_id {
+            Some(variant_id) => query.filter(dsl::variant_id.eq(variant_id)),
+            None => query.filter(dsl::variant_id.is_null())
+        }
+    }
+
     fn create_product_price(connection: &PgConnection, editable_product_price: EditableProductPrice) -> Result<ProductPrice, diesel::result::Error> {
         diesel::insert_into(product_prices::table)
             .values(&editable_product_price)
             .get_result::<ProductPrice>(connection)
     }
 }
+
+impl FullProductPrice {
+    /// Share of the price left after the cost, where 12% is `Money(1200)`.
+    pub fn margin(price: &Money, cost: &Money) -> Option<Money> {
+        if price.0 <= 0 {
+            return None;
+        }
+        price.checked_sub(cost)?.checked_mul_div(PERCENTAGE_SCALE, price.0 as i128, RoundingMode::from_env())
+    }
+
+    /// What the price adds over the cost, as a percentage of the cost.
+    pub fn markup(price: &Money, cost: &Money) -> Option<Money> {
+        if cost.0 <= 0 {
+            return None;
+        }
+        price.checked_sub(cost)?.checked_mul_div(PERCENTAGE_SCALE, cost.0 as i128, RoundingMode::from_env())
+    }
+}
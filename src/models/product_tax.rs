@@ -0,0 +1,60 @@
+use diesel;
+use diesel::RunQueryDsl;
+use diesel::QueryDsl;
+use diesel::ExpressionMethods;
+use diesel::BoolExpressionMethods;
+use diesel::pg::PgConnection;
+use crate::schema::product_taxes;
+use crate::models::tax::Tax;
+
+#[derive(Identifiable, Serialize, Deserialize, Queryable, Debug, Clone, PartialEq)]
+#[table_name="product_taxes"]
+pub struct ProductTax {
+    pub id: i32,
+    pub product_id: i32,
+    pub tax_id: i32
+}
+
+#[derive(Serialize, Deserialize, Insertable, Debug, Clone)]
+#[table_name="product_taxes"]
+pub struct NewProductTax {
+    pub product_id: i32,
+    pub tax_id: i32
+}
+
+impl ProductTax {
+    /// Leaves the product with exactly the given default taxes.
+    pub fn batch_action(connection: &PgConnection, tax_ids: Vec<i32>, product_id: i32) -> Result<bool, diesel::result::Error> {
+        use crate::schema::product_taxes::dsl;
+
+        diesel::delete(dsl::product_taxes
+                .filter(dsl::product_id.eq(product_id).and(dsl::tax_id.ne_all(&tax_ids))))
+            .execute(connection)?;
+
+        let current_tax_ids = Self::tax_ids(connection, product_id)?;
+
+        for tax_id in tax_ids {
+            if !current_tax_ids.contains(&tax_id) {
+                diesel::insert_into(product_taxes::table)
+                    .values(&NewProductTax { product_id, tax_id })
+                    .execute(connection)?;
+            }
+        }
+
+        Ok(true)
+    }
+
+    pub fn tax_ids(connection: &PgConnection, product_id: i32) -> Result<Vec<i32>, diesel::result::Error> {
+        use crate::schema::product_taxes::dsl;
+
+        dsl::product_taxes
+            .filter(dsl::product_id.eq(product_id))
+            .order(dsl::id)
+            .select(dsl::tax_id)
+            .load::<i32>(connection)
+    }
+
+    pub fn taxes(connection: &PgConnection, product_id: i32) -> Result<Vec<Tax>, diesel::result::Error> {
+        Tax::find_all(connection, &Self::tax_ids(connection, product_id)?)
+    }
+}
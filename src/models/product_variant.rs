@@ -0,0 +1,169 @@
+use std::str::FromStr;
+use serde_json;
+use diesel;
+use diesel::prelude::*;
+use diesel::sql_types;
+use diesel::pg::PgConnection;
+use crate::schema;
+use crate::schema::product_variants;
+use crate::models::product_price::FullProductPrice;
+use crate::models::product::{ Product, ProductError };
+use crate::models::product_barcode::ProductBarcode;
+use crate::handlers::base::Search;
+use crate::basic_model_actions;
+
+type BoxedQuery<'a> =
+    diesel::query_builder::BoxedSelectStatement<'a, (sql_types::Integer,
+                                                     sql_types::Integer,
+                                                     sql_types::Nullable<sql_types::Text>,
+                                                     sql_types::Nullable<sql_types::Text>,
+                                                     sql_types::Nullable<sql_types::Text>),
+                                                     schema::product_variants::table, diesel::pg::Pg>;
+
+/// One size/color of a product, sold and stocked on its own. Its stock
+/// comes from the stock movements registered for it.
+#[derive(Serialize, Deserialize, Queryable, PartialEq,
+         Debug, Clone, AsChangeset, FromData, Responder)]
+#[table_name="product_variants"]
+pub struct ProductVariant {
+    pub id: i32,
+    pub product_id: i32,
+    pub code: Option<String>,
+    pub size: Option<String>,
+    pub color: Option<String>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, FromForm, FromData)]
+pub struct SearchProductVariant {
+    pub id: Option<i32>,
+    pub product_id: Option<i32>,
+    pub code: Option<String>,
+    pub size: Option<String>,
+    pub color: Option<String>
+}
+
+#[derive(Serialize, Deserialize, Insertable, Debug, FromData, Responder)]
+#[table_name="product_variants"]
+pub struct NewProductVariant {
+    pub product_id: i32,
+    pub code: Option<String>,
+    pub size: Option<String>,
+    pub color: Option<String>
+}
+
+/// A variant with its stock and the prices it sells at, its own where it
+/// overrides the product's.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FullProductVariant {
+    pub variant: ProductVariant,
+    pub stock: f64,
+    pub prices: Vec<FullProductPrice>
+}
+
+impl ProductVariant {
+    pub fn variants(connection: &PgConnection, param_product_id: i32) -> Result<Vec<ProductVariant>, diesel::result::Error> {
+        use crate::schema::product_variants::dsl::*;
+
+        product_variants
+            .filter(product_id.eq(param_product_id))
+            .order(id)
+            .load::<ProductVariant>(connection)
+    }
+
+    /// The variant, as long as it's one of the product's.
+    pub fn find(connection: &PgConnection, param_product_id: i32, variant_id: i32) -> Result<Option<ProductVariant>, diesel::result::Error> {
+        use crate::schema::product_variants::dsl::*;
+
+        product_variants
+            .filter(id.eq(variant_id).and(product_id.eq(param_product_id)))
+            .first::<ProductVariant>(connection)
+            .optional()
+    }
+
+    pub fn create(connection: &PgConnection, new_variant: NewProductVariant) -> Result<ProductVariant, ProductError> {
+        connection.transaction(|| {
+            if let Some(ref code) = new_variant.code {
+                Self::check_code(connection, code, new_variant.product_id, None)?;
+            }
+
+            Ok(<Self as BasicModelActions>::create(connection, new_variant)?)
+        })
+    }
+
+    pub fn update(connection: &PgConnection, param_id: i32, variant: ProductVariant) -> Result<ProductVariant, ProductError> {
+        connection.transaction(|| {
+            if let Some(ref code) = variant.code {
+                Self::check_code(connection, code, variant.product_id, Some(param_id))?;
+            }
+
+            Ok(<Self as BasicModelActions>::update(connection, param_id, variant)?)
+        })
+    }
+
+    /// Holds variant codes to the rules of product codes: one shaped like a
+    /// barcode must carry the right check digit, and it can't belong to
+    /// another product or to another variant.
+    fn check_code(connection: &PgConnection, code: &str, param_product_id: i32, variant_id: Option<i32>) -> Result<(), ProductError> {
+        use crate::schema::product_variants::dsl;
+
+        if ProductBarcode::looks_like_barcode(code) && !ProductBarcode::valid(code) {
+            return Err(ProductError::InvalidBarcode { code: code.to_string() });
+        }
+
+        Product::lock_codes(connection)?;
+
+        if let Some(owner) = Product::code_owner(connection, code)? {
+            if owner != param_product_id {
+                return Err(ProductError::DuplicateCode { code: code.to_string(), product_id: Some(owner) });
+            }
+        }
+
+        let other_variant = dsl::product_variants
+            .filter(dsl::code.eq(code))
+            .select(dsl::id)
+            .first::<i32>(connection)
+            .optional()?;
+        match other_variant {
+            Some(other_id) if Some(other_id) != variant_id =>
+                Err(ProductError::DuplicateCode { code: code.to_string(), product_id: Some(param_product_id) }),
+            _ => Ok(())
+        }
+    }
+
+    fn searching_records<'a>(search: Option<Search<SearchProductVariant>>) -> BoxedQuery<'a> {
+        use crate::schema::product_variants::dsl::*;
+
+        let mut query = schema::product_variants::table.into_boxed::<diesel::pg::Pg>();
+
+        if let Some(search_product_variant) = search {
+            let Search(product_variant) = search_product_variant;
+            if let Some(product_variant_id) = product_variant.id {
+                query = query.filter(id.eq(product_variant_id));
+            }
+            if let Some(product_variant_product_id) = product_variant.product_id {
+                query = query.filter(product_id.eq(product_variant_product_id));
+            }
+            if let Some(product_variant_code) = product_variant.code {
+                query = query.filter(code.like(product_variant_code));
+            }
+            if let Some(product_variant_size) = product_variant.size {
+                query = query.filter(size.like(product_variant_size));
+            }
+            if let Some(product_variant_color) = product_variant.color {
+                query = query.filter(color.like(product_variant_color));
+            }
+        }
+
+        query
+    }
+}
+
+impl FromStr for SearchProductVariant {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+basic_model_actions!(product_variants, ProductVariant, NewProductVariant, SearchProductVariant);
@@ -0,0 +1,406 @@
+use std::fmt;
+use std::io::Read;
+use std::str::FromStr;
+use diesel;
+use diesel::sql_types;
+use diesel::prelude::*;
+use diesel::pg::PgConnection;
+use crate::models::naive_date_form::NaiveDateForm;
+use crate::models::purchase_product::PurchaseProduct;
+use crate::models::purchase_product::NewPurchaseProduct;
+use crate::models::calculation::Calculation;
+use crate::models::item_calculation::ItemCalculation;
+use crate::models::money::Money;
+use crate::models::purchase_status::PurchaseStatus;
+use crate::models::purchase_status::PurchaseStatusMapping;
+use crate::models::product_variant::ProductVariant;
+use crate::models::unit::Unit;
+use crate::models::supplier::Supplier;
+use crate::models::supplier::BasicModelActions;
+use rocket::{ Request, Data };
+use rocket::data:: { FromData, Outcome };
+use rocket::http::Status;
+use rocket::Outcome::{ Failure, Success };
+use crate::schema;
+use crate::schema::purchases;
+use serde_json;
+use crate::handlers::base::Search;
+
+type BoxedQuery<'a> =
+    diesel::query_builder::BoxedSelectStatement<'a, (sql_types::Integer,
+                                                     sql_types::Integer,
+                                                     sql_types::Date,
+                                                     sql_types::BigInt,
+                                                     sql_types::BigInt,
+                                                     sql_types::BigInt,
+                                                     sql_types::BigInt,
+                                                     sql_types::BigInt,
+                                                     sql_types::Nullable<sql_types::Text>,
+                                                     sql_types::Integer,
+                                                     PurchaseStatusMapping,
+                                                     sql_types::Bool
+                                                     ),
+                                                     schema::purchases::table, diesel::pg::Pg>;
+
+#[derive(AsChangeset, Insertable, Serialize, Deserialize, Clone, Queryable,
+         Debug, FromForm)]
+#[table_name="purchases"]
+pub struct Purchase {
+    pub id: i32,
+    pub supplier_id: i32,
+    pub purchase_date: NaiveDateForm,
+    pub sub_total: Money,
+    pub sub_total_without_discount: Money,
+    pub discount_calculated: Money,
+    pub taxes_calculated: Money,
+    pub total: Money,
+    pub observation: Option<String>,
+    pub currency_id: i32,
+    #[serde(skip_deserializing)]
+    pub status: PurchaseStatus,
+    pub update_costs: bool
+}
+
+#[derive(Insertable, Serialize, Deserialize, Clone, Debug, FromForm)]
+#[table_name="purchases"]
+pub struct NewPurchase {
+    pub supplier_id: i32,
+    pub purchase_date: NaiveDateForm,
+    pub sub_total: Option<Money>,
+    pub sub_total_without_discount: Option<Money>,
+    pub discount_calculated: Option<Money>,
+    pub taxes_calculated: Option<Money>,
+    pub total: Option<Money>,
+    pub observation: Option<String>,
+    pub currency_id: i32,
+    #[serde(skip_deserializing)]
+    pub status: PurchaseStatus,
+    pub update_costs: Option<bool>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FullPurchase {
+    pub purchase: Purchase,
+    pub purchase_products: Vec<PurchaseProduct>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FullNewPurchase {
+    purchase: NewPurchase,
+    purchase_products: Vec<NewPurchaseProduct>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "error", rename_all = "snake_case")]
+pub enum PurchaseError {
+    Locked { purchase_id: i32, status: PurchaseStatus, reason: String },
+    PurchaseNotFound { purchase_id: i32 },
+    UnknownVariant { product_id: i32, variant_id: i32 },
+    IncompatibleUnit { product_id: i32, unit_id: i32 },
+    Overflow,
+    Database { message: String },
+}
+
+impl fmt::Display for PurchaseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PurchaseError::Locked { purchase_id, status, .. } =>
+                write!(f, "Purchase {} is {:?} and can't be changed", purchase_id, status),
+            PurchaseError::PurchaseNotFound { purchase_id } =>
+                write!(f, "Purchase {} not found", purchase_id),
+            PurchaseError::UnknownVariant { product_id, variant_id } =>
+                write!(f, "Variant {} isn't one of product {}'s variants", variant_id, product_id),
+            PurchaseError::IncompatibleUnit { product_id, unit_id } =>
+                write!(f, "Unit {} can't be converted to product {}'s unit", unit_id, product_id),
+            PurchaseError::Overflow =>
+                write!(f, "The purchase amounts are too large"),
+            PurchaseError::Database { message } =>
+                write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<diesel::result::Error> for PurchaseError {
+    fn from(error: diesel::result::Error) -> Self {
+        PurchaseError::Database { message: error.to_string() }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, FromForm)]
+pub struct SearchPurchase {
+    pub id: Option<i32>,
+    pub supplier_id: Option<i32>,
+    pub purchase_date: Option<NaiveDateForm>,
+    pub total: Option<Money>,
+    pub observation: Option<String>,
+    pub currency_id: Option<i32>,
+    pub status: Option<PurchaseStatus>
+}
+
+impl Purchase {
+    pub fn list(connection: &PgConnection, limit: i64, offset: i64, search: Option<Search<SearchPurchase>>) ->
+        Result<Vec<Purchase>, diesel::result::Error> {
+            let query = Self::searching_records(search);
+
+            query
+                .limit(limit)
+                .offset(offset)
+                .load(connection)
+    }
+
+    pub fn show(connection: &PgConnection, request_id: i32) -> Result<FullPurchase, diesel::result::Error> {
+        use crate::schema::purchases::dsl::*;
+        use crate::schema::purchase_products;
+
+        let purchase_result = purchases
+            .find(request_id)
+            .get_result::<Purchase>(connection)?;
+
+        let purchase_products_result = purchase_products::dsl::purchase_products
+            .filter(purchase_products::dsl::purchase_id.eq(purchase_result.id))
+            .load::<PurchaseProduct>(connection)?;
+
+        Ok(FullPurchase {
+            purchase: purchase_result,
+            purchase_products: purchase_products_result
+        })
+    }
+
+    pub fn create(connection: &PgConnection, full_new_purchase: FullNewPurchase) -> Result<Purchase, PurchaseError> {
+        connection.transaction(|| {
+            let full_new_purchase = full_new_purchase
+                .with_variants(connection)?
+                .with_units(connection)?;
+            let purchase = diesel::insert_into(purchases::table)
+                .values(&full_new_purchase.purchase_with_calculations()?)
+                .get_result::<Purchase>(connection)?;
+
+            PurchaseProduct::batch_action(connection, full_new_purchase.purchase_products, purchase.id)?;
+
+            Ok(purchase)
+        })
+    }
+
+    pub fn update(connection: &PgConnection, param_id: i32, full_purchase: FullNewPurchase) -> Result<Purchase, PurchaseError> {
+        use crate::schema::purchases::dsl::*;
+
+        connection.transaction(|| {
+            Self::editable(connection, param_id)?;
+            let full_purchase = full_purchase
+                .with_variants(connection)?
+                .with_units(connection)?;
+            let purchase = diesel::update(purchases.find(param_id))
+                .set((supplier_id.eq(full_purchase.purchase.supplier_id),
+                      currency_id.eq(full_purchase.purchase.currency_id),
+                      purchase_date.eq(&full_purchase.purchase.purchase_date),
+                      observation.eq(&full_purchase.purchase.observation),
+                      update_costs.eq(full_purchase.purchase.update_costs.unwrap_or(false)),
+                      sub_total.eq(full_purchase.calculate_sub_total()?),
+                      sub_total_without_discount.eq(full_purchase.subtotal_without_discount()?),
+                      discount_calculated.eq(full_purchase.calculate_discount()?),
+                      taxes_calculated.eq(full_purchase.calculate_taxes()?),
+                      total.eq(full_purchase.calculate_total()?)))
+                .get_result::<Purchase>(connection)?;
+
+            PurchaseProduct::batch_action(connection, full_purchase.purchase_products, purchase.id)?;
+
+            Ok(purchase)
+        })
+    }
+
+    pub fn delete(connection: &PgConnection, param_id: i32) -> Result<usize, PurchaseError> {
+        use crate::schema::purchases::dsl::*;
+
+        connection.transaction(|| {
+            Self::editable(connection, param_id)?;
+
+            let deleted = diesel::delete(purchases.find(param_id))
+                .execute(connection)?;
+
+            Ok(deleted)
+        })
+    }
+
+    /// Loads and locks the purchase, failing unless it's still a draft. The
+    /// stock and costs of received purchases are only undone by cancelling.
+    fn editable(connection: &PgConnection, param_id: i32) -> Result<Purchase, PurchaseError> {
+        use crate::schema::purchases::dsl::*;
+
+        let purchase = purchases
+            .find(param_id)
+            .for_update()
+            .get_result::<Purchase>(connection)
+            .map_err(|error| match error {
+                diesel::result::Error::NotFound => PurchaseError::PurchaseNotFound { purchase_id: param_id },
+                error => PurchaseError::from(error),
+            })?;
+
+        if purchase.status != PurchaseStatus::Draft {
+            return Err(PurchaseError::Locked {
+                purchase_id: purchase.id,
+                status: purchase.status,
+                reason: "Only draft purchases can be changed, cancel it instead".to_string()
+            });
+        }
+
+        Ok(purchase)
+    }
+
+    pub fn supplier(&self, connection: &PgConnection) -> Result<Supplier, diesel::result::Error> {
+        Supplier::show(connection, self.supplier_id)
+    }
+
+    fn searching_records<'a>(search: Option<Search<SearchPurchase>>) -> BoxedQuery<'a> {
+        use crate::schema::purchases::dsl::*;
+
+        let mut query = schema::purchases::table.into_boxed::<diesel::pg::Pg>();
+
+        if let Some(search_purchase) = search {
+            let Search(purchase) = search_purchase;
+            if let Some(purchase_id) = purchase.id {
+                query = query.filter(id.eq(purchase_id));
+            }
+            if let Some(purchase_supplier_id) = purchase.supplier_id {
+                query = query.filter(supplier_id.eq(purchase_supplier_id));
+            }
+            if let Some(purchase_purchase_date) = purchase.purchase_date {
+                query = query.filter(purchase_date.eq(purchase_purchase_date));
+            }
+            if let Some(purchase_observation) = purchase.observation {
+                query = query.filter(observation.like(purchase_observation));
+            }
+            if let Some(purchase_status) = purchase.status {
+                query = query.filter(status.eq(purchase_status));
+            }
+        }
+
+        query
+    }
+}
+
+impl FullNewPurchase {
+    /// Checks that every line bought as a variant names one of its
+    /// product's variants.
+    pub fn with_variants(self, connection: &PgConnection) -> Result<Self, PurchaseError> {
+        for purchase_product in &self.purchase_products {
+            if let Some(variant_id) = purchase_product.variant_id {
+                let product_id = purchase_product.product_id;
+                ProductVariant::find(connection, product_id, variant_id)?
+                    .ok_or(PurchaseError::UnknownVariant { product_id, variant_id })?;
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Checks that every line bought in a unit of its own can be counted in
+    /// its product's unit, keeping the factor on the line for its stock.
+    pub fn with_units(mut self, connection: &PgConnection) -> Result<Self, PurchaseError> {
+        for purchase_product in self.purchase_products.iter_mut() {
+            let product_id = purchase_product.product_id;
+            let unit_factor = Unit::to_base(connection, product_id, purchase_product.unit_id)?
+                .ok_or(PurchaseError::IncompatibleUnit { product_id, unit_id: purchase_product.unit_id.unwrap_or_default() })?;
+            purchase_product.unit_factor = Some(unit_factor);
+        }
+
+        Ok(self)
+    }
+
+    pub fn purchase_with_calculations(&self) -> Result<NewPurchase, PurchaseError> {
+        let mut purchase = self.purchase.clone();
+        purchase.sub_total = Some(self.calculate_sub_total()?);
+        purchase.sub_total_without_discount = Some(self.subtotal_without_discount()?);
+        purchase.discount_calculated = Some(self.calculate_discount()?);
+        purchase.taxes_calculated = Some(self.calculate_taxes()?);
+        purchase.total = Some(self.calculate_total()?);
+        Ok(purchase)
+    }
+
+    pub fn calculate_sub_total(&self) -> Result<Money, PurchaseError> {
+        let items = self.get_items();
+        let calculation = Calculation::new(items);
+        calculation.subtotal().ok_or(PurchaseError::Overflow)
+    }
+
+    pub fn calculate_total(&self) -> Result<Money, PurchaseError> {
+        let items = self.get_items();
+        let calculation = Calculation::new(items);
+        calculation.calculate_total().ok_or(PurchaseError::Overflow)
+    }
+
+    pub fn subtotal_without_discount(&self) -> Result<Money, PurchaseError> {
+        let items = self.get_items();
+        let calculation = Calculation::new(items);
+        calculation.subtotal_without_discount().ok_or(PurchaseError::Overflow)
+    }
+
+    pub fn calculate_discount(&self) -> Result<Money, PurchaseError> {
+        let items = self.get_items();
+        let calculation = Calculation::new(items);
+        calculation.calculate_discount().ok_or(PurchaseError::Overflow)
+    }
+
+    pub fn calculate_taxes(&self) -> Result<Money, PurchaseError> {
+        let items = self.get_items();
+        let calculation = Calculation::new(items);
+        calculation.calculate_taxes().ok_or(PurchaseError::Overflow)
+    }
+
+    fn get_items(&self) -> Vec<ItemCalculation> {
+         self
+        .purchase_products
+        .iter()
+        .map(|new_purchase_product| new_purchase_product.to_item_calc_method())
+        .collect::<Vec<ItemCalculation>>()
+    }
+}
+
+impl FromStr for Purchase {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+impl FromStr for SearchPurchase {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+impl FromData for FullNewPurchase {
+    type Error = String;
+
+    fn from_data( _: &Request, data: Data) -> Outcome<Self, String> {
+        let mut string_data = String::new();
+        if let Err(e) = data.open().read_to_string(&mut string_data) {
+            return Failure((Status::InternalServerError, format!("{:?}", e)));
+        }
+
+        let maybe_full_new_purchase = serde_json::from_str(&string_data);
+
+        match maybe_full_new_purchase {
+            Ok(ok_full_new_purchase) => {
+                let full_new_purchase: FullNewPurchase = ok_full_new_purchase;
+                if full_new_purchase.purchase_products.is_empty() {
+                    return Failure(( Status::UnprocessableEntity, "No products selected!".to_string() ));
+                }
+                Success(full_new_purchase)
+            },
+            Err(err) => Failure((Status::BadRequest, err.to_string()))
+        }
+    }
+}
+
+use rocket::response::{self, Responder, content};
+
+impl<'r> Responder<'r> for Purchase {
+    fn respond_to(self, request: &Request) -> response::Result<'r> {
+        serde_json::to_string(&self)
+            .map(|value| content::Json(value).respond_to(request).unwrap())
+            .map_err(|_| Status::InternalServerError)
+    }
+}
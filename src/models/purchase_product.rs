@@ -0,0 +1,160 @@
+use diesel;
+use diesel::RunQueryDsl;
+use diesel::OptionalExtension;
+use diesel::QueryDsl;
+use diesel::ExpressionMethods;
+use diesel::BoolExpressionMethods;
+use diesel::pg::PgConnection;
+use crate::schema::purchase_products;
+use crate::models::money::Money;
+use crate::models::item_calculation::ItemCalculation;
+use crate::models::purchase::PurchaseError;
+
+#[derive(AsChangeset, Insertable, Serialize, Deserialize, Clone, PartialEq, Identifiable, Associations,
+         Queryable, Debug, FromForm, FromData, Responder)]
+pub struct PurchaseProduct {
+    pub id: i32,
+    pub purchase_id: i32,
+    pub product_id: i32,
+    pub tax: Money,
+    pub amount: f64,
+    pub price: Money,
+    pub discount: Money,
+    pub subtotal: Money,
+    pub sub_total_without_discount: Money,
+    pub discount_calculated: Money,
+    pub taxes_calculated: Money,
+    pub total: Money,
+    pub observation: Option<String>,
+    pub unit_id: Option<i32>,
+    pub variant_id: Option<i32>,
+    pub unit_factor: f64,
+}
+
+#[derive(Insertable, Serialize, Deserialize, Clone, Debug, FromForm, FromData)]
+#[table_name="purchase_products"]
+pub struct NewPurchaseProduct {
+    pub purchase_id: Option<i32>,
+    pub product_id: i32,
+    pub tax: Money,
+    pub amount: f64,
+    pub price: Money,
+    pub discount: Option<Money>,
+    pub subtotal: Option<Money>,
+    pub sub_total_without_discount: Option<Money>,
+    pub discount_calculated: Option<Money>,
+    pub taxes_calculated: Option<Money>,
+    pub total: Option<Money>,
+    pub observation: Option<String>,
+    pub unit_id: Option<i32>,
+    pub variant_id: Option<i32>,
+    #[serde(skip_deserializing)]
+    pub unit_factor: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, FromForm, Responder)]
+pub struct SearchPurchaseProduct {
+    pub id: Option<i32>,
+    pub purchase_id: Option<i32>,
+    pub product_id: Option<i32>,
+    pub tax: Option<i32>,
+    pub amount: Option<f64>,
+    pub price: Option<i32>,
+    pub discount: Option<i32>,
+    pub subtotal: Option<f64>,
+    pub sub_total_without_discount: Option<f64>,
+    pub discount_calculated: Option<f64>,
+    pub taxes_calculated: Option<f64>,
+    pub total: Option<f64>,
+    pub observation: Option<String>,
+}
+
+impl PurchaseProduct {
+    pub fn batch_action(connection: &PgConnection, vec_purchase_products: Vec<NewPurchaseProduct>, purchase_id: i32) -> Result<bool, PurchaseError> {
+        use crate::schema::purchase_products::dsl;
+
+        for mut new_purchase_product in vec_purchase_products {
+            new_purchase_product.purchase_id = Some(purchase_id);
+
+            let query = dsl::purchase_products
+                .filter(dsl::product_id.eq(new_purchase_product.product_id).and(dsl::purchase_id.eq(purchase_id)))
+                .into_boxed::<diesel::pg::Pg>();
+            let query = match new_purchase_product.variant_id {
+                Some(variant_id) => query.filter(dsl::variant_id.eq(variant_id)),
+                None => query.filter(dsl::variant_id.is_null())
+            };
+
+            let result_purchase_product = query
+                .first::<PurchaseProduct>(connection)
+                .optional()?;
+
+            if let Some(edit_purchase_product) = result_purchase_product {
+                let discount = (&new_purchase_product).discount.clone();
+                diesel::update(dsl::purchase_products.find(edit_purchase_product.id))
+                    .set((dsl::tax.eq(&new_purchase_product.tax),
+                          dsl::amount.eq(&new_purchase_product.amount),
+                          dsl::price.eq(&new_purchase_product.price),
+                          dsl::discount.eq(discount.unwrap_or(Money(0))),
+                          dsl::unit_id.eq(new_purchase_product.unit_id),
+                          dsl::unit_factor.eq(new_purchase_product.unit_factor.unwrap_or(1.0)),
+                          dsl::subtotal.eq(new_purchase_product.calculate_sub_total()?),
+                          dsl::sub_total_without_discount.eq(new_purchase_product.subtotal_without_discount()?),
+                          dsl::discount_calculated.eq(new_purchase_product.calculate_discount()?),
+                          dsl::taxes_calculated.eq(new_purchase_product.calculate_taxes()?),
+                          dsl::total.eq(new_purchase_product.calculate_total()?)))
+                    .get_result::<PurchaseProduct>(connection)?;
+            } else {
+                diesel::insert_into(purchase_products::table)
+                    .values(&new_purchase_product.with_calculations()?)
+                    .get_result::<PurchaseProduct>(connection)?;
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+impl NewPurchaseProduct {
+
+    pub fn with_calculations(&self) -> Result<Self, PurchaseError> {
+        let mut new_purchase_product = self.clone();
+        new_purchase_product.discount = Some(self.calculate_discount()?);
+        new_purchase_product.subtotal = Some(self.calculate_sub_total()?);
+        new_purchase_product.sub_total_without_discount = Some(self.subtotal_without_discount()?);
+        new_purchase_product.discount_calculated = Some(self.calculate_discount()?);
+        new_purchase_product.taxes_calculated = Some(self.calculate_taxes()?);
+        new_purchase_product.total = Some(self.calculate_total()?);
+        Ok(new_purchase_product)
+    }
+
+    pub fn to_item_calc_method(&self) -> ItemCalculation {
+        let discount = (&self).discount.clone();
+        ItemCalculation::new(&self.tax, &discount.unwrap_or(Money(0)), &self.price, self.amount)
+    }
+
+    pub fn calculate_total(&self) -> Result<Money, PurchaseError> {
+        let item_calc = self.to_item_calc_method();
+        item_calc.calculate_total().ok_or(PurchaseError::Overflow)
+    }
+
+    pub fn calculate_sub_total(&self) -> Result<Money, PurchaseError> {
+        let item_calc = self.to_item_calc_method();
+        item_calc.subtotal().ok_or(PurchaseError::Overflow)
+    }
+
+    pub fn subtotal_without_discount(&self) -> Result<Money, PurchaseError> {
+        let item_calc = self.to_item_calc_method();
+        item_calc.subtotal_without_discount().ok_or(PurchaseError::Overflow)
+    }
+
+    pub fn calculate_discount(&self) -> Result<Money, PurchaseError> {
+        let item_calc = self.to_item_calc_method();
+        item_calc.calculate_discount().ok_or(PurchaseError::Overflow)
+    }
+
+    pub fn calculate_taxes(&self) -> Result<Money, PurchaseError> {
+        let item_calc = self.to_item_calc_method();
+        item_calc.calculate_taxes().ok_or(PurchaseError::Overflow)
+    }
+
+}
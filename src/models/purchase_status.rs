@@ -0,0 +1,74 @@
+use diesel;
+use diesel::prelude::*;
+use diesel::query_dsl::RunQueryDsl;
+use diesel::QueryDsl;
+use diesel::pg::PgConnection;
+use crate::models::purchase::Purchase;
+use crate::models::product_cost::ProductCost;
+use crate::models::stock_movement::StockMovement;
+use crate::models::stock_movement::StockDocument;
+use rocket::http::RawStr;
+use rocket::request::FromFormValue;
+use std::default::Default;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, DbEnum)]
+pub enum PurchaseStatus {
+    Draft,
+    Received,
+    Cancelled,
+}
+
+impl Default for PurchaseStatus {
+    fn default() -> PurchaseStatus {
+        PurchaseStatus::Draft
+    }
+}
+
+impl<'v> FromFormValue<'v> for PurchaseStatus {
+    type Error = &'v RawStr;
+
+    fn from_form_value(form_value: &'v RawStr) -> Result<PurchaseStatus, &'v RawStr> {
+        match form_value.as_str() {
+            "draft" => Ok(PurchaseStatus::Draft),
+            "received" => Ok(PurchaseStatus::Received),
+            "cancelled" => Ok(PurchaseStatus::Cancelled),
+            _ => Err(form_value),
+        }
+    }
+}
+
+impl PurchaseStatus {
+    pub fn to_received(connection: &PgConnection, id: i32) -> Result<bool, String> {
+        Self::save_status(connection, id, PurchaseStatus::Draft, PurchaseStatus::Received, StockDocument::Purchase)
+    }
+
+    pub fn to_cancelled(connection: &PgConnection, id: i32) -> Result<bool, String> {
+        Self::save_status(connection, id, PurchaseStatus::Received, PurchaseStatus::Cancelled, StockDocument::PurchaseCancellation)
+    }
+
+    fn save_status(
+        connection: &PgConnection,
+        id: i32,
+        previous_status: PurchaseStatus,
+        next_status: PurchaseStatus,
+        stock_document: StockDocument,
+    ) -> Result<bool, String> {
+        use crate::schema::purchases::dsl;
+
+        connection
+            .transaction::<_, diesel::result::Error, _>(|| {
+                let purchase = diesel::update(dsl::purchases.find(id).filter(dsl::status.eq(previous_status)))
+                    .set(dsl::status.eq(next_status))
+                    .get_result::<Purchase>(connection)?;
+
+                StockMovement::register_purchase(connection, id, stock_document)?;
+
+                if purchase.update_costs && stock_document == StockDocument::Purchase {
+                    ProductCost::update_from_purchase(connection, &purchase)?;
+                }
+
+                Ok(true)
+            })
+            .map_err(|_| "Not valid State".to_string())
+    }
+}
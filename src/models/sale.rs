@@ -1,19 +1,39 @@
+use std::fmt;
 use std::io::Read;
 use std::str::FromStr;
+use std::collections::HashMap;
+use std::collections::HashSet;
 use diesel;
 use diesel::sql_types;
 use diesel::prelude::*;
-use crate::models::db_connection::*;
+use diesel::pg::PgConnection;
 use crate::models::naive_date_form::NaiveDateForm;
 use crate::models::sale_product::SaleProduct;
 use crate::models::sale_product::NewSaleProduct;
 use crate::models::calculation::Calculation;
+use crate::models::calculation::Adjustment;
 use crate::models::item_calculation::ItemCalculation;
-use crate::models::money::Money;
+use crate::models::item_calculation::TaxBreakdown;
+use crate::models::money::{ Money, RoundingMode };
 use crate::models::sale_status::SaleStatus;
 use crate::models::sale_status::SaleStatusMapping;
 use crate::models::client::Client;
 use crate::models::client::BasicModelActions;
+use crate::models::currency::Currency;
+use crate::models::currency::BasicModelActions as CurrencyModelActions;
+use crate::models::price::Price;
+use crate::models::price::BasicModelActions as PriceModelActions;
+use crate::models::product_price::ProductPrice;
+use crate::models::product_variant::ProductVariant;
+use crate::models::unit::Unit;
+use crate::models::pricing_rule::PricingRule;
+use crate::models::exchange_rate::ExchangeRate;
+use crate::models::tax::Tax;
+use crate::models::tax::BasicModelActions as TaxModelActions;
+use crate::models::product_tax::ProductTax;
+use crate::models::sale_tax::SaleTax;
+use crate::models::sale_adjustment::SaleAdjustment;
+use crate::models::sale_adjustment::NewSaleAdjustment;
 use rocket::{ Request, Data };
 use rocket::data:: { FromData, Outcome };
 use rocket::http::Status;
@@ -27,15 +47,19 @@ type BoxedQuery<'a> =
     diesel::query_builder::BoxedSelectStatement<'a, (sql_types::Integer,
                                                      sql_types::Integer,
                                                      sql_types::Date,
-                                                     sql_types::Integer,
-                                                     sql_types::Integer,
-                                                     sql_types::Integer,
-                                                     sql_types::Integer,
-                                                     sql_types::Integer,
+                                                     sql_types::BigInt,
+                                                     sql_types::BigInt,
+                                                     sql_types::BigInt,
+                                                     sql_types::BigInt,
+                                                     sql_types::BigInt,
                                                      sql_types::Nullable<sql_types::Text>,
                                                      sql_types::Integer,
                                                      SaleStatusMapping,
-                                                     sql_types::Nullable<sql_types::Date>
+                                                     sql_types::Nullable<sql_types::Date>,
+                                                     sql_types::Nullable<sql_types::Integer>,
+                                                     sql_types::Nullable<sql_types::Text>,
+                                                     sql_types::Double,
+                                                     sql_types::BigInt
                                                      ),
                                                      schema::sales::table, diesel::pg::Pg>;
 
@@ -53,9 +77,12 @@ pub struct Sale {
     pub total: Money,
     pub observation: Option<String>,
     pub currency_id: i32,
-    #[serde(skip_deserializing)]
     pub status: SaleStatus,
-    pub expiring_date: Option<NaiveDateForm>
+    pub expiring_date: Option<NaiveDateForm>,
+    pub series_id: Option<i32>,
+    pub number: Option<String>,
+    pub exchange_rate: f64,
+    pub adjustments_calculated: Money
 }
 
 #[derive(Insertable, Serialize, Deserialize, Clone, Debug, FromForm)]
@@ -72,19 +99,97 @@ pub struct NewSale {
     pub currency_id: i32,
     #[serde(skip_deserializing)]
     pub status: SaleStatus,
-    pub expiring_date: Option<NaiveDateForm>
+    pub expiring_date: Option<NaiveDateForm>,
+    pub series_id: Option<i32>,
+    #[serde(skip_deserializing)]
+    pub exchange_rate: Option<f64>,
+    pub adjustments_calculated: Option<Money>
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct FullSale {
     pub sale: Sale,
-    pub sale_products: Vec<SaleProduct>
+    pub sale_products: Vec<SaleProduct>,
+    pub taxes: Vec<SaleTax>,
+    pub adjustments: Vec<SaleAdjustment>,
+    pub formatted: FormattedSale
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FormattedSale {
+    pub sub_total: String,
+    pub sub_total_without_discount: String,
+    pub discount_calculated: String,
+    pub adjustments_calculated: String,
+    pub taxes_calculated: String,
+    pub total: String
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct FullNewSale {
     sale: NewSale,
-    sale_products: Vec<NewSaleProduct>
+    sale_products: Vec<NewSaleProduct>,
+    #[serde(default)]
+    adjustments: Vec<NewSaleAdjustment>,
+    #[serde(skip)]
+    taxes: HashMap<i32, Tax>,
+    #[serde(skip)]
+    listed_prices: HashSet<usize>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "error", rename_all = "snake_case")]
+pub enum SaleError {
+    Locked { sale_id: i32, status: SaleStatus, reason: String },
+    SaleNotFound { sale_id: i32 },
+    MissingExchangeRate { from_currency_id: i32, to_currency_id: i32 },
+    TaxNotFound { tax_id: i32 },
+    InvalidAdjustment { reason: String },
+    MissingPrice { product_id: i32, price_id: Option<i32> },
+    UnknownVariant { product_id: i32, variant_id: i32 },
+    DuplicateLine { product_id: i32, variant_id: Option<i32> },
+    IncompatibleUnit { product_id: i32, unit_id: i32 },
+    Overflow,
+    Database { message: String },
+}
+
+impl fmt::Display for SaleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaleError::Locked { sale_id, status, .. } =>
+                write!(f, "Sale {} is {:?} and can't be changed", sale_id, status),
+            SaleError::SaleNotFound { sale_id } =>
+                write!(f, "Sale {} not found", sale_id),
+            SaleError::MissingExchangeRate { from_currency_id, to_currency_id } =>
+                write!(f, "There's no exchange rate from currency {} to {}", from_currency_id, to_currency_id),
+            SaleError::TaxNotFound { tax_id } =>
+                write!(f, "Tax {} not found", tax_id),
+            SaleError::InvalidAdjustment { reason } =>
+                write!(f, "{}", reason),
+            SaleError::MissingPrice { product_id, price_id: Some(price_id) } =>
+                write!(f, "Product {} has no price in price list {}", product_id, price_id),
+            SaleError::MissingPrice { product_id, price_id: None } =>
+                write!(f, "Product {} has no price and the client has no price list", product_id),
+            SaleError::UnknownVariant { product_id, variant_id } =>
+                write!(f, "Variant {} isn't one of product {}'s variants", variant_id, product_id),
+            SaleError::DuplicateLine { product_id, variant_id: Some(variant_id) } =>
+                write!(f, "Variant {} of product {} is on more than one line", variant_id, product_id),
+            SaleError::DuplicateLine { product_id, variant_id: None } =>
+                write!(f, "Product {} is on more than one line", product_id),
+            SaleError::IncompatibleUnit { product_id, unit_id } =>
+                write!(f, "Unit {} can't be converted to product {}'s unit", unit_id, product_id),
+            SaleError::Overflow =>
+                write!(f, "The sale amounts are too large"),
+            SaleError::Database { message } =>
+                write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<diesel::result::Error> for SaleError {
+    fn from(error: diesel::result::Error) -> Self {
+        SaleError::Database { message: error.to_string() }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, FromForm)]
@@ -100,89 +205,168 @@ pub struct SearchSale {
     pub observation: Option<String>,
     pub currency_id: Option<i32>,
     pub status: Option<SaleStatus>,
-    pub expiring_date: Option<NaiveDateForm>
+    pub expiring_date: Option<NaiveDateForm>,
+    pub series_id: Option<i32>,
+    pub number: Option<String>
 }
 
 impl Sale {
-    pub fn list(limit: i64, offset: i64, search: Option<Search<SearchSale>>) ->
+    pub fn list(connection: &PgConnection, limit: i64, offset: i64, search: Option<Search<SearchSale>>) ->
         Result<Vec<Sale>, diesel::result::Error> {
-            let connection = establish_connection();
-            
             let query = Self::searching_records(search);
 
             query
                 .limit(limit)
                 .offset(offset)
-                .load(&connection)
+                .load(connection)
     }
 
-    pub fn show(request_id: i32) -> Result<FullSale, diesel::result::Error> {
+    pub fn show(connection: &PgConnection, request_id: i32) -> Result<FullSale, diesel::result::Error> {
         use crate::schema::sales::dsl::*;
         use crate::schema::sale_products;
 
-        let connection = establish_connection();
-
         let sale_result = sales
             .find(request_id)
-            .get_result::<Sale>(&connection)?;
+            .get_result::<Sale>(connection)?;
 
         let sale_products_result = sale_products::dsl::sale_products
             .filter(sale_products::dsl::sale_id.eq(sale_result.id))
-            .load::<SaleProduct>(&connection)?;
+            .load::<SaleProduct>(connection)?;
+
+        let currency = Currency::show(connection, sale_result.currency_id)?;
+        let taxes_result = SaleTax::list(connection, sale_result.id)?;
+        let adjustments_result = SaleAdjustment::list(connection, sale_result.id)?;
 
         Ok(FullSale {
+            formatted: sale_result.formatted(&currency),
             sale: sale_result,
-            sale_products: sale_products_result
+            sale_products: sale_products_result,
+            taxes: taxes_result,
+            adjustments: adjustments_result
         })
     }
  
-    pub fn create(full_new_sale: FullNewSale) -> Result<Sale, diesel::result::Error> {
-        let connection = establish_connection();
-
-        let sale: Result<Sale, diesel::result::Error> = diesel::insert_into(sales::table)
-            .values(&full_new_sale.sale_with_calculations())
-            .get_result(&connection);
+    pub fn create(connection: &PgConnection, full_new_sale: FullNewSale) -> Result<Sale, SaleError> {
+        connection.transaction(|| {
+            let full_new_sale = full_new_sale
+                .without_pricing_rules()
+                .with_variants(connection)?
+                .with_units(connection)?
+                .with_prices(connection)?
+                .with_pricing_rules(connection)?
+                .with_exchange_rates(connection)?
+                .with_taxes(connection)?
+                .with_adjustments()?;
+            let tax_breakdown = full_new_sale.tax_breakdown()?;
+
+            let sale = diesel::insert_into(sales::table)
+                .values(&full_new_sale.sale_with_calculations()?)
+                .get_result::<Sale>(connection)?;
+
+            SaleProduct::batch_action(connection, full_new_sale.sale_products, &full_new_sale.taxes, sale.id)?;
+            SaleTax::replace(connection, sale.id, tax_breakdown)?;
+            SaleAdjustment::replace(connection, sale.id, full_new_sale.adjustments)?;
+
+            Ok(sale)
+        })
+    }
 
-        if let Ok(db_sale) = &sale {
-            SaleProduct::batch_action(full_new_sale.sale_products, db_sale.id)?;
-        }
+    pub fn update(connection: &PgConnection, param_id: i32, full_sale: FullNewSale) -> Result<Sale, SaleError> {
+        use crate::schema::sales::dsl::*;
 
-        sale
+        connection.transaction(|| {
+            Self::editable(connection, param_id)?;
+            let full_sale = full_sale
+                .without_pricing_rules()
+                .with_variants(connection)?
+                .with_units(connection)?
+                .with_prices(connection)?
+                .with_pricing_rules(connection)?
+                .with_exchange_rates(connection)?
+                .with_taxes(connection)?
+                .with_adjustments()?;
+            let tax_breakdown = full_sale.tax_breakdown()?;
+
+            let sale = diesel::update(sales.find(param_id))
+                .set((client_id.eq(full_sale.sale.client_id),
+                      currency_id.eq(full_sale.sale.currency_id),
+                      series_id.eq(full_sale.sale.series_id),
+                      exchange_rate.eq(full_sale.sale.exchange_rate.unwrap_or(1.0)),
+                      sale_date.eq(&full_sale.sale.sale_date),
+                      observation.eq(&full_sale.sale.observation),
+                      sub_total.eq(full_sale.calculate_sub_total()?),
+                      sub_total_without_discount.eq(full_sale.subtotal_without_discount()?),
+                      discount_calculated.eq(full_sale.calculate_discount()?),
+                      adjustments_calculated.eq(full_sale.calculate_adjustments()?),
+                      taxes_calculated.eq(full_sale.calculate_taxes()?),
+                      total.eq(full_sale.calculate_total()?)))
+                .get_result::<Sale>(connection)?;
+
+            SaleProduct::batch_action(connection, full_sale.sale_products, &full_sale.taxes, sale.id)?;
+            SaleTax::replace(connection, sale.id, tax_breakdown)?;
+            SaleAdjustment::replace(connection, sale.id, full_sale.adjustments)?;
+
+            Ok(sale)
+        })
     }
 
-    pub fn update(param_id: i32, full_sale: FullNewSale) -> Result<Sale, diesel::result::Error> {
+    pub fn delete(connection: &PgConnection, param_id: i32) -> Result<usize, SaleError> {
         use crate::schema::sales::dsl::*;
-        let connection = establish_connection();
-
-        let sale = diesel::update(sales.find(param_id))
-            .set((client_id.eq(full_sale.sale.client_id),
-                  currency_id.eq(full_sale.sale.currency_id),
-                  sale_date.eq(&full_sale.sale.sale_date),
-                  observation.eq(&full_sale.sale.observation),
-                  sub_total.eq(full_sale.calculate_sub_total()),
-                  sub_total_without_discount.eq(full_sale.subtotal_without_discount()),
-                  discount_calculated.eq(full_sale.calculate_discount()),
-                  taxes_calculated.eq(full_sale.calculate_taxes()),
-                  total.eq(full_sale.calculate_total())))
-            .get_result::<Sale>(&connection);
-
-        if let Ok(db_sale) = &sale {
-            SaleProduct::batch_action(full_sale.sale_products, db_sale.id)?;
-        }
+        use crate::schema::sale_products;
+
+        connection.transaction(|| {
+            Self::editable(connection, param_id)?;
+
+            diesel::delete(sale_products::dsl::sale_products.filter(sale_products::dsl::sale_id.eq(param_id)))
+                .execute(connection)?;
+            SaleTax::delete(connection, param_id)?;
+            SaleAdjustment::delete(connection, param_id)?;
+
+            let deleted = diesel::delete(sales.find(param_id))
+                .execute(connection)?;
 
-        sale
+            Ok(deleted)
+        })
     }
 
-    pub fn delete(param_id: i32) -> Result<usize, diesel::result::Error> {
+    /// Loads and locks the sale, failing unless it's still a draft. Issued
+    /// sales are only corrected through cancellations or credit notes.
+    fn editable(connection: &PgConnection, param_id: i32) -> Result<Sale, SaleError> {
         use crate::schema::sales::dsl::*;
-        let connection = establish_connection();
 
-        diesel::delete(sales.find(param_id))
-            .execute(&connection)
+        let sale = sales
+            .find(param_id)
+            .for_update()
+            .get_result::<Sale>(connection)
+            .map_err(|error| match error {
+                diesel::result::Error::NotFound => SaleError::SaleNotFound { sale_id: param_id },
+                error => SaleError::from(error),
+            })?;
+
+        if sale.status != SaleStatus::Draft {
+            return Err(SaleError::Locked {
+                sale_id: sale.id,
+                status: sale.status,
+                reason: "Only draft sales can be changed, cancel it or issue a credit note instead".to_string()
+            });
+        }
+
+        Ok(sale)
+    }
+
+    pub fn formatted(&self, currency: &Currency) -> FormattedSale {
+        FormattedSale {
+            sub_total: self.sub_total.format(currency),
+            sub_total_without_discount: self.sub_total_without_discount.format(currency),
+            discount_calculated: self.discount_calculated.format(currency),
+            adjustments_calculated: self.adjustments_calculated.format(currency),
+            taxes_calculated: self.taxes_calculated.format(currency),
+            total: self.total.format(currency)
+        }
     }
 
-    pub fn client(&self) -> Result<Client, diesel::result::Error> {
-        Client::show(self.client_id)
+    pub fn client(&self, connection: &PgConnection) -> Result<Client, diesel::result::Error> {
+        Client::show(connection, self.client_id)
     }
 
     fn searching_records<'a>(search: Option<Search<SearchSale>>) -> BoxedQuery<'a> {
@@ -204,6 +388,12 @@ impl Sale {
             if let Some(sale_status) = sale.status {
                 query = query.filter(status.eq(sale_status));
             }
+            if let Some(sale_series_id) = sale.series_id {
+                query = query.filter(series_id.eq(sale_series_id));
+            }
+            if let Some(sale_number) = sale.number {
+                query = query.filter(number.eq(sale_number));
+            }
         }
 
         query
@@ -211,51 +401,301 @@ impl Sale {
 }
 
 impl FullNewSale {
-    pub fn sale_with_calculations(&self) -> NewSale {
+    /// Brings the lines priced from a price list or a pricing rule into the
+    /// sale currency, keeping the rate applied to each line and the rate of
+    /// the sale currency against the default one. Prices sent with the sale
+    /// are already in its currency and are kept as they are.
+    pub fn with_exchange_rates(mut self, connection: &PgConnection) -> Result<Self, SaleError> {
+        let sale_currency_id = self.sale.currency_id;
+        let default_currency_id = Currency::default_currency_id(connection).unwrap_or(sale_currency_id);
+        let sale_date = self.sale.sale_date.clone();
+
+        self.sale.exchange_rate =
+            Some(Self::exchange_rate(connection, default_currency_id, sale_currency_id, &sale_date)?);
+
+        for (index, sale_product) in self.sale_products.iter_mut().enumerate() {
+            if !self.listed_prices.contains(&index) {
+                continue;
+            }
+
+            let price_currency_id = match sale_product.price_id {
+                Some(price_id) => Price::show(connection, price_id)?.currency_id.unwrap_or(default_currency_id),
+                None => default_currency_id
+            };
+            let rate = Self::exchange_rate(connection, price_currency_id, sale_currency_id, &sale_date)?;
+
+            sale_product.price = match sale_product.price {
+                Some(ref price) => Some(price.convert(rate).ok_or(SaleError::Overflow)?),
+                None => None
+            };
+            sale_product.exchange_rate = Some(rate);
+        }
+
+        Ok(self)
+    }
+
+    /// Checks that every line sold as a variant names one of its product's
+    /// variants, and that each product or variant is on a single line, the
+    /// one its stored line is kept as.
+    pub fn with_variants(self, connection: &PgConnection) -> Result<Self, SaleError> {
+        let mut lines = HashSet::new();
+        for sale_product in &self.sale_products {
+            if !lines.insert((sale_product.product_id, sale_product.variant_id)) {
+                return Err(SaleError::DuplicateLine { product_id: sale_product.product_id, variant_id: sale_product.variant_id });
+            }
+            if let Some(variant_id) = sale_product.variant_id {
+                let product_id = sale_product.product_id;
+                ProductVariant::find(connection, product_id, variant_id)?
+                    .ok_or(SaleError::UnknownVariant { product_id, variant_id })?;
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Checks that every line sold in a unit of its own can be counted in
+    /// its product's unit, keeping the factor on the line so its stock moves
+    /// the same even if the product's unit changes later.
+    pub fn with_units(mut self, connection: &PgConnection) -> Result<Self, SaleError> {
+        for sale_product in self.sale_products.iter_mut() {
+            sale_product.unit_factor = Some(Self::unit_factor(connection, sale_product)?);
+        }
+
+        Ok(self)
+    }
+
+    /// Prices the lines that don't carry a price from the client's price
+    /// list as it was on the sale date, remembering the list so the price is
+    /// converted from its currency. List prices are for the product's unit
+    /// and are scaled to the unit the line is sold in.
+    pub fn with_prices(mut self, connection: &PgConnection) -> Result<Self, SaleError> {
+        if self.sale_products.iter().all(|sale_product| sale_product.price.is_some()) {
+            return Ok(self);
+        }
+
+        let client_price_id = Client::show(connection, self.sale.client_id)?.price_id;
+
+        for (index, sale_product) in self.sale_products.iter_mut().enumerate().filter(|(_, sale_product)| sale_product.price.is_none()) {
+            let product_id = sale_product.product_id;
+            let price_id = client_price_id
+                .ok_or(SaleError::MissingPrice { product_id, price_id: None })?;
+            let price = ProductPrice::price(connection, product_id, sale_product.variant_id, price_id, &self.sale.sale_date)
+                .map_err(|error| match error {
+                    diesel::result::Error::NotFound => SaleError::MissingPrice { product_id, price_id: Some(price_id) },
+                    error => SaleError::from(error),
+                })?;
+
+            let unit_factor = Self::unit_factor(connection, sale_product)?;
+            let unit_price = price
+                .times(unit_factor, RoundingMode::from_env())
+                .ok_or(SaleError::IncompatibleUnit { product_id, unit_id: sale_product.unit_id.unwrap_or_default() })?;
+
+            sale_product.price = Some(unit_price);
+            sale_product.price_id = Some(price_id);
+            self.listed_prices.insert(index);
+        }
+
+        Ok(self)
+    }
+
+    /// Takes off what the pricing rules left on lines sent back with the
+    /// rule they were priced with, so the rules are worked out again for
+    /// the current amounts. Rule prices of lines priced from a list go
+    /// back to the list price.
+    pub fn without_pricing_rules(mut self) -> Self {
+        for sale_product in self.sale_products.iter_mut() {
+            if sale_product.pricing_rule_id.take().is_some() {
+                sale_product.discount = None;
+                sale_product.discount_amount = None;
+                if sale_product.price_id.is_some() {
+                    sale_product.price = None;
+                }
+            }
+        }
+
+        self
+    }
+
+    /// Applies to each line the promotion in force on the sale date that
+    /// leaves it cheapest. Lines with a discount of their own are kept as
+    /// they are, but still count towards the amounts of their product,
+    /// counted in the product's unit. Rule prices are in the currency of the
+    /// line's price.
+    pub fn with_pricing_rules(mut self, connection: &PgConnection) -> Result<Self, SaleError> {
+        let sale_date = self.sale.sale_date.clone();
+        let no_taxes = HashMap::new();
+
+        let mut unit_factors = vec![];
+        let mut product_amounts: HashMap<i32, f64> = HashMap::new();
+        for sale_product in &self.sale_products {
+            let unit_factor = Self::unit_factor(connection, sale_product)?;
+            *product_amounts.entry(sale_product.product_id).or_insert(0.0) += sale_product.amount * unit_factor;
+            unit_factors.push(unit_factor);
+        }
+
+        for (index, sale_product) in self.sale_products.iter_mut().enumerate() {
+            let discounted = sale_product.discount.as_ref().map_or(false, |discount| discount.0 != 0) ||
+                sale_product.discount_amount.as_ref().map_or(false, |discount_amount| discount_amount.0 != 0);
+            if discounted {
+                continue;
+            }
+
+            let unit_factor = unit_factors[index];
+            let product_amount = product_amounts[&sale_product.product_id];
+            let best = PricingRule::applicable(connection, sale_product.product_id, &sale_date)?
+                .iter()
+                .filter_map(|pricing_rule| pricing_rule.apply(sale_product, unit_factor, product_amount))
+                .filter_map(|priced| priced
+                            .to_item_calc_method(&no_taxes)
+                            .subtotal_without_discount()
+                            .map(|subtotal| (subtotal, priced)))
+                .min_by(|first, second| first.0.cmp(&second.0));
+
+            if let Some((subtotal, priced)) = best {
+                let current_subtotal = sale_product
+                    .to_item_calc_method(&no_taxes)
+                    .subtotal_without_discount()
+                    .ok_or(SaleError::Overflow)?;
+                if subtotal < current_subtotal {
+                    if priced.price != sale_product.price {
+                        self.listed_prices.insert(index);
+                    }
+                    *sale_product = priced;
+                }
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Links each line to its taxes, the product's default taxes when the
+    /// line doesn't name any, and sets the line tax to their sum.
+    pub fn with_taxes(mut self, connection: &PgConnection) -> Result<Self, SaleError> {
+        for sale_product in self.sale_products.iter_mut() {
+            let tax_ids = match sale_product.tax_ids.take() {
+                Some(tax_ids) => tax_ids,
+                None => ProductTax::tax_ids(connection, sale_product.product_id)?
+            };
+
+            for tax_id in &tax_ids {
+                if !self.taxes.contains_key(tax_id) {
+                    let tax = Tax::show(connection, *tax_id)
+                        .map_err(|error| match error {
+                            diesel::result::Error::NotFound => SaleError::TaxNotFound { tax_id: *tax_id },
+                            error => SaleError::from(error),
+                        })?;
+                    self.taxes.insert(*tax_id, tax);
+                }
+            }
+
+            if !tax_ids.is_empty() {
+                sale_product.tax = Money(tax_ids.iter().map(|tax_id| self.taxes[tax_id].percentage as i64).sum());
+            }
+            sale_product.tax_ids = Some(tax_ids);
+        }
+
+        Ok(self)
+    }
+
+    /// Shares the sale adjustments among the lines and keeps what each one
+    /// came to.
+    pub fn with_adjustments(mut self) -> Result<Self, SaleError> {
+        let adjustments = self.adjustments
+            .iter()
+            .map(|new_sale_adjustment| new_sale_adjustment.to_adjustment())
+            .collect::<Option<Vec<Adjustment>>>()
+            .ok_or_else(|| SaleError::InvalidAdjustment {
+                reason: "Each adjustment needs either a percentage or an amount".to_string()
+            })?;
+
+        let calculation = Calculation::new(self.get_items())
+            .with_adjustments(&adjustments)
+            .ok_or(SaleError::Overflow)?;
+
+        for (sale_product, adjustment) in self.sale_products.iter_mut().zip(calculation.line_adjustments()) {
+            sale_product.adjustment = Some(adjustment);
+        }
+        for (new_sale_adjustment, calculated) in self.adjustments.iter_mut().zip(calculation.adjustment_amounts()) {
+            new_sale_adjustment.calculated = Some(calculated);
+        }
+
+        Ok(self)
+    }
+
+    pub fn tax_breakdown(&self) -> Result<Vec<TaxBreakdown>, SaleError> {
+        Calculation::new(self.get_items())
+            .tax_breakdown()
+            .ok_or(SaleError::Overflow)
+    }
+
+    /// How many of the product's unit one of the line's unit is.
+    fn unit_factor(connection: &PgConnection, sale_product: &NewSaleProduct) -> Result<f64, SaleError> {
+        let product_id = sale_product.product_id;
+        Unit::to_base(connection, product_id, sale_product.unit_id)?
+            .ok_or(SaleError::IncompatibleUnit { product_id, unit_id: sale_product.unit_id.unwrap_or_default() })
+    }
+
+    fn exchange_rate(connection: &PgConnection, from: i32, to: i32, date: &NaiveDateForm) -> Result<f64, SaleError> {
+        ExchangeRate::rate(connection, from, to, date)
+            .map_err(|error| match error {
+                diesel::result::Error::NotFound =>
+                    SaleError::MissingExchangeRate { from_currency_id: from, to_currency_id: to },
+                error => SaleError::from(error),
+            })
+    }
+
+    pub fn sale_with_calculations(&self) -> Result<NewSale, SaleError> {
         let mut sale = self.sale.clone();
-        sale.sub_total = Some(self.calculate_sub_total());
-        sale.sub_total_without_discount = Some(self.subtotal_without_discount());
-        sale.discount_calculated = Some(self.calculate_discount());
-        sale.taxes_calculated = Some(self.calculate_taxes());
-        sale.total = Some(self.calculate_total());
-        sale
+        sale.sub_total = Some(self.calculate_sub_total()?);
+        sale.sub_total_without_discount = Some(self.subtotal_without_discount()?);
+        sale.discount_calculated = Some(self.calculate_discount()?);
+        sale.adjustments_calculated = Some(self.calculate_adjustments()?);
+        sale.taxes_calculated = Some(self.calculate_taxes()?);
+        sale.total = Some(self.calculate_total()?);
+        Ok(sale)
     }
 
-    pub fn calculate_sub_total(&self) -> Money {
+    pub fn calculate_sub_total(&self) -> Result<Money, SaleError> {
         let items = self.get_items();
         let calculation = Calculation::new(items);
-        calculation.subtotal()
+        calculation.subtotal().ok_or(SaleError::Overflow)
     }
 
-    pub fn calculate_total(&self) -> Money {
+    pub fn calculate_total(&self) -> Result<Money, SaleError> {
         let items = self.get_items();
         let calculation = Calculation::new(items);
-        calculation.calculate_total()
+        calculation.calculate_total().ok_or(SaleError::Overflow)
     }
 
-    pub fn subtotal_without_discount(&self) -> Money {
+    pub fn subtotal_without_discount(&self) -> Result<Money, SaleError> {
         let items = self.get_items();
         let calculation = Calculation::new(items);
-        calculation.subtotal_without_discount()
+        calculation.subtotal_without_discount().ok_or(SaleError::Overflow)
     }
 
-    pub fn calculate_discount(&self) -> Money {
+    pub fn calculate_discount(&self) -> Result<Money, SaleError> {
         let items = self.get_items();
         let calculation = Calculation::new(items);
-        calculation.calculate_discount()
+        calculation.calculate_discount().ok_or(SaleError::Overflow)
     }
 
-    pub fn calculate_taxes(&self) -> Money {
+    pub fn calculate_adjustments(&self) -> Result<Money, SaleError> {
         let items = self.get_items();
         let calculation = Calculation::new(items);
-        calculation.calculate_taxes()
+        calculation.calculate_adjustments().ok_or(SaleError::Overflow)
+    }
+
+    pub fn calculate_taxes(&self) -> Result<Money, SaleError> {
+        let items = self.get_items();
+        let calculation = Calculation::new(items);
+        calculation.calculate_taxes().ok_or(SaleError::Overflow)
     }
 
     fn get_items(&self) -> Vec<ItemCalculation> {
          self
         .sale_products
         .iter()
-        .map(|new_sale_product| new_sale_product.to_item_calc_method())
+        .map(|new_sale_product| new_sale_product.to_item_calc_method(&self.taxes))
         .collect::<Vec<ItemCalculation>>()
     }
 }
@@ -280,7 +720,6 @@ impl FromData for FullNewSale {
     type Error = String;
 
     fn from_data( _: &Request, data: Data) -> Outcome<Self, String> {
-
         let mut string_data = String::new();
         if let Err(e) = data.open().read_to_string(&mut string_data) {
             return Failure((Status::InternalServerError, format!("{:?}", e)));
@@ -296,10 +735,7 @@ impl FromData for FullNewSale {
                 }
                 Success(full_new_sale)
             },
-            Err(err) => {
-                println!("Error deserializing {:#?} {:?}", &string_data, err);
-                return Failure((Status::BadRequest, format!("Error deserializing {:?} {:?}", &string_data, err)))
-            }
+            Err(err) => Failure((Status::BadRequest, err.to_string()))
         }
     }
 }
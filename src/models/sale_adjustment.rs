@@ -0,0 +1,96 @@
+use diesel;
+use diesel::RunQueryDsl;
+use diesel::QueryDsl;
+use diesel::ExpressionMethods;
+use diesel::pg::PgConnection;
+use crate::schema::sale_adjustments;
+use crate::models::money::Money;
+use crate::models::calculation::Adjustment;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, DbEnum)]
+pub enum AdjustmentKind {
+    Discount,
+    Surcharge,
+    Shipping,
+}
+
+/// A discount, surcharge or shipping charge on the whole sale, either a
+/// percentage of the lines or a fixed amount shared among them.
+#[derive(Identifiable, Serialize, Deserialize, Queryable, Debug, Clone, PartialEq)]
+#[table_name="sale_adjustments"]
+pub struct SaleAdjustment {
+    pub id: i32,
+    pub sale_id: i32,
+    pub kind: AdjustmentKind,
+    pub percentage: Option<Money>,
+    pub amount: Option<Money>,
+    pub description: Option<String>,
+    pub calculated: Money
+}
+
+#[derive(Serialize, Deserialize, Insertable, Debug, Clone)]
+#[table_name="sale_adjustments"]
+pub struct NewSaleAdjustment {
+    pub sale_id: Option<i32>,
+    pub kind: AdjustmentKind,
+    pub percentage: Option<Money>,
+    pub amount: Option<Money>,
+    pub description: Option<String>,
+    #[serde(skip_deserializing)]
+    pub calculated: Option<Money>
+}
+
+impl SaleAdjustment {
+    pub fn list(connection: &PgConnection, sale_id: i32) -> Result<Vec<SaleAdjustment>, diesel::result::Error> {
+        use crate::schema::sale_adjustments::dsl;
+
+        dsl::sale_adjustments
+            .filter(dsl::sale_id.eq(sale_id))
+            .order(dsl::id)
+            .load::<SaleAdjustment>(connection)
+    }
+
+    pub fn replace(connection: &PgConnection, sale_id: i32, new_sale_adjustments: Vec<NewSaleAdjustment>) -> Result<Vec<SaleAdjustment>, diesel::result::Error> {
+        Self::delete(connection, sale_id)?;
+
+        if new_sale_adjustments.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let new_sale_adjustments = new_sale_adjustments
+            .into_iter()
+            .map(|mut new_sale_adjustment| {
+                new_sale_adjustment.sale_id = Some(sale_id);
+                new_sale_adjustment
+            })
+            .collect::<Vec<NewSaleAdjustment>>();
+
+        diesel::insert_into(sale_adjustments::table)
+            .values(&new_sale_adjustments)
+            .get_results::<SaleAdjustment>(connection)
+    }
+
+    pub fn delete(connection: &PgConnection, sale_id: i32) -> Result<usize, diesel::result::Error> {
+        use crate::schema::sale_adjustments::dsl;
+
+        diesel::delete(dsl::sale_adjustments.filter(dsl::sale_id.eq(sale_id)))
+            .execute(connection)
+    }
+}
+
+impl NewSaleAdjustment {
+    /// The adjustment as the calculation applies it, negative for
+    /// discounts. None unless exactly one of percentage or amount is set.
+    pub fn to_adjustment(&self) -> Option<Adjustment> {
+        let sign = match self.kind {
+            AdjustmentKind::Discount => -1,
+            AdjustmentKind::Surcharge | AdjustmentKind::Shipping => 1,
+        };
+
+        match (&self.percentage, &self.amount) {
+            (Some(percentage), None) => Some(Adjustment::Percentage(Money(sign * percentage.0))),
+            (None, Some(amount)) => Some(Adjustment::Amount(Money(sign * amount.0))),
+            _ => None
+        }
+    }
+}
@@ -1,15 +1,19 @@
 use diesel;
 use diesel::RunQueryDsl;
+use diesel::OptionalExtension;
 use diesel::QueryDsl;
 use diesel::ExpressionMethods;
 use diesel::BoolExpressionMethods;
+use diesel::pg::PgConnection;
+use std::collections::HashMap;
 use crate::schema::sale_products;
-use crate::models::money::Money;
-use crate::models::db_connection::*;
+use crate::models::money::{ Money, RoundingMode };
+use crate::models::tax::Tax;
 use crate::models::item_calculation::ItemCalculation;
+use crate::models::sale::SaleError;
 
 #[derive(AsChangeset, Insertable, Serialize, Deserialize, Clone, PartialEq, Identifiable, Associations,
-         Queryable, Debug, FromForm, FromData, Responder)]
+         Queryable, Debug, FromData, Responder)]
 pub struct SaleProduct {
     pub id: i32,
     pub sale_id: i32,
@@ -24,16 +28,27 @@ pub struct SaleProduct {
     pub taxes_calculated: Money,
     pub total: Money,
     pub observation: Option<String>,
+    pub price_id: Option<i32>,
+    pub exchange_rate: f64,
+    pub tax_ids: Vec<i32>,
+    pub discount_amount: Money,
+    pub tax_included: bool,
+    pub adjustment: Money,
+    pub pricing_rule_id: Option<i32>,
+    pub variant_id: Option<i32>,
+    pub unit_id: Option<i32>,
+    pub unit_factor: f64,
 }
 
-#[derive(Insertable, Serialize, Deserialize, Clone, Debug, FromForm, FromData)]
+#[derive(Insertable, Serialize, Deserialize, Clone, Debug, FromData)]
 #[table_name="sale_products"]
 pub struct NewSaleProduct {
     pub sale_id: Option<i32>,
     pub product_id: i32,
+    #[serde(default)]
     pub tax: Money,
     pub amount: f64,
-    pub price: Money,
+    pub price: Option<Money>,
     pub discount: Option<Money>,
     pub subtotal: Option<Money>,
     pub sub_total_without_discount: Option<Money>,
@@ -41,6 +56,19 @@ pub struct NewSaleProduct {
     pub taxes_calculated: Option<Money>,
     pub total: Option<Money>,
     pub observation: Option<String>,
+    pub price_id: Option<i32>,
+    #[serde(skip_deserializing)]
+    pub exchange_rate: Option<f64>,
+    pub tax_ids: Option<Vec<i32>>,
+    pub discount_amount: Option<Money>,
+    pub tax_included: Option<bool>,
+    #[serde(skip_deserializing)]
+    pub adjustment: Option<Money>,
+    pub pricing_rule_id: Option<i32>,
+    pub variant_id: Option<i32>,
+    pub unit_id: Option<i32>,
+    #[serde(skip_deserializing)]
+    pub unit_factor: Option<f64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, FromForm, Responder)]
@@ -61,83 +89,105 @@ pub struct SearchSaleProduct {
 }
 
 impl SaleProduct {
-    pub fn batch_action(vec_sale_products: Vec<NewSaleProduct>, sale_id: i32) -> Result<bool, diesel::result::Error> {
+    pub fn batch_action(connection: &PgConnection, vec_sale_products: Vec<NewSaleProduct>, taxes: &HashMap<i32, Tax>, sale_id: i32) -> Result<bool, SaleError> {
         use crate::schema::sale_products::dsl;
-        let connection = establish_connection();
 
         for mut new_sale_product in vec_sale_products {
             new_sale_product.sale_id = Some(sale_id);
 
-            let result_sale_product = 
-                dsl::sale_products
-                    .filter(dsl::product_id.eq(new_sale_product.product_id).and(dsl::sale_id.eq(sale_id)))
-                    .first::<SaleProduct>(&connection);
+            let query = dsl::sale_products
+                .filter(dsl::product_id.eq(new_sale_product.product_id).and(dsl::sale_id.eq(sale_id)))
+                .into_boxed::<diesel::pg::Pg>();
+            let query = match new_sale_product.variant_id {
+                Some(variant_id) => query.filter(dsl::variant_id.eq(variant_id)),
+                None => query.filter(dsl::variant_id.is_null())
+            };
+
+            let result_sale_product = query
+                .first::<SaleProduct>(connection)
+                .optional()?;
 
-            if let Ok(edit_sale_product) = result_sale_product {
+            if let Some(edit_sale_product) = result_sale_product {
                 let discount = (&new_sale_product).discount.clone();
+                let item_calc = new_sale_product.to_item_calc_method(taxes);
                 diesel::update(dsl::sale_products.find(edit_sale_product.id))
                     .set((dsl::tax.eq(&new_sale_product.tax),
                           dsl::amount.eq(&new_sale_product.amount),
-                          dsl::price.eq(&new_sale_product.price),
+                          dsl::price.eq(new_sale_product.price.clone().unwrap_or_default()),
                           dsl::discount.eq(discount.unwrap_or(Money(0))),
-                          dsl::subtotal.eq(new_sale_product.calculate_sub_total()),
-                          dsl::sub_total_without_discount.eq(new_sale_product.subtotal_without_discount()),
-                          dsl::discount_calculated.eq(new_sale_product.calculate_discount()),
-                          dsl::taxes_calculated.eq(new_sale_product.calculate_taxes()),
-                          dsl::total.eq(new_sale_product.calculate_total())))
-                    .get_result::<SaleProduct>(&connection)?;
+                          dsl::price_id.eq(new_sale_product.price_id),
+                          dsl::exchange_rate.eq(new_sale_product.exchange_rate.unwrap_or(1.0)),
+                          dsl::tax_ids.eq(new_sale_product.tax_ids.clone().unwrap_or_default()),
+                          dsl::discount_amount.eq(new_sale_product.discount_amount.clone().unwrap_or_default()),
+                          dsl::tax_included.eq(new_sale_product.tax_included.unwrap_or(false)),
+                          dsl::adjustment.eq(new_sale_product.adjustment.clone().unwrap_or_default()),
+                          dsl::pricing_rule_id.eq(new_sale_product.pricing_rule_id),
+                          dsl::unit_id.eq(new_sale_product.unit_id),
+                          dsl::unit_factor.eq(new_sale_product.unit_factor.unwrap_or(1.0)),
+                          dsl::subtotal.eq(item_calc.subtotal().ok_or(SaleError::Overflow)?),
+                          dsl::sub_total_without_discount.eq(item_calc.subtotal_without_discount().ok_or(SaleError::Overflow)?),
+                          dsl::discount_calculated.eq(item_calc.calculate_discount().ok_or(SaleError::Overflow)?),
+                          dsl::taxes_calculated.eq(item_calc.calculate_taxes().ok_or(SaleError::Overflow)?),
+                          dsl::total.eq(item_calc.calculate_total().ok_or(SaleError::Overflow)?)))
+                    .get_result::<SaleProduct>(connection)?;
             } else {
                 diesel::insert_into(sale_products::table)
-                    .values(&new_sale_product.with_calculations())
-                    .get_result::<SaleProduct>(&connection)?;
+                    .values(&new_sale_product.with_calculations(taxes).ok_or(SaleError::Overflow)?)
+                    .get_result::<SaleProduct>(connection)?;
             }
         }
 
         Ok(true)
     }
+
+    /// Calculates `amount` units of the line, taking the matching share of
+    /// its fixed discount and of the sale adjustments.
+    pub fn to_item_calc_method(&self, amount: f64, taxes: &[Tax]) -> ItemCalculation {
+        let rounding = RoundingMode::from_env();
+        let discount_amount = self.discount_amount
+            .prorate(amount, self.amount, rounding)
+            .unwrap_or_else(|| self.discount_amount.clone());
+        let adjustment = self.adjustment
+            .prorate(amount, self.amount, rounding)
+            .unwrap_or_else(|| self.adjustment.clone());
+
+        ItemCalculation::new(&self.tax, &self.discount, &self.price, amount)
+            .with_taxes(taxes)
+            .with_discount_amount(&discount_amount)
+            .with_tax_included(self.tax_included)
+            .with_adjustment(&adjustment)
+    }
 }
 
 impl NewSaleProduct {
 
-    pub fn with_calculations(&self) -> Self {
+    /// The line with its amounts worked out, None when they don't fit.
+    pub fn with_calculations(&self, taxes: &HashMap<i32, Tax>) -> Option<Self> {
+        let item_calc = self.to_item_calc_method(taxes);
         let mut new_sale_product = self.clone();
-        new_sale_product.discount = Some(self.calculate_discount());
-        new_sale_product.subtotal = Some(self.calculate_sub_total());
-        new_sale_product.sub_total_without_discount = Some(self.subtotal_without_discount());
-        new_sale_product.discount_calculated = Some(self.calculate_discount());
-        new_sale_product.taxes_calculated = Some(self.calculate_taxes());
-        new_sale_product.total = Some(self.calculate_total());
-        new_sale_product
+        new_sale_product.discount = Some(self.discount.clone().unwrap_or(Money(0)));
+        new_sale_product.subtotal = Some(item_calc.subtotal()?);
+        new_sale_product.sub_total_without_discount = Some(item_calc.subtotal_without_discount()?);
+        new_sale_product.discount_calculated = Some(item_calc.calculate_discount()?);
+        new_sale_product.taxes_calculated = Some(item_calc.calculate_taxes()?);
+        new_sale_product.total = Some(item_calc.calculate_total()?);
+        Some(new_sale_product)
     }
 
-    pub fn to_item_calc_method(&self) -> ItemCalculation {
+    /// Calculates the line with the taxes it links to, looked up in the
+    /// taxes already loaded for the sale.
+    pub fn to_item_calc_method(&self, taxes: &HashMap<i32, Tax>) -> ItemCalculation {
         let discount = (&self).discount.clone();
-        ItemCalculation::new(&self.tax, &discount.unwrap_or(Money(0)), &self.price, self.amount)
-    }
-
-    pub fn calculate_total(&self) -> Money {
-        let item_calc = self.to_item_calc_method();
-        item_calc.calculate_total()
-    }
-
-    pub fn calculate_sub_total(&self) -> Money {
-        let item_calc = self.to_item_calc_method();
-        item_calc.subtotal()
+        let line_taxes = self.tax_ids
+            .iter()
+            .flatten()
+            .filter_map(|tax_id| taxes.get(tax_id).cloned())
+            .collect::<Vec<Tax>>();
+
+        ItemCalculation::new(&self.tax, &discount.unwrap_or(Money(0)), &self.price.clone().unwrap_or_default(), self.amount)
+            .with_taxes(&line_taxes)
+            .with_discount_amount(&self.discount_amount.clone().unwrap_or_default())
+            .with_tax_included(self.tax_included.unwrap_or(false))
+            .with_adjustment(&self.adjustment.clone().unwrap_or_default())
     }
-
-    pub fn subtotal_without_discount(&self) -> Money {
-        let item_calc = self.to_item_calc_method();
-        item_calc.subtotal_without_discount()
-    }
-
-    pub fn calculate_discount(&self) -> Money {
-        let item_calc = self.to_item_calc_method();
-        item_calc.calculate_discount()
-    }
-
-    pub fn calculate_taxes(&self) -> Money {
-        let item_calc = self.to_item_calc_method();
-        item_calc.calculate_taxes()
-    }
-
 }
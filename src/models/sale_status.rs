@@ -1,17 +1,23 @@
+use std::fmt;
 use diesel;
 use diesel::prelude::*;
 use diesel::query_dsl::RunQueryDsl;
 use diesel::QueryDsl;
-use crate::models::db_connection::*;
+use diesel::pg::PgConnection;
 use crate::models::sale::Sale;
+use crate::models::sale_status_history::SaleStatusHistory;
+use crate::models::numbering_series::NumberingSeries;
+use crate::models::stock_movement::StockMovement;
+use crate::models::stock_movement::StockDocument;
 use rocket::http::RawStr;
 use rocket::request::FromFormValue;
 use std::default::Default;
 
-#[derive(Serialize, Deserialize, Debug, Clone, DbEnum)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, DbEnum)]
 pub enum SaleStatus {
     Draft,
     Saved,
+    Active,
     Overdue,
     Cancelled,
     Payed,
@@ -30,6 +36,7 @@ impl<'v> FromFormValue<'v> for SaleStatus {
         match form_value.as_str() {
             "draft" => Ok(SaleStatus::Draft),
             "saved" => Ok(SaleStatus::Saved),
+            "active" => Ok(SaleStatus::Active),
             "overdue" => Ok(SaleStatus::Overdue),
             "cancelled" => Ok(SaleStatus::Cancelled),
             "payed" => Ok(SaleStatus::Payed),
@@ -38,29 +45,158 @@ impl<'v> FromFormValue<'v> for SaleStatus {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StockEffect {
+    Keep,
+    Commit,
+    Release,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Transition {
+    pub from: SaleStatus,
+    pub to: SaleStatus,
+    pub stock: StockEffect,
+    pub assigns_number: bool,
+}
+
+pub const TRANSITIONS: &[Transition] = &[
+    Transition { from: SaleStatus::Draft, to: SaleStatus::Saved, stock: StockEffect::Commit, assigns_number: true },
+    Transition { from: SaleStatus::Draft, to: SaleStatus::Active, stock: StockEffect::Commit, assigns_number: true },
+    Transition { from: SaleStatus::Draft, to: SaleStatus::Cancelled, stock: StockEffect::Keep, assigns_number: false },
+    Transition { from: SaleStatus::Saved, to: SaleStatus::Active, stock: StockEffect::Keep, assigns_number: false },
+    Transition { from: SaleStatus::Saved, to: SaleStatus::Overdue, stock: StockEffect::Keep, assigns_number: false },
+    Transition { from: SaleStatus::Saved, to: SaleStatus::Payed, stock: StockEffect::Keep, assigns_number: false },
+    Transition { from: SaleStatus::Saved, to: SaleStatus::Cancelled, stock: StockEffect::Release, assigns_number: false },
+    Transition { from: SaleStatus::Active, to: SaleStatus::Overdue, stock: StockEffect::Keep, assigns_number: false },
+    Transition { from: SaleStatus::Active, to: SaleStatus::Payed, stock: StockEffect::Keep, assigns_number: false },
+    Transition { from: SaleStatus::Active, to: SaleStatus::Cancelled, stock: StockEffect::Release, assigns_number: false },
+    Transition { from: SaleStatus::Overdue, to: SaleStatus::Payed, stock: StockEffect::Keep, assigns_number: false },
+    Transition { from: SaleStatus::Overdue, to: SaleStatus::Cancelled, stock: StockEffect::Release, assigns_number: false },
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "error", rename_all = "snake_case")]
+pub enum SaleStatusError {
+    InvalidTransition { from: SaleStatus, to: SaleStatus },
+    SaleNotFound { sale_id: i32 },
+    MissingSeries { sale_id: i32 },
+    Database { message: String },
+}
+
+impl fmt::Display for SaleStatusError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaleStatusError::InvalidTransition { from, to } =>
+                write!(f, "A sale can't change from {:?} to {:?}", from, to),
+            SaleStatusError::SaleNotFound { sale_id } =>
+                write!(f, "Sale {} not found", sale_id),
+            SaleStatusError::MissingSeries { sale_id } =>
+                write!(f, "There's no numbering series to issue sale {}", sale_id),
+            SaleStatusError::Database { message } =>
+                write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<diesel::result::Error> for SaleStatusError {
+    fn from(error: diesel::result::Error) -> Self {
+        SaleStatusError::Database { message: error.to_string() }
+    }
+}
+
 impl SaleStatus {
-    pub fn to_saved(id: i32) -> Result<bool, String> {
-        Self::save_status(id, SaleStatus::Draft, SaleStatus::Saved)
+    pub fn to_saved(connection: &PgConnection, id: i32, changed_by: &str) -> Result<Sale, SaleStatusError> {
+        Self::transition(connection, id, SaleStatus::Saved, changed_by)
+    }
+
+    pub fn to_active(connection: &PgConnection, id: i32, changed_by: &str) -> Result<Sale, SaleStatusError> {
+        Self::transition(connection, id, SaleStatus::Active, changed_by)
     }
 
-    pub fn to_cancelled(id: i32) -> Result<bool, String> {
-        Self::save_status(id, SaleStatus::Saved, SaleStatus::Cancelled)
+    pub fn to_cancelled(connection: &PgConnection, id: i32, changed_by: &str) -> Result<Sale, SaleStatusError> {
+        Self::transition(connection, id, SaleStatus::Cancelled, changed_by)
     }
 
-    fn save_status(
+    /// Statuses of sales that have been issued and still wait for payment.
+    pub fn open_statuses() -> Vec<SaleStatus> {
+        vec![SaleStatus::Saved, SaleStatus::Active, SaleStatus::Overdue]
+    }
+
+    /// Statuses the transition table allows to move to `to`.
+    pub fn statuses_reaching(to: SaleStatus) -> Vec<SaleStatus> {
+        TRANSITIONS
+            .iter()
+            .filter(|transition| transition.to == to)
+            .map(|transition| transition.from)
+            .collect()
+    }
+
+    pub fn find_transition(from: SaleStatus, to: SaleStatus) -> Option<&'static Transition> {
+        TRANSITIONS
+            .iter()
+            .find(|transition| transition.from == from && transition.to == to)
+    }
+
+    pub fn transition(connection: &PgConnection, id: i32, next_status: SaleStatus, changed_by: &str) -> Result<Sale, SaleStatusError> {
+        connection.transaction(|| {
+            Self::apply_transition(connection, id, next_status, changed_by)
+        })
+    }
+
+    /// Moves the sale to `next_status` on the given connection, applying the
+    /// stock effect of the transition and recording it in the history. It
+    /// must run inside a transaction opened by the caller.
+    pub fn apply_transition(
+        connection: &PgConnection,
         id: i32,
-        previous_status: SaleStatus,
         next_status: SaleStatus,
-    ) -> Result<bool, String> {
+        changed_by: &str,
+    ) -> Result<Sale, SaleStatusError> {
         use crate::schema::sales::dsl;
-        let connection = establish_connection();
 
-        match diesel::update(dsl::sales.find(id).filter(dsl::status.eq(previous_status)))
+        let sale = dsl::sales
+            .find(id)
+            .for_update()
+            .get_result::<Sale>(connection)
+            .map_err(|error| match error {
+                diesel::result::Error::NotFound => SaleStatusError::SaleNotFound { sale_id: id },
+                error => SaleStatusError::from(error),
+            })?;
+
+        let transition = Self::find_transition(sale.status, next_status)
+            .ok_or(SaleStatusError::InvalidTransition { from: sale.status, to: next_status })?;
+
+        let updated_sale = diesel::update(dsl::sales.find(id))
             .set(dsl::status.eq(next_status))
-            .get_result::<Sale>(&connection)
-        {
-            Ok(_) => Ok(true),
-            Err(_) => Err("Not valid State".to_string()),
+            .get_result::<Sale>(connection)?;
+
+        let updated_sale = if transition.assigns_number && updated_sale.number.is_none() {
+            let (series_id, number) = NumberingSeries::take_number(connection, sale.series_id)
+                .map_err(|error| match error {
+                    diesel::result::Error::NotFound => SaleStatusError::MissingSeries { sale_id: id },
+                    error => SaleStatusError::from(error),
+                })?;
+
+            diesel::update(dsl::sales.find(id))
+                .set((dsl::series_id.eq(series_id), dsl::number.eq(number)))
+                .get_result::<Sale>(connection)?
+        } else {
+            updated_sale
+        };
+
+        match transition.stock {
+            StockEffect::Commit => {
+                StockMovement::register_sale(connection, id, StockDocument::Sale)?;
+            },
+            StockEffect::Release => {
+                StockMovement::register_sale(connection, id, StockDocument::SaleCancellation)?;
+            },
+            StockEffect::Keep => {}
         }
+
+        SaleStatusHistory::record(connection, id, sale.status, next_status, changed_by)?;
+
+        Ok(updated_sale)
     }
 }
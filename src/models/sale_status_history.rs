@@ -0,0 +1,48 @@
+use diesel;
+use diesel::prelude::*;
+use diesel::pg::PgConnection;
+use chrono::NaiveDateTime;
+use crate::models::sale_status::SaleStatus;
+use crate::schema::sale_status_history;
+
+#[derive(Serialize, Deserialize, Clone, Queryable, Debug)]
+pub struct SaleStatusHistory {
+    pub id: i32,
+    pub sale_id: i32,
+    pub from_status: SaleStatus,
+    pub to_status: SaleStatus,
+    pub changed_by: String,
+    pub changed_at: NaiveDateTime
+}
+
+#[derive(Insertable, Debug)]
+#[table_name="sale_status_history"]
+pub struct NewSaleStatusHistory<'a> {
+    pub sale_id: i32,
+    pub from_status: SaleStatus,
+    pub to_status: SaleStatus,
+    pub changed_by: &'a str
+}
+
+impl SaleStatusHistory {
+    pub fn list(connection: &PgConnection, sale_id: i32) -> Result<Vec<SaleStatusHistory>, diesel::result::Error> {
+        use crate::schema::sale_status_history::dsl;
+
+        dsl::sale_status_history
+            .filter(dsl::sale_id.eq(sale_id))
+            .order(dsl::id)
+            .load::<SaleStatusHistory>(connection)
+    }
+
+    pub fn record(
+        connection: &PgConnection,
+        sale_id: i32,
+        from_status: SaleStatus,
+        to_status: SaleStatus,
+        changed_by: &str,
+    ) -> Result<SaleStatusHistory, diesel::result::Error> {
+        diesel::insert_into(sale_status_history::table)
+            .values(&NewSaleStatusHistory { sale_id, from_status, to_status, changed_by })
+            .get_result::<SaleStatusHistory>(connection)
+    }
+}
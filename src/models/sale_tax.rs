@@ -0,0 +1,75 @@
+use diesel;
+use diesel::RunQueryDsl;
+use diesel::QueryDsl;
+use diesel::ExpressionMethods;
+use diesel::pg::PgConnection;
+use crate::schema::sale_taxes;
+use crate::models::money::Money;
+use crate::models::item_calculation::TaxBreakdown;
+
+/// One line of the per-tax breakdown of a sale, kept as it was calculated
+/// so later changes to the taxes don't alter issued invoices.
+#[derive(Identifiable, Serialize, Deserialize, Queryable, Debug, Clone, PartialEq)]
+#[table_name="sale_taxes"]
+pub struct SaleTax {
+    pub id: i32,
+    pub sale_id: i32,
+    pub tax_id: Option<i32>,
+    pub name: String,
+    pub percentage: Money,
+    pub base: Money,
+    pub amount: Money
+}
+
+#[derive(Serialize, Deserialize, Insertable, Debug, Clone)]
+#[table_name="sale_taxes"]
+pub struct NewSaleTax {
+    pub sale_id: i32,
+    pub tax_id: Option<i32>,
+    pub name: String,
+    pub percentage: Money,
+    pub base: Money,
+    pub amount: Money
+}
+
+impl SaleTax {
+    pub fn list(connection: &PgConnection, sale_id: i32) -> Result<Vec<SaleTax>, diesel::result::Error> {
+        use crate::schema::sale_taxes::dsl;
+
+        dsl::sale_taxes
+            .filter(dsl::sale_id.eq(sale_id))
+            .order(dsl::id)
+            .load::<SaleTax>(connection)
+    }
+
+    pub fn replace(connection: &PgConnection, sale_id: i32, breakdown: Vec<TaxBreakdown>) -> Result<Vec<SaleTax>, diesel::result::Error> {
+        Self::delete(connection, sale_id)?;
+
+        if breakdown.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let new_sale_taxes = breakdown
+            .into_iter()
+            .map(|tax| NewSaleTax {
+                sale_id,
+                tax_id: tax.tax_id,
+                name: tax.name,
+                percentage: tax.percentage,
+                base: tax.base,
+                amount: tax.amount
+            })
+            .collect::<Vec<NewSaleTax>>();
+
+        diesel::insert_into(sale_taxes::table)
+            .values(&new_sale_taxes)
+            .get_results::<SaleTax>(connection)
+    }
+
+    pub fn delete(connection: &PgConnection, sale_id: i32) -> Result<usize, diesel::result::Error> {
+        use crate::schema::sale_taxes::dsl;
+
+        diesel::delete(dsl::sale_taxes.filter(dsl::sale_id.eq(sale_id)))
+            .execute(connection)
+    }
+}
@@ -0,0 +1,165 @@
+use diesel;
+use diesel::prelude::*;
+use diesel::pg::PgConnection;
+use diesel::sql_types;
+use chrono::NaiveDateTime;
+use crate::models::product::Product;
+use crate::models::sale_product::SaleProduct;
+use crate::models::purchase_product::PurchaseProduct;
+use crate::models::credit_note_product::CreditNoteProduct;
+use crate::schema::stock_movements;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, DbEnum)]
+pub enum StockDocument {
+    Initial,
+    Sale,
+    SaleCancellation,
+    Purchase,
+    PurchaseCancellation,
+    CreditNote,
+}
+
+impl StockDocument {
+    /// Direction in which a document moves the stock: positive documents
+    /// bring goods in, negative ones take them out.
+    pub fn sign(&self) -> f64 {
+        match self {
+            StockDocument::Initial => 1.0,
+            StockDocument::Sale => -1.0,
+            StockDocument::SaleCancellation => 1.0,
+            StockDocument::Purchase => 1.0,
+            StockDocument::PurchaseCancellation => -1.0,
+            StockDocument::CreditNote => 1.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Queryable, Debug)]
+pub struct StockMovement {
+    pub id: i32,
+    pub product_id: i32,
+    pub amount: f64,
+    pub document: StockDocument,
+    pub document_id: Option<i32>,
+    pub created_at: NaiveDateTime,
+    pub variant_id: Option<i32>
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name="stock_movements"]
+pub struct NewStockMovement {
+    pub product_id: i32,
+    pub amount: f64,
+    pub document: StockDocument,
+    pub document_id: Option<i32>,
+    pub variant_id: Option<i32>
+}
+
+impl StockMovement {
+    pub fn list(connection: &PgConnection, product_id: i32) -> Result<Vec<StockMovement>, diesel::result::Error> {
+        use crate::schema::stock_movements::dsl;
+
+        dsl::stock_movements
+            .filter(dsl::product_id.eq(product_id))
+            .order(dsl::id)
+            .load::<StockMovement>(connection)
+    }
+
+    /// The variant's stock, from the movements registered for it.
+    pub fn variant_stock(connection: &PgConnection, variant_id: i32) -> Result<f64, diesel::result::Error> {
+        use crate::schema::stock_movements::dsl;
+
+        dsl::stock_movements
+            .filter(dsl::variant_id.eq(variant_id))
+            .select(diesel::dsl::sum(dsl::amount))
+            .first::<Option<f64>>(connection)
+            .map(|stock| stock.unwrap_or(0.0))
+    }
+
+    /// Writes the movement to the ledger and applies it to the cached
+    /// `products.stock` value.
+    pub fn register(connection: &PgConnection, new_stock_movement: &NewStockMovement) -> Result<StockMovement, diesel::result::Error> {
+        let stock_movement = diesel::insert_into(stock_movements::table)
+            .values(new_stock_movement)
+            .get_result::<StockMovement>(connection)?;
+
+        diesel::sql_query("UPDATE products SET stock = COALESCE(stock, 0) + $1 WHERE id = $2")
+            .bind::<sql_types::Double, _>(stock_movement.amount)
+            .bind::<sql_types::Integer, _>(stock_movement.product_id)
+            .execute(connection)?;
+
+        Ok(stock_movement)
+    }
+
+    /// Moves the stock of every line in the sale. A cancellation only gives
+    /// back what hasn't already come back through credit notes.
+    pub fn register_sale(connection: &PgConnection, sale_id: i32, document: StockDocument) -> Result<Vec<StockMovement>, diesel::result::Error> {
+        use crate::schema::sale_products::dsl;
+
+        let vec_sale_products = dsl::sale_products
+            .filter(dsl::sale_id.eq(sale_id))
+            .load::<SaleProduct>(connection)?;
+
+        vec_sale_products
+            .iter()
+            .map(|sale_product| {
+                let amount = match document {
+                    StockDocument::SaleCancellation =>
+                        sale_product.amount - CreditNoteProduct::returned_amount(connection, sale_product.id)?,
+                    _ => sale_product.amount
+                };
+
+                let amount = amount * sale_product.unit_factor;
+
+                Self::register(connection, &NewStockMovement {
+                    product_id: sale_product.product_id,
+                    amount: document.sign() * amount,
+                    document,
+                    document_id: Some(sale_id),
+                    variant_id: sale_product.variant_id
+                })
+            })
+            .collect()
+    }
+
+    pub fn register_purchase(connection: &PgConnection, purchase_id: i32, document: StockDocument) -> Result<Vec<StockMovement>, diesel::result::Error> {
+        use crate::schema::purchase_products::dsl;
+
+        let vec_purchase_products = dsl::purchase_products
+            .filter(dsl::purchase_id.eq(purchase_id))
+            .load::<PurchaseProduct>(connection)?;
+
+        vec_purchase_products
+            .iter()
+            .map(|purchase_product| {
+                let amount = purchase_product.amount * purchase_product.unit_factor;
+
+                Self::register(connection, &NewStockMovement {
+                    product_id: purchase_product.product_id,
+                    amount: document.sign() * amount,
+                    document,
+                    document_id: Some(purchase_id),
+                    variant_id: purchase_product.variant_id
+                })
+            })
+            .collect()
+    }
+
+    /// Recomputes `products.stock` from the ledger, discarding whatever the
+    /// cached value was.
+    pub fn reconcile(connection: &PgConnection, product_id: i32) -> Result<Product, diesel::result::Error> {
+        use crate::schema::stock_movements::dsl;
+        use crate::schema::products;
+
+        connection.transaction(|| {
+            let ledger_stock = dsl::stock_movements
+                .filter(dsl::product_id.eq(product_id))
+                .select(diesel::dsl::sum(dsl::amount))
+                .first::<Option<f64>>(connection)?;
+
+            diesel::update(products::dsl::products.find(product_id))
+                .set(products::dsl::stock.eq(ledger_stock.unwrap_or(0.0)))
+                .get_result::<Product>(connection)
+        })
+    }
+}
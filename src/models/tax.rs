@@ -1,13 +1,14 @@
 use diesel;
 use diesel::prelude::*;
 use diesel::sql_types;
+use diesel::pg::PgConnection;
 use crate::schema;
 use crate::schema::taxes;
 use crate::handlers::base::Search;
 use crate::basic_model_actions;
 
 type BoxedQuery<'a> = 
-    diesel::query_builder::BoxedSelectStatement<'a, (sql_types::Integer, sql_types::Text, sql_types::Integer),
+    diesel::query_builder::BoxedSelectStatement<'a, (sql_types::Integer, sql_types::Text, sql_types::Integer, sql_types::Bool),
                                                      schema::taxes::table, diesel::pg::Pg>;
 
 #[derive(Serialize, Deserialize, Queryable, Eq, PartialEq, Hash, 
@@ -16,14 +17,18 @@ type BoxedQuery<'a> =
 pub struct Tax {
     pub id: i32,
     pub name: String,
-    pub percentage: i32
+    /// In hundredths of a percent, 21% is 2100.
+    pub percentage: i32,
+    /// Levied on the base plus the taxes before it on the line.
+    pub compound: bool
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, FromForm, FromData)]
 pub struct SearchTax {
     pub id: Option<i32>,
     pub name: Option<String>,
-    pub percentage: Option<i32>
+    pub percentage: Option<i32>,
+    pub compound: Option<bool>
 }
 
 #[derive(Serialize, Deserialize, Insertable, Eq, PartialEq, Hash,
@@ -31,10 +36,31 @@ pub struct SearchTax {
 #[table_name="taxes"]
 pub struct NewTax {
     pub name: String,
-    pub percentage: i32
+    pub percentage: i32,
+    pub compound: Option<bool>
 }
 
 impl Tax {
+    /// Loads the taxes in the order of `tax_ids`, failing with NotFound if
+    /// any of them doesn't exist.
+    pub fn find_all(connection: &PgConnection, tax_ids: &[i32]) -> Result<Vec<Tax>, diesel::result::Error> {
+        use crate::schema::taxes::dsl::*;
+
+        let found = taxes
+            .filter(id.eq_any(tax_ids))
+            .load::<Tax>(connection)?;
+
+        tax_ids
+            .iter()
+            .map(|tax_id| {
+                found
+                    .iter()
+                    .find(|tax| tax.id == *tax_id)
+                    .cloned()
+                    .ok_or(diesel::result::Error::NotFound)
+            })
+            .collect()
+    }
 
     fn searching_records<'a>(search: Option<Search<SearchTax>>) -> BoxedQuery<'a> {
         use crate::schema::taxes::dsl::*;
@@ -52,6 +78,9 @@ impl Tax {
             if let Some(taxes_percentage) = tax.percentage {
                 query = query.filter(percentage.eq(taxes_percentage));
             }
+            if let Some(taxes_compound) = tax.compound {
+                query = query.filter(compound.eq(taxes_compound));
+            }
         }
 
         query
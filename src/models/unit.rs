@@ -0,0 +1,176 @@
+use std::fmt;
+use std::str::FromStr;
+use serde_json;
+use diesel;
+use diesel::prelude::*;
+use diesel::sql_types;
+use diesel::pg::PgConnection;
+use crate::schema;
+use crate::schema::units;
+use crate::handlers::base::Search;
+use crate::basic_model_actions;
+
+type BoxedQuery<'a> =
+    diesel::query_builder::BoxedSelectStatement<'a, (sql_types::Integer,
+                                                     sql_types::Text,
+                                                     sql_types::Text,
+                                                     sql_types::Nullable<sql_types::Integer>,
+                                                     sql_types::Double),
+                                                     schema::units::table, diesel::pg::Pg>;
+
+/// A unit of measure. A unit with a `base_unit_id` holds `factor` of its
+/// base unit, so a box of twelve is `factor: 12.0` of the unit and a gram
+/// is `factor: 0.001` of the kilogram. Base units count as 1 of themselves
+/// and have no base of their own.
+#[derive(Serialize, Deserialize, Queryable, PartialEq,
+         Debug, Clone, AsChangeset, FromData, Responder)]
+#[table_name="units"]
+pub struct Unit {
+    pub id: i32,
+    pub name: String,
+    pub symbol: String,
+    pub base_unit_id: Option<i32>,
+    pub factor: f64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, FromForm, FromData)]
+pub struct SearchUnit {
+    pub id: Option<i32>,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub base_unit_id: Option<i32>
+}
+
+#[derive(Serialize, Deserialize, Insertable, Debug, FromData, Responder)]
+#[table_name="units"]
+pub struct NewUnit {
+    pub name: String,
+    pub symbol: String,
+    pub base_unit_id: Option<i32>,
+    pub factor: Option<f64>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "error", rename_all = "snake_case")]
+pub enum UnitError {
+    InvalidFactor { factor: f64 },
+    Database { message: String },
+}
+
+impl fmt::Display for UnitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UnitError::InvalidFactor { factor } =>
+                write!(f, "A unit can't hold {} of its base unit", factor),
+            UnitError::Database { message } =>
+                write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<diesel::result::Error> for UnitError {
+    fn from(error: diesel::result::Error) -> Self {
+        UnitError::Database { message: error.to_string() }
+    }
+}
+
+impl Unit {
+    pub fn create(connection: &PgConnection, new_unit: NewUnit) -> Result<Unit, UnitError> {
+        if let Some(factor) = new_unit.factor {
+            Self::check_factor(factor)?;
+        }
+
+        Ok(<Self as BasicModelActions>::create(connection, new_unit)?)
+    }
+
+    pub fn update(connection: &PgConnection, param_id: i32, unit: Unit) -> Result<Unit, UnitError> {
+        Self::check_factor(unit.factor)?;
+
+        Ok(<Self as BasicModelActions>::update(connection, param_id, unit)?)
+    }
+
+    fn check_factor(factor: f64) -> Result<(), UnitError> {
+        if factor > 0.0 && factor.is_finite() {
+            Ok(())
+        } else {
+            Err(UnitError::InvalidFactor { factor })
+        }
+    }
+
+    /// How many of `unit` one of these is, None when they don't measure
+    /// the same thing.
+    pub fn conversion(&self, unit: &Unit) -> Option<f64> {
+        if self.base_unit_id.unwrap_or(self.id) != unit.base_unit_id.unwrap_or(unit.id) {
+            return None;
+        }
+        Some(self.base_factor() / unit.base_factor())
+    }
+
+    /// How many of the product's base unit one `unit_id` is. Quantities
+    /// without a unit are already in the base unit, and products without
+    /// one can't be counted in any other.
+    pub fn to_base(connection: &PgConnection, product_id: i32, unit_id: Option<i32>) -> Result<Option<f64>, diesel::result::Error> {
+        use crate::schema::products;
+
+        let unit_id = match unit_id {
+            Some(unit_id) => unit_id,
+            None => return Ok(Some(1.0))
+        };
+
+        let base_unit_id = products::table
+            .find(product_id)
+            .select(products::unit_id)
+            .first::<Option<i32>>(connection)?;
+
+        match base_unit_id {
+            Some(base_unit_id) if base_unit_id == unit_id => Ok(Some(1.0)),
+            Some(base_unit_id) => {
+                let unit = Self::show(connection, unit_id)?;
+                let base_unit = Self::show(connection, base_unit_id)?;
+                Ok(unit.conversion(&base_unit))
+            },
+            None => Ok(None)
+        }
+    }
+
+    fn base_factor(&self) -> f64 {
+        match self.base_unit_id {
+            Some(_) => self.factor,
+            None => 1.0
+        }
+    }
+
+    fn searching_records<'a>(search: Option<Search<SearchUnit>>) -> BoxedQuery<'a> {
+        use crate::schema::units::dsl::*;
+
+        let mut query = schema::units::table.into_boxed::<diesel::pg::Pg>();
+
+        if let Some(search_unit) = search {
+            let Search(unit) = search_unit;
+            if let Some(unit_id) = unit.id {
+                query = query.filter(id.eq(unit_id));
+            }
+            if let Some(unit_name) = unit.name {
+                query = query.filter(name.like(unit_name));
+            }
+            if let Some(unit_symbol) = unit.symbol {
+                query = query.filter(symbol.like(unit_symbol));
+            }
+            if let Some(unit_base_unit_id) = unit.base_unit_id {
+                query = query.filter(base_unit_id.eq(unit_base_unit_id));
+            }
+        }
+
+        query
+    }
+}
+
+impl FromStr for SearchUnit {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+basic_model_actions!(units, Unit, NewUnit, SearchUnit);
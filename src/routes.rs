@@ -6,6 +6,15 @@ use crate::handlers::clients;
 use crate::handlers::currencies;
 use crate::handlers::taxes;
 use crate::handlers::sales;
+use crate::handlers::purchases;
+use crate::handlers::payments;
+use crate::handlers::credit_notes;
+use crate::handlers::numbering_series;
+use crate::handlers::exchange_rates;
+use crate::handlers::pricing_rules;
+use crate::handlers::categories;
+use crate::handlers::product_variants;
+use crate::handlers::units;
 use rocket;
 
 pub fn routes() -> Vec<rocket::Route> {
@@ -39,7 +48,27 @@ pub fn routes() -> Vec<rocket::Route> {
             taxes::show_route(),
             taxes::create_route(),
             taxes::update_route(),
-            taxes::delete_route()];
+            taxes::delete_route(),
+            numbering_series::index_route(),
+            numbering_series::show_route(),
+            numbering_series::create_route(),
+            numbering_series::update_route(),
+            numbering_series::delete_route(),
+            exchange_rates::index_route(),
+            exchange_rates::show_route(),
+            exchange_rates::create_route(),
+            exchange_rates::update_route(),
+            exchange_rates::delete_route(),
+            pricing_rules::index_route(),
+            pricing_rules::show_route(),
+            pricing_rules::create_route(),
+            pricing_rules::update_route(),
+            pricing_rules::delete_route(),
+            categories::index_route(),
+            categories::show_route(),
+            categories::create_route(),
+            categories::update_route(),
+            categories::delete_route()];
 
     let mut automatic_routes =
         routes![
@@ -48,6 +77,15 @@ pub fn routes() -> Vec<rocket::Route> {
             products::create,
             products::update,
             products::delete,
+            products::stock_movements,
+            products::reconcile_stock,
+            products::price_history,
+            products::by_code,
+            product_variants::index,
+            product_variants::show,
+            product_variants::create,
+            product_variants::update,
+            product_variants::delete,
             sales::index,
             sales::show,
             sales::create,
@@ -55,6 +93,27 @@ pub fn routes() -> Vec<rocket::Route> {
             sales::delete,
             sales::save,
             sales::cancel,
+            sales::activate,
+            sales::history,
+            purchases::index,
+            purchases::show,
+            purchases::create,
+            purchases::update,
+            purchases::delete,
+            purchases::receive,
+            purchases::cancel,
+            payments::index,
+            payments::create,
+            credit_notes::index,
+            credit_notes::show,
+            credit_notes::create,
+            clients::balance,
+            prices::bulk_update,
+            units::index,
+            units::show,
+            units::create,
+            units::update,
+            units::delete,
         ];
     
     manual_routes.append(&mut automatic_routes);
@@ -1,3 +1,11 @@
+table! {
+    categories (id) {
+        id -> Int4,
+        name -> Varchar,
+        parent_id -> Nullable<Int4>,
+    }
+}
+
 table! {
     clients (id) {
         id -> Int4,
@@ -6,6 +14,7 @@ table! {
         company_name -> Nullable<Varchar>,
         email -> Nullable<Varchar>,
         phone -> Nullable<Varchar>,
+        price_id -> Nullable<Int4>,
     }
 }
 
@@ -13,6 +22,37 @@ table! {
     costs (id) {
         id -> Int4,
         name -> Varchar,
+        primary_cost -> Bool,
+    }
+}
+
+table! {
+    credit_note_products (id) {
+        id -> Int4,
+        credit_note_id -> Int4,
+        sale_product_id -> Int4,
+        product_id -> Int4,
+        amount -> Float8,
+        subtotal -> Int8,
+        sub_total_without_discount -> Int8,
+        discount_calculated -> Int8,
+        taxes_calculated -> Int8,
+        total -> Int8,
+        observation -> Nullable<Text>,
+    }
+}
+
+table! {
+    credit_notes (id) {
+        id -> Int4,
+        sale_id -> Int4,
+        credit_note_date -> Date,
+        sub_total -> Int8,
+        sub_total_without_discount -> Int8,
+        discount_calculated -> Int8,
+        taxes_calculated -> Int8,
+        total -> Int8,
+        observation -> Nullable<Text>,
     }
 }
 
@@ -28,10 +68,102 @@ table! {
     }
 }
 
+table! {
+    exchange_rates (id) {
+        id -> Int4,
+        from_currency_id -> Int4,
+        to_currency_id -> Int4,
+        rate -> Float8,
+        effective_date -> Date,
+    }
+}
+
+table! {
+    numbering_series (id) {
+        id -> Int4,
+        name -> Varchar,
+        prefix -> Varchar,
+        padding -> Int4,
+        next_number -> Int4,
+        default_series -> Bool,
+    }
+}
+
+table! {
+    overdue_sweeps (id) {
+        id -> Int4,
+        reference_date -> Date,
+        sale_ids -> Array<Int4>,
+        swept_at -> Timestamp,
+    }
+}
+
+table! {
+    use diesel::sql_types::Int4;
+    use diesel::sql_types::Int8;
+    use diesel::sql_types::Date;
+    use diesel::sql_types::Nullable;
+    use diesel::sql_types::Text;
+    use crate::models::payment::PaymentMethodMapping;
+    payments (id) {
+        id -> Int4,
+        sale_id -> Int4,
+        payment_date -> Date,
+        amount -> Int8,
+        currency_id -> Int4,
+        payment_method -> PaymentMethodMapping,
+        observation -> Nullable<Text>,
+    }
+}
+
 table! {
     prices (id) {
         id -> Int4,
         name -> Varchar,
+        currency_id -> Nullable<Int4>,
+        markup -> Nullable<Int8>,
+    }
+}
+
+table! {
+    use diesel::sql_types::Int4;
+    use diesel::sql_types::Int8;
+    use diesel::sql_types::Bool;
+    use diesel::sql_types::Date;
+    use diesel::sql_types::Float8;
+    use diesel::sql_types::Nullable;
+    use diesel::sql_types::Varchar;
+    use crate::models::pricing_rule::PricingRuleKindMapping;
+    pricing_rules (id) {
+        id -> Int4,
+        name -> Varchar,
+        kind -> PricingRuleKindMapping,
+        product_id -> Nullable<Int4>,
+        starts_on -> Nullable<Date>,
+        ends_on -> Nullable<Date>,
+        percentage -> Nullable<Int8>,
+        buy_amount -> Nullable<Float8>,
+        pay_amount -> Nullable<Float8>,
+        min_amount -> Nullable<Float8>,
+        price -> Nullable<Int8>,
+        active -> Bool,
+        category_id -> Nullable<Int4>,
+    }
+}
+
+table! {
+    product_barcodes (id) {
+        id -> Int4,
+        product_id -> Int4,
+        code -> Varchar,
+    }
+}
+
+table! {
+    product_categories (id) {
+        id -> Int4,
+        product_id -> Int4,
+        category_id -> Int4,
     }
 }
 
@@ -41,7 +173,7 @@ table! {
         product_id -> Int4,
         cost_id -> Int4,
         supplier_id -> Int4,
-        cost -> Int4,
+        cost -> Int8,
     }
 }
 
@@ -50,7 +182,28 @@ table! {
         id -> Int4,
         product_id -> Int4,
         price_id -> Int4,
-        price -> Int4,
+        price -> Int8,
+        valid_from -> Nullable<Date>,
+        valid_to -> Nullable<Date>,
+        variant_id -> Nullable<Int4>,
+    }
+}
+
+table! {
+    product_variants (id) {
+        id -> Int4,
+        product_id -> Int4,
+        code -> Nullable<Varchar>,
+        size -> Nullable<Varchar>,
+        color -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    product_taxes (id) {
+        id -> Int4,
+        product_id -> Int4,
+        tax_id -> Int4,
     }
 }
 
@@ -61,6 +214,69 @@ table! {
         description -> Nullable<Text>,
         stock -> Nullable<Float8>,
         code -> Nullable<Varchar>,
+        unit_id -> Nullable<Int4>,
+    }
+}
+
+table! {
+    purchase_products (id) {
+        id -> Int4,
+        purchase_id -> Int4,
+        product_id -> Int4,
+        tax -> Int8,
+        amount -> Float8,
+        price -> Int8,
+        discount -> Int8,
+        subtotal -> Int8,
+        sub_total_without_discount -> Int8,
+        discount_calculated -> Int8,
+        taxes_calculated -> Int8,
+        total -> Int8,
+        observation -> Nullable<Text>,
+        unit_id -> Nullable<Int4>,
+        variant_id -> Nullable<Int4>,
+        unit_factor -> Float8,
+    }
+}
+
+table! {
+    use diesel::sql_types::Int4;
+    use diesel::sql_types::Int8;
+    use diesel::sql_types::Bool;
+    use diesel::sql_types::Date;
+    use diesel::sql_types::Nullable;
+    use diesel::sql_types::Text;
+    use crate::models::purchase_status::PurchaseStatusMapping;
+    purchases (id) {
+        id -> Int4,
+        supplier_id -> Int4,
+        purchase_date -> Date,
+        sub_total -> Int8,
+        sub_total_without_discount -> Int8,
+        discount_calculated -> Int8,
+        taxes_calculated -> Int8,
+        total -> Int8,
+        observation -> Nullable<Text>,
+        currency_id -> Int4,
+        status -> PurchaseStatusMapping,
+        update_costs -> Bool,
+    }
+}
+
+table! {
+    use diesel::sql_types::Int4;
+    use diesel::sql_types::Int8;
+    use diesel::sql_types::Nullable;
+    use diesel::sql_types::Varchar;
+    use crate::models::sale_adjustment::AdjustmentKindMapping;
+    sale_adjustments (id) {
+        id -> Int4,
+        sale_id -> Int4,
+        kind -> AdjustmentKindMapping,
+        percentage -> Nullable<Int8>,
+        amount -> Nullable<Int8>,
+        description -> Nullable<Varchar>,
+        calculated -> Int8,
     }
 }
 
@@ -69,38 +285,99 @@ table! {
         id -> Int4,
         sale_id -> Int4,
         product_id -> Int4,
-        tax -> Int4,
+        tax -> Int8,
         amount -> Float8,
-        price -> Int4,
-        discount -> Int4,
-        subtotal -> Int4,
-        sub_total_without_discount -> Int4,
-        discount_calculated -> Int4,
-        taxes_calculated -> Int4,
-        total -> Int4,
+        price -> Int8,
+        discount -> Int8,
+        subtotal -> Int8,
+        sub_total_without_discount -> Int8,
+        discount_calculated -> Int8,
+        taxes_calculated -> Int8,
+        total -> Int8,
         observation -> Nullable<Text>,
+        price_id -> Nullable<Int4>,
+        exchange_rate -> Float8,
+        tax_ids -> Array<Int4>,
+        discount_amount -> Int8,
+        tax_included -> Bool,
+        adjustment -> Int8,
+        pricing_rule_id -> Nullable<Int4>,
+        variant_id -> Nullable<Int4>,
+        unit_id -> Nullable<Int4>,
+        unit_factor -> Float8,
+    }
+}
+
+table! {
+    use diesel::sql_types::Int4;
+    use diesel::sql_types::Varchar;
+    use diesel::sql_types::Timestamp;
+    use crate::models::sale_status::SaleStatusMapping;
+    sale_status_history (id) {
+        id -> Int4,
+        sale_id -> Int4,
+        from_status -> SaleStatusMapping,
+        to_status -> SaleStatusMapping,
+        changed_by -> Varchar,
+        changed_at -> Timestamp,
+    }
+}
+
+table! {
+    sale_taxes (id) {
+        id -> Int4,
+        sale_id -> Int4,
+        tax_id -> Nullable<Int4>,
+        name -> Varchar,
+        percentage -> Int8,
+        base -> Int8,
+        amount -> Int8,
     }
 }
 
 table! {
     use diesel::types::Int4;
+    use diesel::sql_types::Int8;
     use diesel::sql_types::Date;
     use diesel::sql_types::Nullable;
     use diesel::sql_types::Text;
+    use diesel::sql_types::Varchar;
+    use diesel::sql_types::Float8;
     use crate::models::sale_status::SaleStatusMapping;
     sales (id) {
         id -> Int4,
         client_id -> Int4,
         sale_date -> Date,
-        sub_total -> Int4,
-        sub_total_without_discount -> Int4,
-        discount_calculated -> Int4,
-        taxes_calculated -> Int4,
-        total -> Int4,
+        sub_total -> Int8,
+        sub_total_without_discount -> Int8,
+        discount_calculated -> Int8,
+        taxes_calculated -> Int8,
+        total -> Int8,
         observation -> Nullable<Text>,
         currency_id -> Int4,
         status -> SaleStatusMapping,
         expiring_date -> Nullable<Date>,
+        series_id -> Nullable<Int4>,
+        number -> Nullable<Varchar>,
+        exchange_rate -> Float8,
+        adjustments_calculated -> Int8,
+    }
+}
+
+table! {
+    use diesel::sql_types::Int4;
+    use diesel::sql_types::Float8;
+    use diesel::sql_types::Nullable;
+    use diesel::sql_types::Timestamp;
+    use crate::models::stock_movement::StockDocumentMapping;
+    stock_movements (id) {
+        id -> Int4,
+        product_id -> Int4,
+        amount -> Float8,
+        document -> StockDocumentMapping,
+        document_id -> Nullable<Int4>,
+        created_at -> Timestamp,
+        variant_id -> Nullable<Int4>,
     }
 }
 
@@ -120,29 +397,94 @@ table! {
         id -> Int4,
         name -> Varchar,
         percentage -> Int4,
+        compound -> Bool,
+    }
+}
+
+table! {
+    units (id) {
+        id -> Int4,
+        name -> Varchar,
+        symbol -> Varchar,
+        base_unit_id -> Nullable<Int4>,
+        factor -> Float8,
     }
 }
 
+joinable!(clients -> prices (price_id));
+joinable!(credit_note_products -> credit_notes (credit_note_id));
+joinable!(credit_note_products -> products (product_id));
+joinable!(credit_note_products -> sale_products (sale_product_id));
+joinable!(credit_notes -> sales (sale_id));
+joinable!(payments -> currencies (currency_id));
+joinable!(payments -> sales (sale_id));
+joinable!(prices -> currencies (currency_id));
+joinable!(pricing_rules -> categories (category_id));
+joinable!(pricing_rules -> products (product_id));
+joinable!(product_barcodes -> products (product_id));
+joinable!(product_categories -> categories (category_id));
+joinable!(product_categories -> products (product_id));
 joinable!(product_costs -> costs (cost_id));
 joinable!(product_costs -> products (product_id));
 joinable!(product_costs -> suppliers (supplier_id));
 joinable!(product_prices -> prices (price_id));
+joinable!(product_prices -> product_variants (variant_id));
 joinable!(product_prices -> products (product_id));
+joinable!(product_taxes -> products (product_id));
+joinable!(product_taxes -> taxes (tax_id));
+joinable!(product_variants -> products (product_id));
+joinable!(products -> units (unit_id));
+joinable!(purchase_products -> product_variants (variant_id));
+joinable!(purchase_products -> products (product_id));
+joinable!(purchase_products -> purchases (purchase_id));
+joinable!(purchase_products -> units (unit_id));
+joinable!(purchases -> currencies (currency_id));
+joinable!(purchases -> suppliers (supplier_id));
+joinable!(sale_adjustments -> sales (sale_id));
+joinable!(sale_products -> prices (price_id));
+joinable!(sale_products -> pricing_rules (pricing_rule_id));
+joinable!(sale_products -> product_variants (variant_id));
 joinable!(sale_products -> products (product_id));
 joinable!(sale_products -> sales (sale_id));
+joinable!(sale_products -> units (unit_id));
+joinable!(sale_status_history -> sales (sale_id));
+joinable!(sale_taxes -> sales (sale_id));
+joinable!(sale_taxes -> taxes (tax_id));
 joinable!(sales -> clients (client_id));
 joinable!(sales -> currencies (currency_id));
+joinable!(sales -> numbering_series (series_id));
+joinable!(stock_movements -> product_variants (variant_id));
+joinable!(stock_movements -> products (product_id));
 
 allow_tables_to_appear_in_same_query!(
+    categories,
     clients,
     costs,
+    credit_note_products,
+    credit_notes,
     currencies,
+    exchange_rates,
+    numbering_series,
+    overdue_sweeps,
+    payments,
     prices,
+    pricing_rules,
+    product_barcodes,
+    product_categories,
     product_costs,
     product_prices,
+    product_taxes,
+    product_variants,
     products,
+    purchase_products,
+    purchases,
+    sale_adjustments,
     sale_products,
+    sale_status_history,
+    sale_taxes,
     sales,
+    stock_movements,
     suppliers,
     taxes,
+    units,
 );
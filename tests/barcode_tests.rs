@@ -0,0 +1,25 @@
+extern crate focusvent;
+
+use focusvent::models::product_barcode::ProductBarcode;
+
+#[test]
+fn accepts_valid_ean_13_and_upc_a() {
+    assert!(ProductBarcode::valid("4006381333931"));
+    assert!(ProductBarcode::valid("5901234123457"));
+    assert!(ProductBarcode::valid("036000291452"));
+}
+
+#[test]
+fn rejects_wrong_check_digits() {
+    assert!(!ProductBarcode::valid("4006381333932"));
+    assert!(!ProductBarcode::valid("036000291453"));
+}
+
+#[test]
+fn rejects_codes_that_are_not_barcodes() {
+    assert!(!ProductBarcode::valid("SOAP-2"));
+    assert!(!ProductBarcode::valid("40063813339"));
+    assert!(!ProductBarcode::valid("40063813339310"));
+    assert!(!ProductBarcode::valid("40063813339a1"));
+    assert!(!ProductBarcode::looks_like_barcode("SOAP-2"));
+}
@@ -0,0 +1,73 @@
+extern crate focusvent;
+extern crate diesel;
+extern crate rocket;
+extern crate serde;
+extern crate serde_json;
+
+use rocket::http::ContentType;
+use rocket::http::Status;
+use rocket::local::Client;
+
+use focusvent::models::money::Money;
+use focusvent::models::product::Product;
+use focusvent::models::sale::Sale;
+use focusvent::models::sale::FullSale;
+use focusvent::models::sale_status::SaleStatus;
+use focusvent::models::credit_note::CreditNote;
+use focusvent::models::client_balance::ClientBalance;
+
+use crate::sale_tests;
+
+fn credit(sale: &Sale, sale_product_id: i32, amount: f64, client: &Client) -> Status {
+    client
+        .post(format!("/sales/{}/credit_notes", sale.id))
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "credit_note": {{
+                "credit_note_date": "2018-12-04"
+            }},
+            "credit_note_products": [{{
+                "sale_product_id": {},
+                "amount": {}
+            }}]
+        }}"#, sale_product_id, amount))
+        .dispatch()
+        .status()
+}
+
+fn full_sale(sale: &Sale, client: &Client) -> FullSale {
+    let mut response = client.get(format!("/sales/{}", sale.id)).dispatch();
+    serde_json::from_str(&response.body_string().unwrap()).unwrap()
+}
+
+pub fn partial_return(product: &Product, client: &Client) {
+    let sale = sale_tests::create_sale(&product, client);
+    let sale_product_id = full_sale(&sale, client).sale_products[0].id;
+    assert_eq!(Status::Conflict, credit(&sale, sale_product_id, 1.0, client));
+
+    client
+        .put(format!("/sales/{}/save", sale.id))
+        .header(ContentType::JSON)
+        .dispatch();
+    assert_eq!(Some(-2.0), sale_tests::product_stock(product, client));
+
+    assert_eq!(Status::Ok, credit(&sale, sale_product_id, 1.0, client));
+    assert_eq!(Some(-1.0), sale_tests::product_stock(product, client));
+
+    let mut response = client.get(format!("/sales/{}/credit_notes", sale.id)).dispatch();
+    let credit_notes: Vec<CreditNote> = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(1, credit_notes.len());
+    assert_eq!(Money(560), credit_notes[0].total);
+
+    let mut response = client.get(format!("/clients/{}/balance", sale.client_id)).dispatch();
+    let balance: ClientBalance = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(Money(560), balance.owed);
+    assert_eq!(Money(560), balance.open_sales[0].credited);
+
+    assert_eq!(Status::UnprocessableEntity, credit(&sale, sale_product_id, 1.5, client));
+    assert_eq!(Status::UnprocessableEntity, credit(&sale, sale_product_id, -1.0, client));
+
+    assert_eq!(Status::Ok, credit(&sale, sale_product_id, 1.0, client));
+    assert_eq!(Some(0.0), sale_tests::product_stock(product, client));
+    assert_eq!(SaleStatus::Payed, full_sale(&sale, client).sale.status);
+}
@@ -2,25 +2,38 @@ extern crate focusvent;
 extern crate diesel;
 extern crate rocket;
 extern crate regex;
+extern crate chrono;
 
 mod product_tests;
 mod price_tests;
 mod sale_tests;
+mod purchase_tests;
+mod payment_tests;
+mod overdue_sweep_tests;
+mod credit_note_tests;
 
 #[cfg(test)]
 mod test {
     use diesel;
     use diesel::pg::PgConnection;
     use diesel::RunQueryDsl;
+    use diesel::QueryDsl;
+    use diesel::ExpressionMethods;
     use rocket;
     use rocket::local::Client;
     use focusvent::models::db_connection::*;
     use crate::product_tests;
     use crate::price_tests;
     use crate::sale_tests;
+    use crate::purchase_tests;
+    use crate::payment_tests;
+    use crate::overdue_sweep_tests;
+    use crate::credit_note_tests;
 
     fn rocket() -> rocket::Rocket {
-        rocket::ignite().mount("/", ::focusvent::routes::routes())
+        rocket::ignite()
+            .manage(init_pool())
+            .mount("/", ::focusvent::routes::routes())
     }
 
     #[test]
@@ -32,6 +45,8 @@ mod test {
         clear(&connection);
         price_tests::update(&client);
         clear(&connection);
+        price_tests::bulk_update(&client);
+        clear(&connection);
         product_tests::index(&client);
         clear(&connection);
         product_tests::index_search(&client);
@@ -40,10 +55,26 @@ mod test {
         clear(&connection);
         product_tests::update_price_and_cost(&client);
         clear(&connection);
+        product_tests::price_history(&client);
+        clear(&connection);
+        product_tests::margins_and_cost_plus_prices(&client);
+        clear(&connection);
+        product_tests::categories(&client);
+        clear(&connection);
+        product_tests::barcodes(&client);
+        clear(&connection);
         product_tests::show(&client);
         clear(&connection);
         sale_tests::failed_creating_sale_with_no_sale_products(&client);
 
+        clear(&connection);
+        let product = product_tests::create_product_with_price(&client);
+        sale_tests::failed_creating_sale_rolls_back(&product, &client);
+
+        clear(&connection);
+        let product = product_tests::create_product_with_price(&client);
+        sale_tests::failed_creating_sale_too_large(&product, &client);
+
         clear(&connection);
         let product = product_tests::create_product_with_price(&client);
         sale_tests::show(&product, &client);
@@ -52,10 +83,80 @@ mod test {
         let product1 = product_tests::create_product(&client);
         let product2 = product_tests::create_product_with_price(&client);
         sale_tests::index(&product1, &product2, &client);
+
+        clear(&connection);
+        let product = product_tests::create_product_with_price(&client);
+        sale_tests::save_and_cancel_move_stock(&product, &client);
+
+        clear(&connection);
+        let product = product_tests::create_product_with_price(&client);
+        sale_tests::status_history(&product, &client);
+
+        clear(&connection);
+        let product = product_tests::create_product_with_price(&client);
+        sale_tests::locked_after_saving(&product, &client);
+
+        clear(&connection);
+        let product = product_tests::create_product_with_price(&client);
+        sale_tests::numbering_series(&product, &client);
+
+        clear(&connection);
+        let product = product_tests::create_product_with_price(&client);
+        sale_tests::exchange_rates(&product, &client);
+
+        clear(&connection);
+        let product = product_tests::create_product_with_price(&client);
+        sale_tests::tax_breakdown(&product, &client);
+
+        clear(&connection);
+        let product1 = product_tests::create_product(&client);
+        let product2 = product_tests::create_product_with_price(&client);
+        sale_tests::adjustments(&product1, &product2, &client);
+
+        clear(&connection);
+        let product = product_tests::create_product(&client);
+        sale_tests::client_price_list(&product, &client);
+
+        clear(&connection);
+        let product1 = product_tests::create_product(&client);
+        let product2 = product_tests::create_product_with_price(&client);
+        sale_tests::pricing_rules(&product1, &product2, &client);
+
+        clear(&connection);
+        let product = product_tests::create_product(&client);
+        sale_tests::variants(&product, &client);
+
+        clear(&connection);
+        sale_tests::units(&client);
+
+        clear(&connection);
+        let product = product_tests::create_product_with_price(&client);
+        purchase_tests::receive_and_cancel(&product, &client);
+
+        clear(&connection);
+        let product = product_tests::create_product_with_price(&client);
+        purchase_tests::units(&product, &client);
+
+        clear(&connection);
+        let product = product_tests::create_product_with_price(&client);
+        purchase_tests::variants(&product, &client);
+
+        clear(&connection);
+        let product = product_tests::create_product_with_price(&client);
+        payment_tests::partial_payments(&product, &client);
+
+        clear(&connection);
+        let product = product_tests::create_product_with_price(&client);
+        overdue_sweep_tests::marks_expired_sales(&product, &client, &connection);
+
+        clear(&connection);
+        let product = product_tests::create_product_with_price(&client);
+        credit_note_tests::partial_return(&product, &client);
     }
 
     fn clear(connection: &PgConnection) {
         use focusvent::schema::currencies::dsl::*;
+        use focusvent::schema::clients::dsl::*;
         use focusvent::schema::prices::dsl::*;
         use focusvent::schema::product_prices::dsl::*;
         use focusvent::schema::product_costs::dsl::*;
@@ -64,15 +165,56 @@ mod test {
         use focusvent::schema::sale_products::dsl::*;
         use focusvent::schema::products::dsl::*;
         use focusvent::schema::sales::dsl::*;
+        use focusvent::schema::stock_movements::dsl::*;
+        use focusvent::schema::purchase_products::dsl::*;
+        use focusvent::schema::purchases::dsl::*;
+        use focusvent::schema::payments::dsl::*;
+        use focusvent::schema::overdue_sweeps::dsl::*;
+        use focusvent::schema::sale_status_history::dsl::*;
+        use focusvent::schema::credit_note_products::dsl::*;
+        use focusvent::schema::credit_notes::dsl::*;
+        use focusvent::schema::numbering_series::dsl::*;
+        use focusvent::schema::exchange_rates::dsl::*;
+        use focusvent::schema::sale_taxes::dsl::*;
+        use focusvent::schema::sale_adjustments::dsl::*;
+        use focusvent::schema::pricing_rules::dsl::*;
+        use focusvent::schema::product_categories::dsl::*;
+        use focusvent::schema::product_barcodes::dsl::*;
+        use focusvent::schema::categories::dsl::*;
+        use focusvent::schema::product_variants::dsl::*;
+        use focusvent::schema::units::dsl::*;
+        use focusvent::schema::product_taxes::dsl::*;
+        use focusvent::schema::taxes::dsl::*;
 
+        diesel::delete(stock_movements).execute(connection).unwrap();
         diesel::delete(product_costs).execute(connection).unwrap();
         diesel::delete(costs).execute(connection).unwrap();
+        diesel::delete(purchase_products).execute(connection).unwrap();
+        diesel::delete(purchases).execute(connection).unwrap();
         diesel::delete(suppliers).execute(connection).unwrap();
         diesel::delete(product_prices).execute(connection).unwrap();
+        diesel::delete(credit_note_products).execute(connection).unwrap();
+        diesel::delete(credit_notes).execute(connection).unwrap();
         diesel::delete(sale_products).execute(connection).unwrap();
+        diesel::delete(product_taxes).execute(connection).unwrap();
+        diesel::delete(pricing_rules).execute(connection).unwrap();
+        diesel::delete(product_categories).execute(connection).unwrap();
+        diesel::delete(product_barcodes).execute(connection).unwrap();
+        diesel::delete(product_variants).execute(connection).unwrap();
         diesel::delete(products).execute(connection).unwrap();
-        diesel::delete(prices).execute(connection).unwrap();
+        diesel::delete(categories).execute(connection).unwrap();
+        diesel::delete(units).execute(connection).unwrap();
+        diesel::delete(payments).execute(connection).unwrap();
+        diesel::delete(sale_status_history).execute(connection).unwrap();
+        diesel::delete(sale_taxes).execute(connection).unwrap();
+        diesel::delete(sale_adjustments).execute(connection).unwrap();
         diesel::delete(sales).execute(connection).unwrap();
+        diesel::delete(clients).execute(connection).unwrap();
+        diesel::delete(prices).execute(connection).unwrap();
+        diesel::delete(taxes).execute(connection).unwrap();
+        diesel::delete(numbering_series.filter(default_series.eq(false))).execute(connection).unwrap();
+        diesel::delete(exchange_rates).execute(connection).unwrap();
         diesel::delete(currencies).execute(connection).unwrap();
+        diesel::delete(overdue_sweeps).execute(connection).unwrap();
     }
 }
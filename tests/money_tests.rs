@@ -0,0 +1,242 @@
+extern crate focusvent;
+#[macro_use]
+extern crate quickcheck;
+
+use focusvent::models::money::{ Money, MoneyError, RoundingMode };
+use focusvent::models::item_calculation::ItemCalculation;
+use focusvent::models::calculation::{ Adjustment, Calculation };
+use focusvent::models::tax::Tax;
+
+fn items(lines: &[(i32, u16, u16, u16)], rounding: RoundingMode) -> Vec<ItemCalculation> {
+    lines
+        .iter()
+        .map(|&(price, amount, discount, tax)| {
+            ItemCalculation::new(&Money((tax % 10000) as i64),
+                                 &Money((discount % 10000) as i64),
+                                 &Money(price as i64),
+                                 amount as f64 / 1000.0)
+                .with_rounding(rounding)
+        })
+        .collect()
+}
+
+fn totals_match_lines(lines: Vec<(i32, u16, u16, u16)>, rounding: RoundingMode) -> bool {
+    let items = items(&lines, rounding);
+    let line_totals = items.iter().map(|item| item.calculate_total().unwrap()).collect::<Vec<Money>>();
+    let line_subtotals = items.iter().map(|item| item.subtotal().unwrap()).collect::<Vec<Money>>();
+    let calculation = Calculation::new(items);
+    let subtotal_without_discount = calculation.subtotal_without_discount().unwrap();
+
+    calculation.calculate_total() == Money::checked_sum(&line_totals) &&
+        calculation.subtotal() == Money::checked_sum(&line_subtotals) &&
+        subtotal_without_discount.checked_add(&calculation.calculate_taxes().unwrap()) == calculation.calculate_total()
+}
+
+quickcheck! {
+    fn calculation_total_is_the_sum_of_line_totals_half_up(lines: Vec<(i32, u16, u16, u16)>) -> bool {
+        totals_match_lines(lines, RoundingMode::HalfUp)
+    }
+
+    fn calculation_total_is_the_sum_of_line_totals_half_even(lines: Vec<(i32, u16, u16, u16)>) -> bool {
+        totals_match_lines(lines, RoundingMode::HalfEven)
+    }
+
+    fn formatting_and_parsing_round_trips(cents: i64) -> bool {
+        Money::parse(&Money(cents).to_string(), RoundingMode::HalfUp) == Ok(Money(cents))
+    }
+}
+
+#[test]
+fn parses_without_floats() {
+    assert_eq!(Ok(Money(1120)), Money::parse("11.2", RoundingMode::HalfUp));
+    assert_eq!(Ok(Money(-5)), Money::parse("-0.05", RoundingMode::HalfUp));
+    assert_eq!(Ok(Money(29)), Money::parse("0.29", RoundingMode::HalfUp));
+    assert_eq!(Ok(Money(92233720368547758)), Money::parse("922337203685477.58", RoundingMode::HalfUp));
+    assert_eq!(Err(MoneyError::Invalid("1,5".to_string())), Money::parse("1,5", RoundingMode::HalfUp));
+    assert_eq!(Err(MoneyError::Invalid(".".to_string())), Money::parse(".", RoundingMode::HalfUp));
+    assert_eq!(Err(MoneyError::Overflow), Money::parse("92233720368547758.08", RoundingMode::HalfUp));
+}
+
+#[test]
+fn rounds_ties_with_the_mode() {
+    assert_eq!(Ok(Money(11)), Money::parse("0.105", RoundingMode::HalfUp));
+    assert_eq!(Ok(Money(10)), Money::parse("0.105", RoundingMode::HalfEven));
+    assert_eq!(Ok(Money(12)), Money::parse("0.115", RoundingMode::HalfEven));
+    assert_eq!(Ok(Money(-11)), Money::parse("-0.105", RoundingMode::HalfUp));
+    assert_eq!(Ok(Money(11)), Money::parse("0.1051", RoundingMode::HalfEven));
+}
+
+#[test]
+fn line_calculation_rounds_once() {
+    let item = ItemCalculation::new(&Money(2100), &Money(0), &Money(333), 3.0)
+        .with_rounding(RoundingMode::HalfUp);
+    assert_eq!(Some(Money(999)), item.subtotal());
+    assert_eq!(Some(Money(210)), item.calculate_taxes());
+    assert_eq!(Some(Money(1209)), item.calculate_total());
+
+    let item = ItemCalculation::new(&Money(0), &Money(0), &Money(2999), 0.5)
+        .with_rounding(RoundingMode::HalfEven);
+    assert_eq!(Some(Money(1500)), item.subtotal());
+}
+
+#[test]
+fn overflow_is_checked() {
+    assert_eq!(None, Money(i64::max_value()).checked_add(&Money(1)));
+    assert_eq!(None, Money(i64::max_value()).times(2.0, RoundingMode::HalfUp));
+    assert_eq!(Some(Money(6_000_000_000)), Money(3_000_000_000).times(2.0, RoundingMode::HalfUp));
+    assert_eq!(None, Money(i64::max_value()).convert(2.0));
+    assert_eq!(None, Money::checked_sum(&[Money(i64::max_value()), Money(1)]));
+}
+
+#[test]
+fn calculations_report_overflow() {
+    let item = ItemCalculation::new(&Money(2100), &Money(0), &Money(i64::max_value()), 1.0);
+    assert_eq!(Some(Money(i64::max_value())), item.subtotal());
+    assert_eq!(None, item.calculate_total());
+
+    let calculation = Calculation::new(vec![
+        ItemCalculation::new(&Money(0), &Money(0), &Money(i64::max_value()), 1.0),
+        ItemCalculation::new(&Money(0), &Money(0), &Money(1), 1.0),
+    ]);
+    assert_eq!(None, calculation.subtotal());
+    assert_eq!(None, calculation.calculate_total());
+    assert!(calculation.with_adjustments(&[Adjustment::Percentage(Money(2000))]).is_none());
+}
+
+#[test]
+fn breaks_taxes_down_by_tax() {
+    let vat = Tax { id: 1, name: "VAT".to_string(), percentage: 2100, compound: false };
+    let internal = Tax { id: 2, name: "Internal".to_string(), percentage: 500, compound: false };
+    let calculation = Calculation::new(vec![
+        ItemCalculation::new(&Money(0), &Money(0), &Money(1000), 2.0)
+            .with_taxes(&[vat.clone()]),
+        ItemCalculation::new(&Money(0), &Money(1000), &Money(500), 1.0)
+            .with_taxes(&[vat, internal]),
+        ItemCalculation::new(&Money(1050), &Money(0), &Money(100), 1.0),
+    ]);
+
+    let breakdown = calculation.tax_breakdown().unwrap();
+    let summary = breakdown
+        .iter()
+        .map(|tax| (tax.tax_id, tax.name.as_str(), tax.base.clone(), tax.amount.clone()))
+        .collect::<Vec<(Option<i32>, &str, Money, Money)>>();
+
+    assert_eq!(vec![(Some(1), "VAT", Money(2450), Money(515)),
+                    (Some(2), "Internal", Money(450), Money(23)),
+                    (None, "10.5%", Money(100), Money(11))],
+               summary);
+    assert_eq!(calculation.calculate_taxes(),
+               Money::checked_sum(breakdown.iter().map(|tax| &tax.amount)));
+}
+
+fn vat() -> Tax {
+    Tax { id: 1, name: "VAT".to_string(), percentage: 2100, compound: true }
+}
+
+fn excise() -> Tax {
+    Tax { id: 3, name: "Excise".to_string(), percentage: 1000, compound: false }
+}
+
+#[test]
+fn compounds_taxes_in_order() {
+    let item = ItemCalculation::new(&Money(0), &Money(0), &Money(1000), 1.0)
+        .with_taxes(&[excise(), vat()])
+        .with_rounding(RoundingMode::HalfUp);
+
+    let amounts = item.tax_breakdown()
+        .unwrap()
+        .iter()
+        .map(|tax| (tax.base.clone(), tax.amount.clone()))
+        .collect::<Vec<(Money, Money)>>();
+    assert_eq!(vec![(Money(1000), Money(100)), (Money(1100), Money(231))], amounts);
+    assert_eq!(Some(Money(1331)), item.calculate_total());
+}
+
+#[test]
+fn takes_included_taxes_out_of_the_price() {
+    let item = ItemCalculation::new(&Money(0), &Money(0), &Money(1331), 1.0)
+        .with_taxes(&[excise(), vat()])
+        .with_tax_included(true)
+        .with_rounding(RoundingMode::HalfUp);
+    assert_eq!(Some(Money(1000)), item.subtotal());
+    assert_eq!(Some(Money(331)), item.calculate_taxes());
+    assert_eq!(Some(Money(1331)), item.calculate_total());
+
+    let item = ItemCalculation::new(&Money(0), &Money(0), &Money(999), 1.0)
+        .with_taxes(&[Tax { compound: false, ..vat() }])
+        .with_tax_included(true)
+        .with_rounding(RoundingMode::HalfUp);
+    assert_eq!(Some(Money(826)), item.subtotal());
+    assert_eq!(Some(Money(173)), item.calculate_taxes());
+    assert_eq!(Some(Money(999)), item.calculate_total());
+}
+
+#[test]
+fn combines_percentage_and_fixed_discounts() {
+    let item = ItemCalculation::new(&Money(2100), &Money(1000), &Money(1000), 2.0)
+        .with_discount_amount(&Money(300))
+        .with_rounding(RoundingMode::HalfUp);
+    assert_eq!(Some(Money(500)), item.calculate_discount());
+    assert_eq!(Some(Money(1500)), item.subtotal_without_discount());
+    assert_eq!(Some(Money(1815)), item.calculate_total());
+
+    let item = ItemCalculation::new(&Money(0), &Money(0), &Money(1000), 1.0)
+        .with_discount_amount(&Money(5000));
+    assert_eq!(Some(Money(0)), item.calculate_total());
+}
+
+quickcheck! {
+    fn included_taxes_keep_the_price_paid(price: u32, amount: u16, discount: u16, discount_amount: u16,
+                                          excise_percentage: u16, vat_percentage: u16) -> bool {
+        let item = ItemCalculation::new(&Money(0), &Money((discount % 10000) as i64), &Money(price as i64), amount as f64 / 1000.0)
+            .with_taxes(&[Tax { percentage: (excise_percentage % 10000) as i32, ..excise() },
+                          Tax { percentage: (vat_percentage % 10000) as i32, ..vat() }])
+            .with_discount_amount(&Money(discount_amount as i64))
+            .with_tax_included(true);
+        let exclusive = item.clone().with_tax_included(false);
+
+        let paid = exclusive.subtotal_without_discount();
+        let subtotal_without_discount = item.subtotal_without_discount().unwrap();
+        item.calculate_total() == paid &&
+            subtotal_without_discount.checked_add(&item.calculate_taxes().unwrap()) == paid &&
+            item.subtotal().unwrap().checked_sub(&item.calculate_discount().unwrap()) == Some(subtotal_without_discount)
+    }
+}
+
+#[test]
+fn apportions_sale_adjustments_by_line_base() {
+    let lines = vec![
+        ItemCalculation::new(&Money(1000), &Money(0), &Money(1000), 1.0),
+        ItemCalculation::new(&Money(1000), &Money(0), &Money(2000), 1.0),
+    ];
+    let calculation = Calculation::new(lines)
+        .with_adjustments(&[Adjustment::Amount(Money(100)),
+                            Adjustment::Percentage(Money(-1000))])
+        .unwrap();
+
+    assert_eq!(vec![Money(100), Money(-300)], calculation.adjustment_amounts());
+    assert_eq!(vec![Money(-67), Money(-133)], calculation.line_adjustments());
+    assert_eq!(Some(Money(-200)), calculation.calculate_adjustments());
+    assert_eq!(Some(Money(280)), calculation.calculate_taxes());
+    assert_eq!(Some(Money(3080)), calculation.calculate_total());
+}
+
+quickcheck! {
+    fn adjusted_total_is_the_sum_of_line_totals(lines: Vec<(i32, u16, u16, u16)>, amount: i32, percentage: i16) -> bool {
+        let calculation = Calculation::new(items(&lines, RoundingMode::HalfUp))
+            .with_adjustments(&[Adjustment::Amount(Money(amount as i64)),
+                                Adjustment::Percentage(Money(percentage as i64))])
+            .unwrap();
+        let line_adjustments = calculation.line_adjustments();
+        let adjusted_items = items(&lines, RoundingMode::HalfUp)
+            .into_iter()
+            .zip(line_adjustments.iter())
+            .map(|(item, adjustment)| item.with_adjustment(adjustment))
+            .collect::<Vec<ItemCalculation>>();
+        let line_totals = adjusted_items.iter().map(|item| item.calculate_total().unwrap()).collect::<Vec<Money>>();
+
+        lines.is_empty() ||
+            (calculation.calculate_total() == Money::checked_sum(&line_totals) &&
+             calculation.calculate_adjustments() == Money::checked_sum(&calculation.adjustment_amounts()))
+    }
+}
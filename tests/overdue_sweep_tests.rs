@@ -0,0 +1,58 @@
+extern crate focusvent;
+extern crate diesel;
+extern crate chrono;
+extern crate rocket;
+extern crate serde;
+extern crate serde_json;
+
+use chrono::NaiveDate;
+use diesel::ExpressionMethods;
+use diesel::QueryDsl;
+use diesel::RunQueryDsl;
+use diesel::pg::PgConnection;
+use rocket::http::ContentType;
+use rocket::local::Client;
+
+use focusvent::models::clock::FixedClock;
+use focusvent::models::overdue_sweep::OverdueSweep;
+use focusvent::models::product::Product;
+use focusvent::models::sale_status::SaleStatus;
+
+use crate::sale_tests;
+
+pub fn marks_expired_sales(product: &Product, client: &Client, connection: &PgConnection) {
+    use focusvent::schema::sales::dsl::*;
+
+    let saved_sale = sale_tests::create_sale(&product, client);
+    let active_sale = sale_tests::create_sale(&product, client);
+    let draft_sale = sale_tests::create_sale(&product, client);
+    client
+        .put(format!("/sales/{}/save", saved_sale.id))
+        .header(ContentType::JSON)
+        .dispatch();
+    client
+        .put(format!("/sales/{}/activate", active_sale.id))
+        .header(ContentType::JSON)
+        .dispatch();
+
+    diesel::update(sales.filter(id.eq_any(vec![saved_sale.id, active_sale.id, draft_sale.id])))
+        .set(expiring_date.eq(NaiveDate::from_ymd(2018, 12, 10)))
+        .execute(connection)
+        .unwrap();
+
+    let sweep = OverdueSweep::run(connection, &FixedClock(NaiveDate::from_ymd(2018, 12, 10))).unwrap();
+    assert!(sweep.sale_ids.is_empty());
+
+    let sweep = OverdueSweep::run(connection, &FixedClock(NaiveDate::from_ymd(2018, 12, 11))).unwrap();
+    assert_eq!(vec![saved_sale.id, active_sale.id], sweep.sale_ids);
+    assert_eq!(NaiveDate::from_ymd(2018, 12, 11), sweep.reference_date);
+
+    let saved_status = sales.find(saved_sale.id).select(status).first::<SaleStatus>(connection).unwrap();
+    assert_eq!(SaleStatus::Overdue, saved_status);
+
+    let active_status = sales.find(active_sale.id).select(status).first::<SaleStatus>(connection).unwrap();
+    assert_eq!(SaleStatus::Overdue, active_status);
+
+    let draft_status = sales.find(draft_sale.id).select(status).first::<SaleStatus>(connection).unwrap();
+    assert_eq!(SaleStatus::Draft, draft_status);
+}
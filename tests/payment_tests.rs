@@ -0,0 +1,69 @@
+extern crate focusvent;
+extern crate diesel;
+extern crate rocket;
+extern crate serde;
+extern crate serde_json;
+
+use rocket::http::ContentType;
+use rocket::http::Status;
+use rocket::local::Client;
+
+use focusvent::models::money::Money;
+use focusvent::models::product::Product;
+use focusvent::models::sale::Sale;
+use focusvent::models::sale::FullSale;
+use focusvent::models::sale_status::SaleStatus;
+use focusvent::models::client_balance::ClientBalance;
+
+use crate::sale_tests;
+
+fn pay(sale: &Sale, amount: &str, client: &Client) -> Status {
+    client
+        .post(format!("/sales/{}/payments", sale.id))
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "payment_date": "2018-12-03",
+            "amount": "{}",
+            "payment_method": "Cash"
+        }}"#, amount))
+        .dispatch()
+        .status()
+}
+
+fn sale_status(sale: &Sale, client: &Client) -> SaleStatus {
+    let mut response = client.get(format!("/sales/{}", sale.id)).dispatch();
+    let full_sale: FullSale = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    full_sale.sale.status
+}
+
+fn client_balance(sale: &Sale, client: &Client) -> ClientBalance {
+    let mut response = client.get(format!("/clients/{}/balance", sale.client_id)).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    serde_json::from_str(&response.body_string().unwrap()).unwrap()
+}
+
+pub fn partial_payments(product: &Product, client: &Client) {
+    let sale = sale_tests::create_sale(&product, client);
+    assert_eq!(Status::UnprocessableEntity, pay(&sale, "5.0", client));
+
+    client
+        .put(format!("/sales/{}/save", sale.id))
+        .header(ContentType::JSON)
+        .dispatch();
+
+    assert_eq!(Status::Ok, pay(&sale, "5.0", client));
+    assert_eq!(SaleStatus::Saved, sale_status(&sale, client));
+
+    let balance = client_balance(&sale, client);
+    assert_eq!(Money(620), balance.owed);
+    assert_eq!(1, balance.open_sales.len());
+    assert_eq!(Money(500), balance.open_sales[0].payed);
+
+    assert_eq!(Status::UnprocessableEntity, pay(&sale, "6.21", client));
+    assert_eq!(Status::Ok, pay(&sale, "6.2", client));
+    assert_eq!(SaleStatus::Payed, sale_status(&sale, client));
+
+    let balance = client_balance(&sale, client);
+    assert_eq!(Money(0), balance.owed);
+    assert!(balance.open_sales.is_empty());
+}
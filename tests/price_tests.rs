@@ -11,6 +11,10 @@ use rocket::http::Status;
 use rocket::local::Client;
 
 use focusvent::models::price::Price;
+use focusvent::models::price_update::PriceChange;
+use focusvent::models::product::Product;
+use focusvent::models::product::FullProduct;
+use focusvent::models::money::Money;
 
 fn create_price(client: &Client, name: String) -> Price {
     let mut response = client
@@ -36,7 +40,7 @@ pub fn update(client: &Client) {
         .dispatch();
     assert_eq!(response.status(), Status::Ok);
     let mut response = client.get(format!("/prices/{}", _price.id)).dispatch();
-    assert_eq!(Some(format!(r#"{{"id":{},"name":"Better"}}"#, _price.id)),
+    assert_eq!(Some(format!(r#"{{"id":{},"name":"Better","currency_id":null,"markup":null}}"#, _price.id)),
                response.body_string());
 }
 
@@ -53,7 +57,81 @@ pub fn index(client: &Client, connection: &PgConnection) {
     create_price(client, "Less".to_string());
     let mut response = client.get("/prices?offset=0&limit=10&search={\"name\": \"Cheap%\"}").dispatch();
     assert_eq!(response.status(), Status::Ok);
-    let string = format!(r#"[{{"id":{},"name":"Cheap"}},{{"id":{},"name":"Cheapest"}}]"#,
+    let string = format!(r#"[{{"id":{},"name":"Cheap","currency_id":null,"markup":null}},{{"id":{},"name":"Cheapest","currency_id":null,"markup":null}}]"#,
                         _price.id, price3.id);
     assert_eq!(Some(string), response.body_string());
 }
+
+fn create_product(client: &Client, name: &str, price_id: i32, price: i64) -> Product {
+    let mut response = client
+        .post("/products")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "product": {{
+                "name": "{}",
+                "description": "for school"
+            }},
+            "prices": [{{
+                "price_id": {},
+                "price": {}
+            }}],
+            "costs": []
+        }}"#, name, price_id, price))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    serde_json::from_str(&response.body_string().unwrap()).unwrap()
+}
+
+fn current_price(client: &Client, product: &Product) -> i64 {
+    let mut response = client.get(format!("/products/{}", product.id)).dispatch();
+    let full_product: FullProduct = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    full_product.prices[0].price
+}
+
+pub fn bulk_update(client: &Client) {
+    let _price = create_price(client, "Retail".to_string());
+    let pencil = create_product(client, "Pencil", _price.id, 1000);
+    let rubber = create_product(client, "Rubber", _price.id, 1999);
+
+    let mut response = client
+        .post(format!("/prices/{}/bulk_update", _price.id))
+        .header(ContentType::JSON)
+        .body(r#"{
+            "percentage": "10",
+            "round_to": "0.5",
+            "dry_run": true
+        }"#)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let price_changes: Vec<PriceChange> = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    let preview = price_changes
+        .iter()
+        .map(|price_change| (price_change.product_id, price_change.old_price.clone(), price_change.new_price.clone()))
+        .collect::<Vec<(i32, Money, Money)>>();
+    assert_eq!(vec![(pencil.id, Money(1000), Money(1100)),
+                    (rubber.id, Money(1999), Money(2200))],
+               preview);
+    assert_eq!(1000, current_price(client, &pencil));
+
+    let response = client
+        .post(format!("/prices/{}/bulk_update", _price.id))
+        .header(ContentType::JSON)
+        .body(r#"{
+            "amount": "-0.5",
+            "search": { "name": "Pencil" }
+        }"#)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(950, current_price(client, &pencil));
+    assert_eq!(1999, current_price(client, &rubber));
+
+    let response = client
+        .post(format!("/prices/{}/bulk_update", _price.id))
+        .header(ContentType::JSON)
+        .body(r#"{
+            "amount": "-15"
+        }"#)
+        .dispatch();
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    assert_eq!(1999, current_price(client, &rubber));
+}
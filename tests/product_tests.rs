@@ -10,8 +10,12 @@ use rocket::local::Client;
 
 use focusvent::models::product::Product;
 use focusvent::models::product::FullProduct;
+use focusvent::models::product_price::ProductPrice;
 use focusvent::models::cost::Cost;
 use focusvent::models::supplier::Supplier;
+use focusvent::models::money::Money;
+use focusvent::models::price::Price;
+use focusvent::models::category::Category;
 
 fn create_price(client: &Client) -> Cost {
     let mut response = client
@@ -148,7 +152,7 @@ pub fn update(client: &Client) {
         .dispatch();
     assert_eq!(response.status(), Status::Ok);
     let mut response = client.get(format!("/products/{}", product.id)).dispatch();
-    assert_eq!(Some(format!(r#"{{"product":{{"id":{},"name":"Shoes","description":"for the feet","stock":0.0,"code":null}},"prices":[],"costs":[]}}"#, product.id)),
+    assert_eq!(Some(format!(r#"{{"product":{{"id":{},"name":"Shoes","description":"for the feet","stock":0.0,"code":null,"unit_id":null}},"prices":[],"costs":[],"taxes":[],"categories":[],"variants":[],"unit":null,"barcodes":[]}}"#, product.id)),
                response.body_string());
 }
 
@@ -163,7 +167,7 @@ pub fn update_price_and_cost(client: &Client) {
         .prices
         .clone()
         .into_iter()
-        .map(|record| record.price ).collect::<Vec<i32>>();
+        .map(|record| record.price ).collect::<Vec<i64>>();
 
     assert_eq!(vec![2000, 1000], prices);
     let response = client
@@ -207,7 +211,7 @@ pub fn update_price_and_cost(client: &Client) {
         .dispatch();
     assert_eq!(response.status(), Status::Ok);
     let mut response = client.get(format!("/products/{}", product.id)).dispatch();
-    assert_eq!(format!(r#"{{"product":{{"id":{},"name":"Shoes","description":"for the feet","stock":0.0,"code":null}},"prices":[{{"price_id":{},"price":9876,"name":"Default"}},{{"price_id":{},"price":1234,"name":"Good"}}],"costs":[{{"cost_id":{},"supplier_id":{},"cost":9999,"name":"Cheap"}},{{"cost_id":{},"supplier_id":{},"cost":9384,"name":"Expensive"}}]}}"#,
+    assert_eq!(format!(r#"{{"product":{{"id":{},"name":"Shoes","description":"for the feet","stock":0.0,"code":null,"unit_id":null}},"prices":[{{"price_id":{},"price":9876,"formatted_price":"$ 98.76","name":"Default","margin":"-96.26","markup":"-49.05"}},{{"price_id":{},"price":1234,"formatted_price":"$ 12.34","name":"Good","margin":"-1470.75","markup":"-93.63"}}],"costs":[{{"cost_id":{},"supplier_id":{},"cost":9999,"name":"Cheap"}},{{"cost_id":{},"supplier_id":{},"cost":9384,"name":"Expensive"}}],"taxes":[],"categories":[],"variants":[],"unit":null,"barcodes":[]}}"#,
                        product.id,
                        full_product.prices[0].price_id, 
                        full_product.prices[1].price_id,
@@ -223,7 +227,7 @@ pub fn index(client: &Client) {
     let product2 = create_product_with_price(client);
     let mut response = client.get("/products?offset=0&limit=10").dispatch();
     assert_eq!(response.status(), Status::Ok);
-    let string = format!(r#"[{{"id":{},"name":"Shoe","description":"for the feet","stock":0.0,"code":null}},{{"id":{},"name":"Hat","description":"for the head","stock":0.0,"code":null}}]"#,
+    let string = format!(r#"[{{"id":{},"name":"Shoe","description":"for the feet","stock":0.0,"code":null,"unit_id":null}},{{"id":{},"name":"Hat","description":"for the head","stock":0.0,"code":null,"unit_id":null}}]"#,
                         product.id, product2.id);
     assert_eq!(Some(string), response.body_string());
 }
@@ -233,7 +237,7 @@ pub fn index_search(client: &Client) {
     create_product_with_price(client);
     let mut response = client.get("/products?offset=0&limit=10&search={\"name\": \"Shoe\"}").dispatch();
     assert_eq!(response.status(), Status::Ok);
-    let string = format!(r#"[{{"id":{},"name":"Shoe","description":"for the feet","stock":0.0,"code":null}}]"#,
+    let string = format!(r#"[{{"id":{},"name":"Shoe","description":"for the feet","stock":0.0,"code":null,"unit_id":null}}]"#,
                         product.id);
     assert_eq!(Some(string), response.body_string());
 }
@@ -250,3 +254,287 @@ pub fn show(client: &Client) {
     assert_eq!(2, full_product.costs.len());
 }
 
+
+pub fn price_history(client: &Client) {
+    let product = create_product_with_price(client);
+    let mut response = client.get(format!("/products/{}", product.id)).dispatch();
+    let full_product: FullProduct =
+        serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    let price_id = full_product.prices[0].price_id;
+
+    let response = client
+        .put(format!("/products/{}", product.id))
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "product": {{
+                "id": {},
+                "name": "Hat",
+                "description": "for the head"
+            }},
+            "prices": [
+                {{
+                    "price_id": {},
+                    "price": 1500,
+                    "valid_from": "2018-01-01"
+                }},
+                {{
+                    "price_id": {},
+                    "price": 2500,
+                    "valid_from": "2999-01-01"
+                }}
+            ],
+            "costs": []
+        }}"#, product.id, price_id, price_id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    let mut response = client.get(format!("/products/{}", product.id)).dispatch();
+    let full_product: FullProduct =
+        serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(1500, full_product.prices[0].price);
+
+    let mut response = client.get(format!("/products/{}/prices/history", product.id)).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let history: Vec<ProductPrice> = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    let versions = history
+        .iter()
+        .filter(|product_price| product_price.price_id == price_id)
+        .map(|product_price| (product_price.price,
+                              product_price.valid_from.map(|date| date.to_string()),
+                              product_price.valid_to.map(|date| date.to_string())))
+        .collect::<Vec<(i64, Option<String>, Option<String>)>>();
+    assert_eq!(vec![(2000, None, Some("2018-01-01".to_string())),
+                    (1500, Some("2018-01-01".to_string()), Some("2999-01-01".to_string())),
+                    (2500, Some("2999-01-01".to_string()), None)],
+               versions);
+}
+
+pub fn margins_and_cost_plus_prices(client: &Client) {
+    let supplier = create_supplier(client);
+    let extra_cost = create_cost(client);
+    let price = create_price(client);
+    let mut response = client
+        .post("/costs")
+        .header(ContentType::JSON)
+        .body(r#"{
+            "name": "Purchase",
+            "primary_cost": true
+        }"#)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let primary_cost: Cost = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    let mut response = client
+        .post("/prices")
+        .header(ContentType::JSON)
+        .body(r#"{
+            "name": "Cost plus",
+            "markup": "25"
+        }"#)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let cost_plus: Price = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(Some(Money(2500)), cost_plus.markup);
+
+    let product_body = |cost: i64| format!(r#"{{
+            "product": {{
+                "name": "Cap",
+                "description": "for the sun"
+            }},
+            "prices": [{{
+                "price_id": {},
+                "price": 1600
+            }}],
+            "costs": [{{
+                "cost_id": {},
+                "cost": {},
+                "supplier_id": {}
+            }}, {{
+                "cost_id": {},
+                "cost": 300,
+                "supplier_id": {}
+            }}]
+        }}"#, price.id, primary_cost.id, cost, supplier.id, extra_cost.id, supplier.id);
+
+    let mut response = client
+        .post("/products")
+        .header(ContentType::JSON)
+        .body(product_body(800))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let product: Product = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+
+    let full_product = |client: &Client| -> FullProduct {
+        let mut response = client.get(format!("/products/{}", product.id)).dispatch();
+        serde_json::from_str(&response.body_string().unwrap()).unwrap()
+    };
+    let prices = full_product(client).prices
+        .into_iter()
+        .map(|product_price| (product_price.price_id, product_price.price, product_price.margin, product_price.markup))
+        .collect::<Vec<(i32, i64, Option<Money>, Option<Money>)>>();
+    assert_eq!(vec![(cost_plus.id, 1000, Some(Money(2000)), Some(Money(2500))),
+                    (price.id, 1600, Some(Money(5000)), Some(Money(10000)))],
+               prices);
+
+    let response = client
+        .put(format!("/products/{}", product.id))
+        .header(ContentType::JSON)
+        .body(product_body(1000))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(1250, full_product(client).prices[0].price);
+}
+
+fn create_category(client: &Client, name: &str, parent_id: Option<i32>) -> Category {
+    let mut response = client
+        .post("/categories")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "name": "{}",
+            "parent_id": {}
+        }}"#, name, parent_id.map_or("null".to_string(), |parent_id| parent_id.to_string())))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    serde_json::from_str(&response.body_string().unwrap()).unwrap()
+}
+
+fn create_product_in(client: &Client, name: &str, category_ids: &[i32]) -> Product {
+    let mut response = client
+        .post("/products")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "product": {{
+                "name": "{}",
+                "description": "in the catalog"
+            }},
+            "prices": [],
+            "costs": [],
+            "categories": {:?}
+        }}"#, name, category_ids))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    serde_json::from_str(&response.body_string().unwrap()).unwrap()
+}
+
+pub fn categories(client: &Client) {
+    let clothing = create_category(client, "Clothing", None);
+    let hats = create_category(client, "Hats", Some(clothing.id));
+    let caps = create_category(client, "Caps", Some(hats.id));
+    let sports = create_category(client, "Sports", None);
+
+    let cap = create_product_in(client, "Cap", &[caps.id, sports.id]);
+    let scarf = create_product_in(client, "Scarf", &[clothing.id]);
+    create_product_in(client, "Ball", &[sports.id]);
+
+    let mut response = client.get(format!("/products/{}", cap.id)).dispatch();
+    let full_product: FullProduct = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(vec![caps.clone(), sports], full_product.categories);
+
+    let product_ids = |category: &Category| -> Vec<i32> {
+        let mut response = client
+            .get(format!("/products?offset=0&limit=10&search={{\"category_id\": {}}}", category.id))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let products: Vec<Product> = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+        products.iter().map(|product| product.id).collect()
+    };
+    assert_eq!(vec![cap.id], product_ids(&hats));
+    assert_eq!(vec![cap.id, scarf.id], product_ids(&clothing));
+    assert_eq!(vec![cap.id], product_ids(&caps));
+}
+
+fn post_product(client: &Client, name: &str, code: &str, barcodes: &[&str]) -> Status {
+    client
+        .post("/products")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "product": {{
+                "name": "{}",
+                "description": "at the checkout",
+                "code": "{}"
+            }},
+            "prices": [],
+            "costs": [],
+            "barcodes": {:?}
+        }}"#, name, code, barcodes))
+        .dispatch()
+        .status()
+}
+
+pub fn barcodes(client: &Client) {
+    let mut response = client
+        .post("/products")
+        .header(ContentType::JSON)
+        .body(r#"{
+            "product": {
+                "name": "Soap",
+                "description": "at the checkout",
+                "code": "4006381333931"
+            },
+            "prices": [],
+            "costs": [],
+            "barcodes": ["036000291452"]
+        }"#)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let soap: Product = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+
+    let response = client
+        .post("/product_variants")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "product_id": {},
+            "code": "5901234123457",
+            "size": "Travel"
+        }}"#, soap.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    for code in &["4006381333931", "036000291452", "5901234123457"] {
+        let mut response = client.get(format!("/products/by-code/{}", code)).dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let full_product: FullProduct = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+        assert_eq!(soap.id, full_product.product.id);
+        assert_eq!(vec!["036000291452".to_string()], full_product.barcodes);
+    }
+
+    let response = client.get("/products/by-code/400638133393").dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+
+    assert_eq!(Status::UnprocessableEntity, post_product(client, "Bad check digit", "4006381333932", &[]));
+    assert_eq!(Status::UnprocessableEntity, post_product(client, "Not a barcode", "SOAP-2", &["SOAP-2"]));
+    assert_eq!(Status::Conflict, post_product(client, "Same code", "SOAP-2", &["036000291452"]));
+    assert_eq!(Status::Conflict, post_product(client, "Variant code", "5901234123457", &[]));
+    assert_eq!(Status::Ok, post_product(client, "Internal code", "SOAP-2", &[]));
+    assert_eq!(Status::UnprocessableEntity, post_product(client, "Code twice", "4006381333931", &["4006381333931"]));
+    assert_eq!(Status::UnprocessableEntity, post_product(client, "Barcode twice", "SOAP-3", &["036000291452", "036000291452"]));
+
+    let post_variant = |code: &str| client
+        .post("/product_variants")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "product_id": {},
+            "code": "{}",
+            "size": "Family"
+        }}"#, soap.id, code))
+        .dispatch()
+        .status();
+    assert_eq!(Status::UnprocessableEntity, post_variant("5901234123458"));
+    assert_eq!(Status::Conflict, post_variant("SOAP-2"));
+    assert_eq!(Status::Conflict, post_variant("5901234123457"));
+
+    let response = client
+        .put(format!("/products/{}", soap.id))
+        .header(ContentType::JSON)
+        .body(r#"{
+            "product": {
+                "name": "Soap bar",
+                "description": "at the checkout",
+                "code": "4006381333931"
+            },
+            "prices": [],
+            "costs": [],
+            "barcodes": ["036000291452", "5901234123457"]
+        }"#)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+}
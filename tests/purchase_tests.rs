@@ -0,0 +1,311 @@
+extern crate focusvent;
+extern crate diesel;
+extern crate rocket;
+extern crate serde;
+extern crate serde_json;
+
+use rocket::http::ContentType;
+use rocket::http::Status;
+use rocket::local::Client;
+
+use focusvent::models::product::Product;
+use focusvent::models::product::FullProduct;
+use focusvent::models::purchase::Purchase;
+use focusvent::models::currency::Currency;
+use focusvent::models::unit::Unit;
+use focusvent::models::product_variant::ProductVariant;
+
+fn create_currency(client: &Client) -> Currency {
+    let mut response = client
+        .post("/currencies")
+        .header(ContentType::JSON)
+        .body(r#"{
+            "value": "Pesos ARS",
+            "symbol": "$",
+            "decimal_point": ",",
+            "thousands_separator": ".",
+            "default_currency": true,
+            "in_use": true
+        }"#)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    serde_json::from_str(&response.body_string().unwrap()).unwrap()
+}
+
+fn show_product(product: &Product, client: &Client) -> FullProduct {
+    let mut response = client.get(format!("/products/{}", product.id)).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    serde_json::from_str(&response.body_string().unwrap()).unwrap()
+}
+
+pub fn create_purchase(product: &Product, supplier_id: i32, client: &Client) -> Purchase {
+    let currency = create_currency(client);
+    let mut response = client
+        .post("/purchases")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "purchase": {{
+                "currency_id": {},
+                "supplier_id": {},
+                "purchase_date": "2018-11-08",
+                "update_costs": true
+            }},
+            "purchase_products": [{{
+                "product_id": {},
+                "tax": "21.0",
+                "amount": 3.0,
+                "price": "20.0"
+            }}]
+        }}"#, currency.id, supplier_id, product.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    serde_json::from_str(&response.body_string().unwrap()).unwrap()
+}
+
+pub fn receive_and_cancel(product: &Product, client: &Client) {
+    let full_product = show_product(product, client);
+    let supplier_id = full_product.costs[0].supplier_id;
+    let purchase = create_purchase(product, supplier_id, client);
+
+    let response = client
+        .put(format!("/purchases/{}/receive", purchase.id))
+        .header(ContentType::JSON)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    let full_product = show_product(product, client);
+    assert_eq!(Some(3.0), full_product.product.stock);
+    let costs = full_product
+        .costs
+        .into_iter()
+        .map(|record| record.cost).collect::<Vec<i32>>();
+    assert_eq!(vec![2000, 2000], costs);
+
+    let response = client
+        .put(format!("/purchases/{}", purchase.id))
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "purchase": {{
+                "currency_id": {},
+                "supplier_id": {},
+                "purchase_date": "2018-11-08"
+            }},
+            "purchase_products": [{{
+                "product_id": {},
+                "tax": "21.0",
+                "amount": 30.0,
+                "price": "20.0"
+            }}]
+        }}"#, purchase.currency_id, supplier_id, product.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Conflict);
+
+    let mut response = client
+        .delete(format!("/purchases/{}", purchase.id))
+        .header(ContentType::JSON)
+        .dispatch();
+    assert_eq!(response.status(), Status::Conflict);
+    assert!(response.body_string().unwrap().starts_with(
+        &format!(r#"{{"error":"locked","purchase_id":{},"status":"Received""#, purchase.id)));
+
+    let response = client
+        .put(format!("/purchases/{}/receive", purchase.id))
+        .header(ContentType::JSON)
+        .dispatch();
+    assert_eq!(response.status(), Status::InternalServerError);
+
+    let response = client
+        .put(format!("/purchases/{}/cancel", purchase.id))
+        .header(ContentType::JSON)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(Some(0.0), show_product(product, client).product.stock);
+}
+
+fn create_unit(client: &Client, body: String) -> Unit {
+    let mut response = client
+        .post("/units")
+        .header(ContentType::JSON)
+        .body(body)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    serde_json::from_str(&response.body_string().unwrap()).unwrap()
+}
+
+pub fn units(product: &Product, client: &Client) {
+    let full_product = show_product(product, client);
+    let unit = create_unit(client, r#"{ "name": "Unit", "symbol": "u" }"#.to_string());
+    let dozen = create_unit(client, format!(r#"{{
+            "name": "Dozen",
+            "symbol": "dz",
+            "base_unit_id": {},
+            "factor": 12.0
+        }}"#, unit.id));
+
+    let mut response = client
+        .post("/products")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "product": {{
+                "name": "Egg",
+                "description": "bought by the dozen",
+                "unit_id": {}
+            }},
+            "prices": [],
+            "costs": [{{
+                "cost_id": {},
+                "supplier_id": {},
+                "cost": 10
+            }}]
+        }}"#, unit.id, full_product.costs[0].cost_id, full_product.costs[0].supplier_id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let egg: Product = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+
+    let currency = create_currency(client);
+    let mut response = client
+        .post("/purchases")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "purchase": {{
+                "currency_id": {},
+                "supplier_id": {},
+                "purchase_date": "2018-12-28",
+                "update_costs": true
+            }},
+            "purchase_products": [{{
+                "product_id": {},
+                "tax": "0.0",
+                "amount": 2.0,
+                "price": "24.0",
+                "unit_id": {}
+            }}]
+        }}"#, currency.id, full_product.costs[0].supplier_id, egg.id, dozen.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let purchase: Purchase = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+
+    let response = client
+        .put(format!("/purchases/{}/receive", purchase.id))
+        .header(ContentType::JSON)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    let full_egg = show_product(&egg, client);
+    assert_eq!(Some(24.0), full_egg.product.stock);
+    assert_eq!(Some(unit), full_egg.unit);
+    assert_eq!(200, full_egg.costs[0].cost);
+
+    let response = client
+        .post("/units")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "name": "Nothing",
+            "symbol": "-",
+            "base_unit_id": {},
+            "factor": 0.0
+        }}"#, unit.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let kilogram = create_unit(client, r#"{ "name": "Kilogram", "symbol": "kg" }"#.to_string());
+    let response = client
+        .post("/purchases")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "purchase": {{
+                "currency_id": {},
+                "supplier_id": {},
+                "purchase_date": "2018-12-28"
+            }},
+            "purchase_products": [{{
+                "product_id": {},
+                "tax": "0.0",
+                "amount": 2.0,
+                "price": "24.0",
+                "unit_id": {}
+            }}]
+        }}"#, currency.id, full_product.costs[0].supplier_id, egg.id, kilogram.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let response = client
+        .put(format!("/products/{}", egg.id))
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "product": {{
+                "name": "Egg",
+                "description": "bought by the dozen",
+                "unit_id": {}
+            }},
+            "prices": [],
+            "costs": []
+        }}"#, dozen.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Conflict);
+
+    let response = client
+        .put(format!("/purchases/{}/cancel", purchase.id))
+        .header(ContentType::JSON)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(Some(0.0), show_product(&egg, client).product.stock);
+}
+
+pub fn variants(product: &Product, client: &Client) {
+    let full_product = show_product(product, client);
+    let supplier_id = full_product.costs[0].supplier_id;
+    let mut response = client
+        .post("/product_variants")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "product_id": {},
+            "code": "HAT-M",
+            "size": "M",
+            "color": "Black"
+        }}"#, product.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let variant: ProductVariant = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+
+    let currency = create_currency(client);
+    let purchase_body = |variant_id: i32| format!(r#"{{
+            "purchase": {{
+                "currency_id": {},
+                "supplier_id": {},
+                "purchase_date": "2019-01-08"
+            }},
+            "purchase_products": [{{
+                "product_id": {},
+                "variant_id": {},
+                "tax": "0.0",
+                "amount": 4.0,
+                "price": "20.0"
+            }}]
+        }}"#, currency.id, supplier_id, product.id, variant_id);
+
+    let response = client
+        .post("/purchases")
+        .header(ContentType::JSON)
+        .body(purchase_body(variant.id + 1000))
+        .dispatch();
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let mut response = client
+        .post("/purchases")
+        .header(ContentType::JSON)
+        .body(purchase_body(variant.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let purchase: Purchase = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+
+    let response = client
+        .put(format!("/purchases/{}/receive", purchase.id))
+        .header(ContentType::JSON)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    let full_product = show_product(product, client);
+    assert_eq!(Some(4.0), full_product.product.stock);
+    assert_eq!(vec![4.0], full_product.variants.iter().map(|variant| variant.stock).collect::<Vec<f64>>());
+}
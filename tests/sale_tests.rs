@@ -5,15 +5,26 @@ extern crate serde;
 extern crate serde_json;
 
 use rocket::http::ContentType;
+use rocket::http::Header;
 use rocket::http::Status;
 use rocket::local::Client;
 
 use focusvent::models::money::Money;
+use focusvent::models::db_connection::establish_connection;
 use focusvent::models::client;
 use focusvent::models::product::Product;
+use focusvent::models::product::FullProduct;
 use focusvent::models::sale::Sale;
 use focusvent::models::sale::FullSale;
 use focusvent::models::currency::Currency;
+use focusvent::models::sale_status::SaleStatus;
+use focusvent::models::sale_status_history::SaleStatusHistory;
+use focusvent::models::numbering_series::NumberingSeries;
+use focusvent::models::price::Price;
+use focusvent::models::tax::Tax;
+use focusvent::models::pricing_rule::PricingRule;
+use focusvent::models::product_variant::ProductVariant;
+use focusvent::models::unit::Unit;
 
 fn create_currency(client: &Client) -> Currency {
     let mut response = client
@@ -63,6 +74,62 @@ pub fn failed_creating_sale_with_no_sale_products(client: &Client) {
     assert_eq!(response.status(), Status::UnprocessableEntity);
 }
 
+pub fn failed_creating_sale_rolls_back(product: &Product, client: &Client) {
+    let currency = create_currency(client);
+    let db_client = create_client(client);
+    let response = client
+        .post("/sales")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "sale": {{
+                "currency_id": {},
+                "client_id": {},
+                "sale_date": "2018-12-01"
+            }},
+            "sale_products": [{{
+                "product_id": {},
+                "tax": "12.0",
+                "amount": 1.0,
+                "price": "5.0"
+            }}, {{
+                "product_id": {},
+                "tax": "12.0",
+                "amount": 1.0,
+                "price": "5.0"
+            }}]
+        }}"#, currency.id, db_client.id, product.id, product.id + 1000))
+        .dispatch();
+    assert_eq!(response.status(), Status::InternalServerError);
+
+    let mut response = client.get("/sales?offset=0&limit=10").dispatch();
+    let sales: Vec<Sale> = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert!(sales.is_empty());
+}
+
+pub fn failed_creating_sale_too_large(product: &Product, client: &Client) {
+    let currency = create_currency(client);
+    let db_client = create_client(client);
+    let mut response = client
+        .post("/sales")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "sale": {{
+                "currency_id": {},
+                "client_id": {},
+                "sale_date": "2018-12-01"
+            }},
+            "sale_products": [{{
+                "product_id": {},
+                "tax": "12.0",
+                "amount": 2.0,
+                "price": "92233720368547758.07"
+            }}]
+        }}"#, currency.id, db_client.id, product.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    assert_eq!(r#"{"error":"overflow"}"#, response.body_string().unwrap());
+}
+
 pub fn create_sale(product: &Product, client: &Client) -> Sale {
     let currency = create_currency(client);
     let db_client = create_client(client);
@@ -93,8 +160,9 @@ pub fn show(product: &Product, client: &Client) {
     assert_eq!(response.status(), Status::Ok);
 
     let full_sale: FullSale = serde_json::from_str(&response.body_string().unwrap()).unwrap();
-    assert_eq!("Jhon", &full_sale.sale.client().unwrap().first_name.unwrap());
+    assert_eq!("Jhon", &full_sale.sale.client(&establish_connection()).unwrap().first_name.unwrap());
     assert_eq!(Money(1120), full_sale.sale.total);
+    assert_eq!("$ 11,20", full_sale.formatted.total);
 }
 
 pub fn index(product: &Product, product2: &Product, client: &Client) {
@@ -104,6 +172,1012 @@ pub fn index(product: &Product, product2: &Product, client: &Client) {
     assert_eq!(response.status(), Status::Ok);
 
     let full_sale_list: Vec<Sale> = serde_json::from_str(&response.body_string().unwrap()).unwrap();
-    assert_eq!("Jhon", &full_sale_list.first().unwrap().client().unwrap().first_name.unwrap());
+    assert_eq!("Jhon", &full_sale_list.first().unwrap().client(&establish_connection()).unwrap().first_name.unwrap());
     assert_eq!(Money(1120), full_sale_list.first().unwrap().total);
 }
+
+pub fn product_stock(product: &Product, client: &Client) -> Option<f64> {
+    let mut response = client.get(format!("/products/{}", product.id)).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let full_product: FullProduct = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    full_product.product.stock
+}
+
+pub fn save_and_cancel_move_stock(product: &Product, client: &Client) {
+    let sale = create_sale(&product, client);
+    assert_eq!(Some(0.0), product_stock(product, client));
+
+    let response = client
+        .put(format!("/sales/{}/save", sale.id))
+        .header(ContentType::JSON)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(Some(-2.0), product_stock(product, client));
+
+    let response = client
+        .put(format!("/sales/{}/cancel", sale.id))
+        .header(ContentType::JSON)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(Some(0.0), product_stock(product, client));
+
+    let mut response = client
+        .put(format!("/products/{}/reconcile_stock", product.id))
+        .header(ContentType::JSON)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let reconciled: Product = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(Some(0.0), reconciled.stock);
+}
+
+pub fn status_history(product: &Product, client: &Client) {
+    let sale = create_sale(&product, client);
+
+    let response = client
+        .put(format!("/sales/{}/save", sale.id))
+        .header(ContentType::JSON)
+        .header(Header::new("X-User", "werner"))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    let response = client
+        .put(format!("/sales/{}/cancel", sale.id))
+        .header(ContentType::JSON)
+        .header(Header::new("X-User", "werner"))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    let mut response = client
+        .put(format!("/sales/{}/activate", sale.id))
+        .header(ContentType::JSON)
+        .dispatch();
+    assert_eq!(response.status(), Status::Conflict);
+    assert_eq!(Some(r#"{"error":"invalid_transition","from":"Cancelled","to":"Active"}"#.to_string()),
+               response.body_string());
+
+    let mut response = client.get(format!("/sales/{}/history", sale.id)).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let history: Vec<SaleStatusHistory> = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    let transitions = history
+        .iter()
+        .map(|record| (record.from_status, record.to_status))
+        .collect::<Vec<(SaleStatus, SaleStatus)>>();
+    assert_eq!(vec![(SaleStatus::Draft, SaleStatus::Saved),
+                    (SaleStatus::Saved, SaleStatus::Cancelled)], transitions);
+    assert!(history.iter().all(|record| record.changed_by == "werner"));
+}
+
+pub fn locked_after_saving(product: &Product, client: &Client) {
+    let sale = create_sale(&product, client);
+    let update_body = format!(r#"{{
+            "sale": {{
+                "currency_id": {},
+                "client_id": {},
+                "sale_date": "2018-12-05"
+            }},
+            "sale_products": [{{
+                "product_id": {},
+                "tax": "12.0",
+                "amount": 1.0,
+                "price": "5.0"
+            }}]
+        }}"#, sale.currency_id, sale.client_id, product.id);
+
+    let response = client
+        .put(format!("/sales/{}", sale.id))
+        .header(ContentType::JSON)
+        .body(update_body.clone())
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    client
+        .put(format!("/sales/{}/save", sale.id))
+        .header(ContentType::JSON)
+        .dispatch();
+
+    let response = client
+        .put(format!("/sales/{}", sale.id))
+        .header(ContentType::JSON)
+        .body(update_body)
+        .dispatch();
+    assert_eq!(response.status(), Status::Conflict);
+
+    let mut response = client
+        .delete(format!("/sales/{}", sale.id))
+        .header(ContentType::JSON)
+        .dispatch();
+    assert_eq!(response.status(), Status::Conflict);
+    assert!(response.body_string().unwrap().starts_with(
+        &format!(r#"{{"error":"locked","sale_id":{},"status":"Saved""#, sale.id)));
+
+    let draft_sale = create_sale(&product, client);
+    let response = client
+        .delete(format!("/sales/{}", draft_sale.id))
+        .header(ContentType::JSON)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+}
+
+fn create_sale_in_series(product: &Product, series: &NumberingSeries, client: &Client) -> Sale {
+    let sale = create_sale(&product, client);
+    let mut response = client
+        .put(format!("/sales/{}", sale.id))
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "sale": {{
+                "currency_id": {},
+                "client_id": {},
+                "sale_date": "2018-12-02",
+                "series_id": {}
+            }},
+            "sale_products": [{{
+                "product_id": {},
+                "tax": "12.0",
+                "amount": 2.0,
+                "price": "5.0"
+            }}]
+        }}"#, sale.currency_id, sale.client_id, series.id, product.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    serde_json::from_str(&response.body_string().unwrap()).unwrap()
+}
+
+fn save_sale(sale: &Sale, client: &Client) -> Sale {
+    let mut response = client
+        .put(format!("/sales/{}/save", sale.id))
+        .header(ContentType::JSON)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    serde_json::from_str(&response.body_string().unwrap()).unwrap()
+}
+
+pub fn numbering_series(product: &Product, client: &Client) {
+    let mut response = client
+        .post("/numbering_series")
+        .header(ContentType::JSON)
+        .body(r#"{
+            "name": "Store A",
+            "prefix": "A-",
+            "padding": 4
+        }"#)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let series: NumberingSeries = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+
+    let mut response = client
+        .put(format!("/numbering_series/{}", series.id))
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "id": {},
+            "name": "Store A",
+            "prefix": "A-",
+            "padding": 4,
+            "next_number": 50,
+            "default_series": false
+        }}"#, series.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let series: NumberingSeries = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(1, series.next_number);
+
+    let first_sale = create_sale_in_series(&product, &series, client);
+    let second_sale = create_sale_in_series(&product, &series, client);
+    assert_eq!(None, first_sale.number);
+
+    assert_eq!(Some("A-0001".to_string()), save_sale(&second_sale, client).number);
+    assert_eq!(Some("A-0002".to_string()), save_sale(&first_sale, client).number);
+
+    let mut response = client.get("/sales?offset=0&limit=10&search={\"number\": \"A-0001\"}").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let sales: Vec<Sale> = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(1, sales.len());
+    assert_eq!(second_sale.id, sales[0].id);
+
+    let default_sale = save_sale(&create_sale(&product, client), client);
+    assert!(default_sale.series_id.is_some());
+    assert!(default_sale.number.is_some());
+}
+
+fn create_foreign_currency(client: &Client, value: &str) -> Currency {
+    let mut response = client
+        .post("/currencies")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "value": "{}",
+            "symbol": "U$S",
+            "decimal_point": ".",
+            "thousands_separator": ",",
+            "default_currency": false,
+            "in_use": true
+        }}"#, value))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    serde_json::from_str(&response.body_string().unwrap()).unwrap()
+}
+
+pub fn exchange_rates(product: &Product, client: &Client) {
+    let currency = create_currency(client);
+    let dollars = create_foreign_currency(client, "Dollars");
+    let euros = create_foreign_currency(client, "Euros");
+
+    let mut response = client
+        .post("/prices")
+        .header(ContentType::JSON)
+        .body(r#"{ "name": "Local" }"#)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let price_list: Price = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+
+    let mut response = client
+        .post("/clients")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "first_name": "Jane",
+            "last_name": "Doe",
+            "price_id": {}
+        }}"#, price_list.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let db_client: client::Client = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+
+    let mut response = client
+        .post("/products")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "product": {{
+                "name": "Imported",
+                "description": "priced in pesos"
+            }},
+            "prices": [{{
+                "price_id": {},
+                "price": 4000
+            }}],
+            "costs": []
+        }}"#, price_list.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let listed_product: Product = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+
+    let response = client
+        .post("/exchange_rates")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "from_currency_id": {},
+            "to_currency_id": {},
+            "rate": 40.0,
+            "effective_date": "2018-11-01"
+        }}"#, dollars.id, currency.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    let sale_body = |currency_id: i32| format!(r#"{{
+            "sale": {{
+                "currency_id": {},
+                "client_id": {},
+                "sale_date": "2018-12-02"
+            }},
+            "sale_products": [{{
+                "product_id": {},
+                "tax": "0.0",
+                "amount": 1.0
+            }}, {{
+                "product_id": {},
+                "price_id": {},
+                "tax": "0.0",
+                "amount": 1.0,
+                "price": "1.5"
+            }}]
+        }}"#, currency_id, db_client.id, listed_product.id, product.id, price_list.id);
+
+    let mut response = client
+        .post("/sales")
+        .header(ContentType::JSON)
+        .body(sale_body(dollars.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let sale: Sale = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(0.025, sale.exchange_rate);
+    assert_eq!(Money(250), sale.total);
+
+    let mut response = client.get(format!("/sales/{}", sale.id)).dispatch();
+    let full_sale: FullSale = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(Money(100), full_sale.sale_products[0].price);
+    assert_eq!(0.025, full_sale.sale_products[0].exchange_rate);
+    assert_eq!(Money(150), full_sale.sale_products[1].price);
+    assert_eq!(1.0, full_sale.sale_products[1].exchange_rate);
+
+    let response = client
+        .post("/sales")
+        .header(ContentType::JSON)
+        .body(sale_body(euros.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+}
+
+fn create_tax(client: &Client, name: &str, percentage: i32) -> Tax {
+    let mut response = client
+        .post("/taxes")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{ "name": "{}", "percentage": {} }}"#, name, percentage))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    serde_json::from_str(&response.body_string().unwrap()).unwrap()
+}
+
+pub fn tax_breakdown(product: &Product, client: &Client) {
+    let currency = create_currency(client);
+    let db_client = create_client(client);
+    let vat = create_tax(client, "VAT", 2100);
+    let internal = create_tax(client, "Internal", 500);
+
+    let mut response = client
+        .post("/products")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "product": {{
+                "name": "Taxed",
+                "description": "with default taxes"
+            }},
+            "prices": [],
+            "costs": [],
+            "taxes": [{}]
+        }}"#, vat.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let taxed_product: Product = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+
+    let sale_body = |tax_id: i32| format!(r#"{{
+            "sale": {{
+                "currency_id": {},
+                "client_id": {},
+                "sale_date": "2018-12-02"
+            }},
+            "sale_products": [{{
+                "product_id": {},
+                "amount": 2.0,
+                "price": "10.0"
+            }}, {{
+                "product_id": {},
+                "tax_ids": [{}, {}],
+                "amount": 1.0,
+                "price": "5.0"
+            }}]
+        }}"#, currency.id, db_client.id, taxed_product.id, product.id, vat.id, tax_id);
+
+    let mut response = client
+        .post("/sales")
+        .header(ContentType::JSON)
+        .body(sale_body(internal.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let sale: Sale = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(Money(550), sale.taxes_calculated);
+    assert_eq!(Money(3050), sale.total);
+
+    let mut response = client.get(format!("/sales/{}", sale.id)).dispatch();
+    let full_sale: FullSale = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    let breakdown = full_sale.taxes
+        .iter()
+        .map(|tax| (tax.tax_id, tax.name.as_str(), tax.base.clone(), tax.amount.clone()))
+        .collect::<Vec<(Option<i32>, &str, Money, Money)>>();
+    assert_eq!(vec![(Some(vat.id), "VAT", Money(2500), Money(525)),
+                    (Some(internal.id), "Internal", Money(500), Money(25))],
+               breakdown);
+    assert_eq!(vec![vat.id], full_sale.sale_products[0].tax_ids);
+
+    let response = client
+        .post("/sales")
+        .header(ContentType::JSON)
+        .body(sale_body(internal.id + 1000))
+        .dispatch();
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+}
+
+pub fn adjustments(product: &Product, product2: &Product, client: &Client) {
+    let currency = create_currency(client);
+    let db_client = create_client(client);
+    let vat = create_tax(client, "VAT", 2100);
+
+    let sale_body = |adjustments: &str| format!(r#"{{
+            "sale": {{
+                "currency_id": {},
+                "client_id": {},
+                "sale_date": "2018-12-10"
+            }},
+            "sale_products": [{{
+                "product_id": {},
+                "amount": 2.0,
+                "price": "10.0"
+            }}, {{
+                "product_id": {},
+                "tax_ids": [{}],
+                "amount": 1.0,
+                "price": "5.0"
+            }}],
+            "adjustments": {}
+        }}"#, currency.id, db_client.id, product.id, product2.id, vat.id, adjustments);
+
+    let mut response = client
+        .post("/sales")
+        .header(ContentType::JSON)
+        .body(sale_body(r#"[{
+            "kind": "Discount",
+            "percentage": "10",
+            "description": "Loyalty"
+        }, {
+            "kind": "Shipping",
+            "amount": "5"
+        }]"#))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let sale: Sale = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(Money(2500), sale.sub_total);
+    assert_eq!(Money(250), sale.adjustments_calculated);
+    assert_eq!(Money(116), sale.taxes_calculated);
+    assert_eq!(Money(2866), sale.total);
+
+    let mut response = client.get(format!("/sales/{}", sale.id)).dispatch();
+    let full_sale: FullSale = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    let calculated = full_sale.adjustments
+        .iter()
+        .map(|adjustment| adjustment.calculated.clone())
+        .collect::<Vec<Money>>();
+    assert_eq!(vec![Money(-250), Money(500)], calculated);
+    let line_adjustments = full_sale.sale_products
+        .iter()
+        .map(|sale_product| sale_product.adjustment.clone())
+        .collect::<Vec<Money>>();
+    assert_eq!(vec![Money(200), Money(50)], line_adjustments);
+    assert_eq!(Money(550), full_sale.taxes[0].base);
+
+    let response = client
+        .post("/sales")
+        .header(ContentType::JSON)
+        .body(sale_body(r#"[{
+            "kind": "Surcharge",
+            "percentage": "5",
+            "amount": "5"
+        }]"#))
+        .dispatch();
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+}
+
+pub fn client_price_list(product: &Product, client: &Client) {
+    let currency = create_currency(client);
+    let mut response = client
+        .post("/prices")
+        .header(ContentType::JSON)
+        .body(r#"{ "name": "Wholesale" }"#)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let price_list: Price = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+
+    let mut response = client
+        .post("/clients")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "first_name": "Jane",
+            "last_name": "Doe",
+            "price_id": {}
+        }}"#, price_list.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let wholesale_client: client::Client = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    let retail_client = create_client(client);
+
+    let mut response = client
+        .post("/products")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "product": {{
+                "name": "Boxed",
+                "description": "sold by the box"
+            }},
+            "prices": [{{
+                "price_id": {},
+                "price": 1500,
+                "valid_from": "2018-12-01"
+            }}],
+            "costs": []
+        }}"#, price_list.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let listed_product: Product = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+
+    let sale_body = |client_id: i32, product_id: i32| format!(r#"{{
+            "sale": {{
+                "currency_id": {},
+                "client_id": {},
+                "sale_date": "2018-12-12"
+            }},
+            "sale_products": [{{
+                "product_id": {},
+                "amount": 2.0
+            }}]
+        }}"#, currency.id, client_id, product_id);
+
+    let mut response = client
+        .post("/sales")
+        .header(ContentType::JSON)
+        .body(sale_body(wholesale_client.id, listed_product.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let sale: Sale = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(Money(3000), sale.total);
+
+    let mut response = client.get(format!("/sales/{}", sale.id)).dispatch();
+    let full_sale: FullSale = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(Money(1500), full_sale.sale_products[0].price);
+    assert_eq!(Some(price_list.id), full_sale.sale_products[0].price_id);
+
+    let response = client
+        .post("/sales")
+        .header(ContentType::JSON)
+        .body(sale_body(wholesale_client.id, product.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let response = client
+        .post("/sales")
+        .header(ContentType::JSON)
+        .body(sale_body(retail_client.id, listed_product.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+}
+
+fn create_pricing_rule(client: &Client, body: String) -> PricingRule {
+    let mut response = client
+        .post("/pricing_rules")
+        .header(ContentType::JSON)
+        .body(body)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    serde_json::from_str(&response.body_string().unwrap()).unwrap()
+}
+
+pub fn pricing_rules(product: &Product, product2: &Product, client: &Client) {
+    let currency = create_currency(client);
+    let db_client = create_client(client);
+
+    let christmas = create_pricing_rule(client, r#"{
+        "name": "Christmas",
+        "kind": "PercentageDiscount",
+        "starts_on": "2018-12-01",
+        "ends_on": "2018-12-31",
+        "percentage": "10"
+    }"#.to_string());
+    create_pricing_rule(client, r#"{
+        "name": "Black Friday",
+        "kind": "PercentageDiscount",
+        "starts_on": "2018-11-23",
+        "ends_on": "2018-11-26",
+        "percentage": "50"
+    }"#.to_string());
+    let multi_buy = create_pricing_rule(client, format!(r#"{{
+        "name": "Buy 3 pay 2",
+        "kind": "MultiBuy",
+        "product_id": {},
+        "buy_amount": 3.0,
+        "pay_amount": 2.0
+    }}"#, product.id));
+    let volume_price = create_pricing_rule(client, format!(r#"{{
+        "name": "Wholesale",
+        "kind": "VolumePrice",
+        "product_id": {},
+        "min_amount": 50.0,
+        "price": "8.0"
+    }}"#, product2.id));
+
+    let mut response = client
+        .post("/sales")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "sale": {{
+                "currency_id": {},
+                "client_id": {},
+                "sale_date": "2018-12-14"
+            }},
+            "sale_products": [{{
+                "product_id": {},
+                "amount": 4.0,
+                "price": "10.0"
+            }}, {{
+                "product_id": {},
+                "amount": 60.0,
+                "price": "10.0"
+            }}]
+        }}"#, currency.id, db_client.id, product.id, product2.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let sale: Sale = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(Money(51000), sale.total);
+
+    let mut response = client.get(format!("/sales/{}", sale.id)).dispatch();
+    let full_sale: FullSale = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(Some(multi_buy.id), full_sale.sale_products[0].pricing_rule_id);
+    assert_eq!(Money(1000), full_sale.sale_products[0].discount_amount);
+    assert_eq!(Some(volume_price.id), full_sale.sale_products[1].pricing_rule_id);
+    assert_eq!(Money(800), full_sale.sale_products[1].price);
+
+    let response = client
+        .put(format!("/sales/{}", sale.id))
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "sale": {{
+                "currency_id": {},
+                "client_id": {},
+                "sale_date": "2018-12-14"
+            }},
+            "sale_products": [{{
+                "product_id": {},
+                "amount": 2.0,
+                "price": "10.0",
+                "discount_amount": "10.0",
+                "pricing_rule_id": {}
+            }}, {{
+                "product_id": {},
+                "amount": 60.0,
+                "price": "8.0",
+                "pricing_rule_id": {}
+            }}]
+        }}"#, currency.id, db_client.id, product.id, multi_buy.id, product2.id, volume_price.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    let mut response = client.get(format!("/sales/{}", sale.id)).dispatch();
+    let full_sale: FullSale = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(Some(christmas.id), full_sale.sale_products[0].pricing_rule_id);
+    assert_eq!(Money(0), full_sale.sale_products[0].discount_amount);
+    assert_eq!(Money(1000), full_sale.sale_products[0].discount);
+    assert_eq!(Some(volume_price.id), full_sale.sale_products[1].pricing_rule_id);
+
+    let small = create_variant(client, product, "PR-S", "S", "Blue");
+    let large = create_variant(client, product, "PR-L", "L", "Blue");
+    let mut response = client
+        .post("/sales")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "sale": {{
+                "currency_id": {},
+                "client_id": {},
+                "sale_date": "2018-12-14"
+            }},
+            "sale_products": [{{
+                "product_id": {},
+                "variant_id": {},
+                "amount": 2.0,
+                "price": "10.0"
+            }}, {{
+                "product_id": {},
+                "variant_id": {},
+                "amount": 1.0,
+                "price": "10.0"
+            }}]
+        }}"#, currency.id, db_client.id, product.id, small.id, product.id, large.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let sale: Sale = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(Money(2000), sale.total);
+
+    let mut response = client.get(format!("/sales/{}", sale.id)).dispatch();
+    let full_sale: FullSale = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    let discounts = full_sale.sale_products
+        .iter()
+        .map(|sale_product| (sale_product.pricing_rule_id, sale_product.discount_amount.clone()))
+        .collect::<Vec<(Option<i32>, Money)>>();
+    assert_eq!(vec![(Some(multi_buy.id), Money(667)), (Some(multi_buy.id), Money(333))], discounts);
+
+    let response = client
+        .post("/sales")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "sale": {{
+                "currency_id": {},
+                "client_id": {},
+                "sale_date": "2018-12-14"
+            }},
+            "sale_products": [{{
+                "product_id": {},
+                "variant_id": {},
+                "amount": 2.0,
+                "price": "10.0"
+            }}, {{
+                "product_id": {},
+                "variant_id": {},
+                "amount": 1.0,
+                "price": "10.0"
+            }}]
+        }}"#, currency.id, db_client.id, product.id, small.id, product.id, small.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+}
+
+fn create_variant(client: &Client, product: &Product, code: &str, size: &str, color: &str) -> ProductVariant {
+    let mut response = client
+        .post("/product_variants")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "product_id": {},
+            "code": "{}",
+            "size": "{}",
+            "color": "{}"
+        }}"#, product.id, code, size, color))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    serde_json::from_str(&response.body_string().unwrap()).unwrap()
+}
+
+pub fn variants(product: &Product, client: &Client) {
+    let currency = create_currency(client);
+    let mut response = client
+        .post("/prices")
+        .header(ContentType::JSON)
+        .body(r#"{ "name": "Retail" }"#)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let price_list: Price = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+
+    let mut response = client
+        .post("/clients")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "first_name": "Jane",
+            "last_name": "Doe",
+            "price_id": {}
+        }}"#, price_list.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let db_client: client::Client = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+
+    let mut response = client
+        .post("/products")
+        .header(ContentType::JSON)
+        .body(r#"{
+            "product": {
+                "name": "T-shirt",
+                "description": "in every size"
+            },
+            "prices": [],
+            "costs": []
+        }"#)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let shirt: Product = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    let small = create_variant(client, &shirt, "TS-S-RED", "S", "Red");
+    let large = create_variant(client, &shirt, "TS-L-RED", "L", "Red");
+
+    let response = client
+        .put(format!("/products/{}", shirt.id))
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "product": {{
+                "name": "T-shirt",
+                "description": "in every size"
+            }},
+            "prices": [
+                {{
+                    "price_id": {},
+                    "price": 1000,
+                    "valid_from": "2018-12-01"
+                }},
+                {{
+                    "price_id": {},
+                    "price": 1200,
+                    "valid_from": "2018-12-01",
+                    "variant_id": {}
+                }}
+            ],
+            "costs": []
+        }}"#, price_list.id, price_list.id, large.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    let mut response = client.get(format!("/products/{}", shirt.id)).dispatch();
+    let full_product: FullProduct = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(vec![1000], full_product.prices.iter().map(|price| price.price).collect::<Vec<i64>>());
+    assert_eq!(vec![small.clone(), large.clone()],
+               full_product.variants.iter().map(|variant| variant.variant.clone()).collect::<Vec<ProductVariant>>());
+    assert_eq!(1000, full_product.variants[0].prices[0].price);
+    assert_eq!(1200, full_product.variants[1].prices[0].price);
+
+    let mut response = client
+        .post("/sales")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "sale": {{
+                "currency_id": {},
+                "client_id": {},
+                "sale_date": "2018-12-26"
+            }},
+            "sale_products": [
+                {{
+                    "product_id": {},
+                    "variant_id": {},
+                    "amount": 1.0
+                }},
+                {{
+                    "product_id": {},
+                    "variant_id": {},
+                    "amount": 2.0
+                }}
+            ]
+        }}"#, currency.id, db_client.id, shirt.id, small.id, shirt.id, large.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let sale: Sale = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(Money(3400), sale.total);
+
+    let mut response = client.get(format!("/sales/{}", sale.id)).dispatch();
+    let full_sale: FullSale = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(2, full_sale.sale_products.len());
+
+    let response = client
+        .put(format!("/sales/{}/save", sale.id))
+        .header(ContentType::JSON)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    let mut response = client.get(format!("/products/{}", shirt.id)).dispatch();
+    let full_product: FullProduct = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(Some(-3.0), full_product.product.stock);
+    assert_eq!(vec![-1.0, -2.0], full_product.variants.iter().map(|variant| variant.stock).collect::<Vec<f64>>());
+
+    let response = client
+        .post("/sales")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "sale": {{
+                "currency_id": {},
+                "client_id": {},
+                "sale_date": "2018-12-26"
+            }},
+            "sale_products": [{{
+                "product_id": {},
+                "variant_id": {},
+                "amount": 1.0,
+                "price": 1000
+            }}]
+        }}"#, currency.id, db_client.id, product.id, small.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+}
+
+fn create_unit(client: &Client, body: String) -> Unit {
+    let mut response = client
+        .post("/units")
+        .header(ContentType::JSON)
+        .body(body)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    serde_json::from_str(&response.body_string().unwrap()).unwrap()
+}
+
+pub fn units(client: &Client) {
+    let currency = create_currency(client);
+    let kilogram = create_unit(client, r#"{ "name": "Kilogram", "symbol": "kg" }"#.to_string());
+    let gram = create_unit(client, format!(r#"{{
+            "name": "Gram",
+            "symbol": "g",
+            "base_unit_id": {},
+            "factor": 0.001
+        }}"#, kilogram.id));
+    let piece = create_unit(client, r#"{ "name": "Piece", "symbol": "pc" }"#.to_string());
+
+    let mut response = client
+        .post("/prices")
+        .header(ContentType::JSON)
+        .body(r#"{ "name": "By weight" }"#)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let price_list: Price = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+
+    let mut response = client
+        .post("/clients")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "first_name": "Jane",
+            "last_name": "Doe",
+            "price_id": {}
+        }}"#, price_list.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let db_client: client::Client = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+
+    let mut response = client
+        .post("/products")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "product": {{
+                "name": "Cheese",
+                "description": "sold by weight",
+                "unit_id": {}
+            }},
+            "prices": [{{
+                "price_id": {},
+                "price": 2000,
+                "valid_from": "2018-12-01"
+            }}],
+            "costs": []
+        }}"#, kilogram.id, price_list.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let cheese: Product = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+
+    let sale_body = |unit_id: i32| format!(r#"{{
+            "sale": {{
+                "currency_id": {},
+                "client_id": {},
+                "sale_date": "2018-12-28"
+            }},
+            "sale_products": [{{
+                "product_id": {},
+                "unit_id": {},
+                "amount": 250.0
+            }}]
+        }}"#, currency.id, db_client.id, cheese.id, unit_id);
+
+    let mut response = client
+        .post("/sales")
+        .header(ContentType::JSON)
+        .body(sale_body(gram.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let sale: Sale = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(Money(500), sale.total);
+
+    let response = client
+        .put(format!("/sales/{}/save", sale.id))
+        .header(ContentType::JSON)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(Some(-0.25), product_stock(&cheese, client));
+
+    let response = client
+        .post("/sales")
+        .header(ContentType::JSON)
+        .body(sale_body(piece.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let wholesale = create_pricing_rule(client, format!(r#"{{
+        "name": "Wholesale cheese",
+        "kind": "VolumePrice",
+        "product_id": {},
+        "min_amount": 1.0,
+        "price": "10.0"
+    }}"#, cheese.id));
+
+    let mut response = client
+        .post("/sales")
+        .header(ContentType::JSON)
+        .body(sale_body(gram.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let sale: Sale = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(Money(500), sale.total);
+
+    let mut response = client
+        .post("/sales")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{
+            "sale": {{
+                "currency_id": {},
+                "client_id": {},
+                "sale_date": "2018-12-28"
+            }},
+            "sale_products": [{{
+                "product_id": {},
+                "unit_id": {},
+                "amount": 2000.0
+            }}]
+        }}"#, currency.id, db_client.id, cheese.id, gram.id))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let sale: Sale = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(Money(2000), sale.total);
+
+    let mut response = client.get(format!("/sales/{}", sale.id)).dispatch();
+    let full_sale: FullSale = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+    assert_eq!(Some(wholesale.id), full_sale.sale_products[0].pricing_rule_id);
+    assert_eq!(Money(1), full_sale.sale_products[0].price);
+}